@@ -1,15 +1,639 @@
 #![no_std]
 
+/// Emitted once a `connect()` syscall returns, pairing the `sys_enter_connect`
+/// destination info with the `sys_exit_connect` return code and measured
+/// latency. `timestamp` is the *enter*-side timestamp (when the connect
+/// attempt started), not the exit time, so consumers can still order events
+/// by when the connection was initiated.
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct ConnectionEvent {
     pub pid: u32,
     pub cgroup_id: u64,
     pub timestamp: u64,
-    pub dest_addr: u32,
+    /// Destination address, big-endian. For `AF_INET` only the first 4
+    /// bytes are populated (the rest are zeroed); for `AF_INET6` all 16
+    /// bytes hold the `sin6_addr`. Check `address_family` to know which.
+    pub dest_addr: [u8; 16],
     pub dest_port: u16,
     pub address_family: u16,
+    /// Nanoseconds between `sys_enter_connect` and `sys_exit_connect` for
+    /// this call.
+    pub latency_ns: u64,
+    /// The syscall's raw return value: 0 on immediate success, a negative
+    /// errno otherwise (including `-EINPROGRESS` - see `in_progress`).
+    pub ret_code: i32,
+    /// Set when `ret_code` is `-EINPROGRESS`: the non-blocking socket's
+    /// connect attempt hadn't completed by the time `connect()` returned, so
+    /// `ret_code` isn't the final outcome and `latency_ns` only measures the
+    /// syscall call itself, not the underlying handshake.
+    pub in_progress: u8,
 }
 
 #[cfg(feature = "user")]
-unsafe impl aya::Pod for ConnectionEvent {}
\ No newline at end of file
+unsafe impl aya::Pod for ConnectionEvent {}
+
+/// Enter-time state for an in-flight `connect()` syscall, staged by
+/// `sys_enter_connect` so `sys_exit_connect` can compute latency and attach
+/// the destination info the exit tracepoint doesn't itself carry.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ConnectStart {
+    pub timestamp: u64,
+    /// See [`ConnectionEvent::dest_addr`] for the `AF_INET` vs `AF_INET6`
+    /// layout.
+    pub dest_addr: [u8; 16],
+    pub dest_port: u16,
+    pub address_family: u16,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for ConnectStart {}
+
+/// Shared event header embedded at the front of every builtin probe's
+/// ring-buffer event. Filled in on the eBPF side by
+/// `HoneyBeeEvent::init_base()` from the current task's pid, cgroup id, and
+/// a `bpf_ktime_get_ns()` timestamp.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct EventMetadata {
+    pub pid: u32,
+    pub cgroup_id: u64,
+    pub timestamp: u64,
+    /// Spare slot `init_base()` never touches. `llm/helpers.rs` repurposes it
+    /// to stash the current tid for logging, since LLM events key their
+    /// connection reassembly on `ssl_handle` rather than pid/tid.
+    pub _pad: u32,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for EventMetadata {}
+
+/// Truncation length for [`ExecEvent::filename`]; the exec'd binary's path
+/// as reported by the `sched_process_exec` tracepoint's `__data_loc`
+/// filename field, capped so the event stays a fixed size.
+pub const EXEC_FILENAME_LEN: usize = 256;
+
+/// Emitted by the exec-watch tracepoint whenever a process calls `exec()`,
+/// so userspace can decide whether to attach SSL probes to it without
+/// racing a short-lived process by stat-ing `/proc/<pid>/exe` after the
+/// fact.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ExecEvent {
+    pub pid: u32,
+    pub comm: [u8; 16],
+    pub filename: [u8; EXEC_FILENAME_LEN],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for ExecEvent {}
+
+/// Emitted by the process-lifecycle tracepoints (`sched_process_exec` and
+/// `sched_process_exit`) so the Kubernetes pod resolver can eagerly prime or
+/// evict its cgroup cache instead of relying on a lazy `/proc` read plus a
+/// periodic sweep. `pid` is the kernel's own per-task id (what userspace
+/// calls the thread id); `tgid` is the thread group id - the process id
+/// userspace tools report - since many threads in the same process (and
+/// thus the same cgroup) can exit independently.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ProcessEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub cgroup_id: u64,
+    pub comm: [u8; 16],
+    pub is_exit: u8,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for ProcessEvent {}
+
+/// Per-(pid, gpu_index) GPU memory accounting delta, emitted whenever an
+/// allocation or free ioctl is observed on an fd already tracked in
+/// `GPU_FD_MAP`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct GpuMemEvent {
+    pub metadata: EventMetadata,
+    pub gpu_index: i32,
+    /// Signed change in allocated bytes for this event (negative for frees).
+    pub delta_bytes: i64,
+    /// Running total of bytes attributed to this (pid, gpu_index) pair.
+    pub total_bytes: u64,
+    pub comm: [u8; 16],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for GpuMemEvent {}
+
+/// GPU device vendor/index tracked per open fd, keyed by `(pid << 32) | fd`
+/// in `GPU_FD_MAP`. Populated when a GPU device file is opened, consulted by
+/// the close and ioctl probes to recover which GPU an fd belongs to without
+/// re-parsing the path.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct GpuFdInfo {
+    pub gpu_index: i32,
+    /// `GpuVendor as u8` (`honeybeepf-ebpf`'s `gpu::utils` module).
+    pub vendor: u8,
+    pub _pad: [u8; 3],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for GpuFdInfo {}
+
+/// Enter-time state for an in-flight GPU device `openat()`, staged by
+/// `sys_enter_openat` so `sys_exit_openat` can recover the real fd (only
+/// known once the syscall returns) and finish populating `GpuOpenEvent`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PendingGpuOpen {
+    pub gpu_index: i32,
+    pub vendor: u8,
+    pub flags: i32,
+    pub filename: [u8; 64],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for PendingGpuOpen {}
+
+/// Emitted once a GPU device `openat()` resolves to a real fd.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct GpuOpenEvent {
+    pub metadata: EventMetadata,
+    pub gpu_index: i32,
+    pub vendor: u8,
+    pub fd: i32,
+    pub flags: i32,
+    pub comm: [u8; 16],
+    pub filename: [u8; 64],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for GpuOpenEvent {}
+
+/// Emitted when a tracked GPU fd (see [`GpuFdInfo`]) is closed.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct GpuCloseEvent {
+    pub metadata: EventMetadata,
+    pub gpu_index: i32,
+    pub fd: i32,
+    pub comm: [u8; 16],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for GpuCloseEvent {}
+
+/// Per-(cgroup, pid) GPU busy-time accumulator key, shared between the
+/// `GPU_BUSY_NS` eBPF map and the userspace poller that drains it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct GpuAccKey {
+    pub cgroup_id: u64,
+    pub pid: u32,
+    pub _pad: u32,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for GpuAccKey {}
+
+/// NCCL collective/point-to-point operation kind, tagging [`NcclEvent`] and
+/// keying [`PendingNcclOp`]'s entry/exit correlation.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NcclOpType {
+    AllReduce = 0,
+    Broadcast = 1,
+    AllGather = 2,
+    ReduceScatter = 3,
+    AllToAll = 4,
+    Send = 5,
+    Recv = 6,
+    GroupStart = 7,
+    GroupEnd = 8,
+    CommInitRank = 9,
+    CommAbort = 10,
+    GetAsyncError = 11,
+    GetVersion = 12,
+    /// Any value that doesn't match a known op - shouldn't happen in
+    /// practice since every eBPF-side emitter sets one of the above, but
+    /// keeps userspace's `From<u8>` total rather than panicking.
+    Unknown = 255,
+}
+
+impl From<u8> for NcclOpType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::AllReduce,
+            1 => Self::Broadcast,
+            2 => Self::AllGather,
+            3 => Self::ReduceScatter,
+            4 => Self::AllToAll,
+            5 => Self::Send,
+            6 => Self::Recv,
+            7 => Self::GroupStart,
+            8 => Self::GroupEnd,
+            9 => Self::CommInitRank,
+            10 => Self::CommAbort,
+            11 => Self::GetAsyncError,
+            12 => Self::GetVersion,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Enter-time state for an in-flight collective/point-to-point NCCL call,
+/// staged by its entry uprobe so the matching exit uretprobe can compute
+/// duration and pair it with the count/datatype/comm/stream info only the
+/// entry side has arguments for.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PendingNcclOp {
+    pub op_type: u8,
+    pub _pad: [u8; 3],
+    pub count: u64,
+    pub datatype_size: u32,
+    pub _pad2: u32,
+    pub comm_ptr: u64,
+    pub stream_ptr: u64,
+    pub group_id: u64,
+    pub start_ns: u64,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for PendingNcclOp {}
+
+/// Emitted once a collective/point-to-point NCCL call returns.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct NcclEvent {
+    pub metadata: EventMetadata,
+    pub op_type: u8,
+    pub ret_code: i32,
+    pub count: u64,
+    pub datatype_size: u32,
+    pub duration_ns: u64,
+    pub bytes_transferred: u64,
+    pub comm_ptr: u64,
+    pub stream_ptr: u64,
+    pub group_id: u64,
+    pub comm: [u8; 16],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for NcclEvent {}
+
+/// Truncation length for [`FileAccessEvent::filename`] and
+/// [`VfsLatencyEvent::filename`], matching `EXEC_FILENAME_LEN`'s rationale.
+pub const MAX_FILENAME_LEN: usize = 256;
+
+/// Longest suffix `file_access.rs`'s `check_suffix_match` will try hashing
+/// against `WATCHED_SUFFIXES` - bounds the countdown loop for the verifier.
+pub const MAX_SUFFIX_LEN: usize = 16;
+
+/// Emitted by the file-access tracepoints (`openat`/`open`/`openat2`/`creat`)
+/// when a watched path, prefix, or suffix matches. `OpenEvent`/`CreatEvent`/
+/// `OpenAt2Event` (`honeybeepf-ebpf`) share this wire layout via
+/// `#[repr(transparent)]`, differing only in which syscall's argument
+/// offsets fill it.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FileAccessEvent {
+    pub metadata: EventMetadata,
+    pub flags: u32,
+    pub mode: u32,
+    pub tid: u32,
+    pub comm: [u8; 16],
+    pub filename: [u8; MAX_FILENAME_LEN],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for FileAccessEvent {}
+
+/// VFS operation kind, tagging [`VfsLatencyEvent::op_type`] and keying
+/// `vfs_latency.rs`'s per-operation `VFS_OP_CONFIG`/`VFS_HIST` entries.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum VfsOpType {
+    Read = 0,
+    Write = 1,
+}
+
+impl From<u8> for VfsOpType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Write,
+            _ => Self::Read,
+        }
+    }
+}
+
+/// Emitted by `vfs_read`/`vfs_write` (kprobe or fentry/fexit, whichever
+/// `VfsLatencyProbe` attached) when an operation clears its configured
+/// latency/byte threshold.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct VfsLatencyEvent {
+    pub metadata: EventMetadata,
+    pub tid: u32,
+    pub op_type: u8,
+    pub latency_ns: u64,
+    pub bytes: u64,
+    pub offset: u64,
+    pub comm: [u8; 16],
+    pub filename: [u8; MAX_FILENAME_LEN],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for VfsLatencyEvent {}
+
+/// Block I/O start/issue vs. done/complete tag, tagging
+/// [`BlockIoEvent::event_type`]. `Start` tags the raw, uncorrelated event
+/// emitted when `BLOCK_IO_START` is full and a start/issue can't wait for
+/// its matching done/complete; `Done` tags a correlated done/complete.
+/// `Unknown` is the placeholder `BlockIoEvent::fill` writes before the
+/// start/done wrappers overwrite it.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BlockIoEventType {
+    Start = 0,
+    Done = 1,
+    Unknown = 2,
+}
+
+/// Emitted by the block I/O start/issue and done/complete tracepoints
+/// (`block_io_start`/`block_io_done`, or the `block_rq_issue`/
+/// `block_rq_complete` fallback). `latency_ns` is only meaningful once a
+/// done/complete has been correlated against its matching start/issue via
+/// `BLOCK_IO_START`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct BlockIoEvent {
+    pub metadata: EventMetadata,
+    pub dev: u32,
+    pub sector: u64,
+    pub nr_sector: u32,
+    pub bytes: u32,
+    pub rwbs: [u8; 8],
+    pub comm: [u8; 16],
+    pub event_type: u8,
+    pub latency_ns: u64,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for BlockIoEvent {}
+
+/// Longest SSL read/write payload `LlmEvent::buf` captures before
+/// truncation - large enough for typical LLM API request/response chunks
+/// without blowing up the ring buffer's per-event size.
+pub const MAX_SSL_BUF_SIZE: usize = 16384;
+
+/// Direction (or handshake) an [`LlmEvent`] was captured on, tagging
+/// `LlmEvent::rw`.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LlmDirection {
+    Read = 0,
+    Write = 1,
+    Handshake = 2,
+}
+
+impl From<u8> for LlmDirection {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Write,
+            2 => Self::Handshake,
+            _ => Self::Read,
+        }
+    }
+}
+
+/// Emitted by the SSL/TLS uprobes (`SSL_read`/`SSL_write`/`*_ex`/
+/// `SSL_do_handshake`) with the decrypted payload, so userspace can inspect
+/// LLM API request/response traffic without its own TLS termination.
+/// Connection reassembly keys on `ssl_handle` rather than pid/tid, since a
+/// single thread can multiplex many SSL connections.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct LlmEvent {
+    pub metadata: EventMetadata,
+    pub ssl_handle: u64,
+    pub rw: u8,
+    pub is_handshake: u8,
+    pub latency_ns: u64,
+    pub len: u32,
+    pub buf: [u8; MAX_SSL_BUF_SIZE],
+    pub buf_filled: u8,
+    pub comm: [u8; 16],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for LlmEvent {}
+
+/// Enter-time state for an in-flight `ncclCommInitRank` call, staged by the
+/// entry uprobe so the exit uretprobe can dereference the resolved
+/// `ncclComm_t` out-parameter and pair it with the rank/world-size/unique-id
+/// info the entry side already captured.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PendingNcclCommInit {
+    pub comm_out_ptr: u64,
+    pub nranks: i32,
+    pub rank: i32,
+    pub unique_id_hash: u64,
+    pub start_ns: u64,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for PendingNcclCommInit {}
+
+/// Emitted once `ncclCommInitRank` returns: the resolved communicator
+/// pointer plus the rank/world-size/unique-id identifying which
+/// communication group it belongs to, so userspace can build a
+/// `comm_ptr -> CommInfo` registry and annotate later collective events
+/// with rank/world-size instead of just `pid`/`comm`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct NcclCommEvent {
+    pub metadata: EventMetadata,
+    pub comm_ptr: u64,
+    pub nranks: i32,
+    pub rank: i32,
+    pub unique_id_hash: u64,
+    pub ret_code: i32,
+    pub duration_ns: u64,
+    pub comm: [u8; 16],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for NcclCommEvent {}
+
+/// Enter-time state for an in-flight `ncclCommGetAsyncError` call, staged by
+/// the entry uprobe so the exit uretprobe can dereference the resolved
+/// `ncclResult_t* asyncError` out-parameter, which is only populated once
+/// the call returns.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PendingNcclAsyncError {
+    pub comm_ptr: u64,
+    pub async_err_ptr: u64,
+    pub start_ns: u64,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for PendingNcclAsyncError {}
+
+/// Per-cgroup running totals accumulated directly in an eBPF exit handler,
+/// as a cheaper alternative to streaming one ring-buffer record per call.
+/// Read-and-cleared periodically by a userspace flush task (see
+/// `spawn_agg_flush_task` in `honeybeepf::probes`), which also rolls each
+/// cgroup's totals up through its ancestors via `/sys/fs/cgroup` so a parent
+/// cgroup's bucket reflects the sum of its descendants', mirroring the
+/// kernel's own rstat accounting.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct CgroupAggBucket {
+    pub count: u64,
+    pub sum_latency_ns: u64,
+    pub max_latency_ns: u64,
+    pub sum_bytes: u64,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for CgroupAggBucket {}
+
+/// Emitted by the runqueue-latency tracepoints when a task's time spent
+/// runnable-but-not-running (from `sched_wakeup`/`sched_wakeup_new` to the
+/// `sched_switch` that runs it) exceeds the configured threshold.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RunqueueEvent {
+    pub pid: u32,
+    pub tid: u32,
+    pub cpu: u32,
+    /// pid of the task switched out to make room for `pid`, or 0 if
+    /// unavailable (e.g. the CPU was idle beforehand).
+    pub prev_pid: u32,
+    pub latency_ns: u64,
+    pub cgroup_id: u64,
+    pub comm: [u8; 16],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for RunqueueEvent {}
+
+/// Number of log2 latency buckets in a histogram-mode probe's bucket array:
+/// bucket `i` counts latencies in `[2^i, 2^(i+1))` ns, with the last bucket
+/// acting as a catch-all for anything at or above `2^(HIST_NUM_BUCKETS - 1)`
+/// ns, so the array stays a fixed size regardless of how slow an outlier
+/// gets.
+pub const HIST_NUM_BUCKETS: usize = 27;
+
+/// Maps a latency in nanoseconds to its log2 bucket index - the position of
+/// its highest set bit, clamped to `HIST_NUM_BUCKETS - 1`. Shared between
+/// the eBPF side (incrementing a bucket on every call) and userspace
+/// (labeling a bucket's upper bound when exporting), so the two can never
+/// disagree on where a given latency falls.
+#[inline]
+pub fn latency_bucket(latency_ns: u64) -> usize {
+    if latency_ns == 0 {
+        return 0;
+    }
+    let highest_bit = 63 - latency_ns.leading_zeros() as usize;
+    highest_bit.min(HIST_NUM_BUCKETS - 1)
+}
+
+/// Upper bound (`le`, in nanoseconds) of a given bucket index, for labeling
+/// exported histogram series as cumulative `le`-bucketed counters. The last
+/// bucket has no finite upper bound, since it also catches every latency at
+/// or above it.
+pub fn bucket_upper_bound_ns(bucket: usize) -> u64 {
+    if bucket + 1 >= HIST_NUM_BUCKETS {
+        u64::MAX
+    } else {
+        1u64 << (bucket + 1)
+    }
+}
+
+/// Live builtin-probe configuration, pushed into the single-entry `CONFIG`
+/// eBPF array map so probes can consult enable flags/thresholds without a
+/// restart. Userspace rebuilds this from [`Settings`](../../honeybeepf/src/settings.rs)
+/// on every reload tick and diffs it against the last-applied value before
+/// writing.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CommonConfig {
+    pub probe_tcp_connect: u8,
+    pub probe_tcp_retrans: u8,
+    pub probe_dns: u8,
+    pub probe_vfs_latency: u8,
+    pub probe_file_access: u8,
+    pub probe_runqueue: u8,
+    pub probe_offcpu: u8,
+    pub probe_llm: u8,
+    pub probe_interval: u32,
+    pub _pad: [u8; 0],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for CommonConfig {}
+
+/// Byte offsets of the `struct file`/`struct inode` fields the VFS probes
+/// walk to classify regular files and read filenames, resolved once from the
+/// running kernel's BTF at attach time (see `resolve_vfs_field_offsets` in
+/// `honeybeepf::probes::builtin::filesystem::vfs_latency`) instead of the
+/// hardcoded, kernel-version-dependent constants those probes fall back to
+/// when BTF resolution isn't available.
+///
+/// `f_path_dentry_offset` is pre-folded (`f_path` offset + `dentry` offset
+/// within `struct path`) since the probes only ever need `file->f_path.dentry`
+/// as a single hop, not the intermediate `struct path` on its own.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct VfsFieldOffsets {
+    /// `file->f_inode`.
+    pub f_inode_offset: u16,
+    /// `file->f_path.dentry`.
+    pub f_path_dentry_offset: u16,
+    /// `dentry->d_name`.
+    pub d_name_offset: u16,
+    /// `qstr->name` (i.e. `d_name.name`).
+    pub qstr_name_offset: u16,
+    /// `inode->i_mode`.
+    pub i_mode_offset: u16,
+    /// 0 until userspace has successfully resolved every offset above from
+    /// BTF; the eBPF side checks this before trusting the rest of the
+    /// struct and otherwise uses its own compiled-in constants.
+    pub configured: u8,
+    pub _pad: [u8; 1],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for VfsFieldOffsets {}
+
+/// Per-operation (read vs write) tunables for `vfs_latency`'s exit handlers,
+/// keyed by op type (`VFS_OP_READ`/`VFS_OP_WRITE`) in the `VFS_OP_CONFIG` map
+/// so reads and writes can be tuned independently at runtime instead of
+/// sharing one latency-only threshold - e.g. a lower latency floor for
+/// writes while raising the read byte floor, or enabling byte-based
+/// filtering for writes too.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct VfsOpConfig {
+    /// Emit an event when observed latency is at or above this, in
+    /// nanoseconds.
+    pub latency_ns_threshold: u64,
+    /// Below `latency_ns_threshold`, also emit when bytes transferred is at
+    /// or above this. 0 disables byte-based filtering for this operation.
+    pub min_bytes_threshold: u64,
+    /// Whether this operation should only be reported for regular files
+    /// (not sockets/pipes/devices).
+    pub regular_files_only: u8,
+    pub _pad: [u8; 7],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for VfsOpConfig {}
\ No newline at end of file
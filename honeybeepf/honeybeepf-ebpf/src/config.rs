@@ -0,0 +1,20 @@
+//! Single-entry config map backing hot-reload: userspace seeds index 0 at
+//! attach time and atomically overwrites it on every applied config change
+//! (see `honeybeepf::reload::ConfigReloader`); probes that want to react to
+//! a live-toggled flag or threshold read it here instead of relying on a
+//! value baked in at load time.
+
+use aya_ebpf::{macros::map, maps::Array};
+use honeybeepf_common::CommonConfig;
+
+const CONFIG_KEY: u32 = 0;
+
+#[map]
+pub static CONFIG: Array<CommonConfig> = Array::with_max_entries(1, 0);
+
+/// Reads the live config, falling back to `default` if userspace hasn't
+/// populated the map yet (e.g. very early during attach).
+#[inline(always)]
+pub fn current_config(default: CommonConfig) -> CommonConfig {
+    unsafe { CONFIG.get(CONFIG_KEY).copied().unwrap_or(default) }
+}
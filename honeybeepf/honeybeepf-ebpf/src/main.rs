@@ -1,16 +1,28 @@
 #![no_std]
 #![no_main]
 
+mod config;
+mod probes;
+
 use aya_ebpf::{
     macros::{map, tracepoint},
-    maps::PerfEventArray,
+    maps::{HashMap, PerfEventArray},
     programs::TracePointContext,
     helpers::{bpf_get_current_pid_tgid, bpf_ktime_get_ns, bpf_get_current_cgroup_id},
 };
 use aya_log_ebpf::info;
-use honeybeepf_common::ConnectionEvent;
+use honeybeepf_common::{ConnectStart, ConnectionEvent};
 
 const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+
+/// Negated `EINPROGRESS` (Linux errno 115), the return value a non-blocking
+/// socket's `connect()` gives when the handshake hasn't completed yet.
+const NEG_EINPROGRESS: i64 = -115;
+
+/// Caps the number of connect() calls that can be in flight (started but not
+/// yet returned) at once, across all tasks.
+const MAX_PENDING_CONNECTS: u32 = 10240;
 
 #[repr(C)]
 struct SockaddrIn {
@@ -20,26 +32,49 @@ struct SockaddrIn {
     sin_zero: [u8; 8],
 }
 
+#[repr(C)]
+struct SockaddrIn6 {
+    sin6_family: u16,
+    sin6_port: u16,
+    sin6_flowinfo: u32,
+    sin6_addr: [u8; 16],
+    sin6_scope_id: u32,
+}
+
 #[map]
 static EVENTS: PerfEventArray<ConnectionEvent> = PerfEventArray::new(0);
 
+/// Enter-time state for an in-flight `connect()`, keyed on the full pid+tid
+/// pair (`bpf_get_current_pid_tgid()`) so `sys_exit_connect` can correlate
+/// the matching exit and compute latency.
+#[map]
+static CONNECT_START: HashMap<u64, ConnectStart> =
+    HashMap::with_max_entries(MAX_PENDING_CONNECTS, 0);
+
 #[tracepoint]
-pub fn honeybeepf(ctx: TracePointContext) -> u32 {
-    match try_connect_trace(ctx) {
+pub fn connect_enter(ctx: TracePointContext) -> u32 {
+    match try_connect_enter(ctx) {
         Ok(()) => 0,
         Err(ret) => ret,
     }
 }
 
-fn try_connect_trace(ctx: TracePointContext) -> Result<(), u32> {
-    let pid = (bpf_get_current_pid_tgid() >> 32) as u32;
-    let cgroup_id = unsafe { bpf_get_current_cgroup_id() };
-    let timestamp = unsafe { bpf_ktime_get_ns() };
+#[tracepoint]
+pub fn connect_exit(ctx: TracePointContext) -> u32 {
+    match try_connect_exit(ctx) {
+        Ok(()) => 0,
+        Err(ret) => ret,
+    }
+}
 
-    let sockaddr_ptr: u64 = unsafe {
-        ctx.read_at(24).map_err(|_| 1u32)?
-    };
+fn try_connect_enter(ctx: TracePointContext) -> Result<(), u32> {
+    let pid_tgid = bpf_get_current_pid_tgid();
+    let timestamp = unsafe { bpf_ktime_get_ns() };
 
+    // sys_enter_connect's args: args[0] (fd) at offset 16, args[1]
+    // (sockaddr*) at offset 24, args[2] (addrlen) at offset 32 - per
+    // `/sys/kernel/debug/tracing/events/syscalls/sys_enter_connect/format`.
+    let sockaddr_ptr: u64 = unsafe { ctx.read_at(24).map_err(|_| 1u32)? };
     if sockaddr_ptr == 0 {
         return Err(1);
     }
@@ -49,11 +84,9 @@ fn try_connect_trace(ctx: TracePointContext) -> Result<(), u32> {
             .map_err(|_| 1u32)?
     };
 
-    let mut event = ConnectionEvent {
-        pid,
-        cgroup_id,
+    let mut start = ConnectStart {
         timestamp,
-        dest_addr: 0,
+        dest_addr: [0; 16],
         dest_port: 0,
         address_family: sa_family,
     };
@@ -63,18 +96,73 @@ fn try_connect_trace(ctx: TracePointContext) -> Result<(), u32> {
             aya_ebpf::helpers::bpf_probe_read_user(sockaddr_ptr as *const SockaddrIn)
                 .map_err(|_| 1u32)?
         };
+        start.dest_port = sockaddr.sin_port;
+        start.dest_addr[..4].copy_from_slice(&sockaddr.sin_addr.to_ne_bytes());
+    } else if sa_family == AF_INET6 {
+        let sockaddr: SockaddrIn6 = unsafe {
+            aya_ebpf::helpers::bpf_probe_read_user(sockaddr_ptr as *const SockaddrIn6)
+                .map_err(|_| 1u32)?
+        };
+        start.dest_port = sockaddr.sin6_port;
+        start.dest_addr = sockaddr.sin6_addr;
+    }
 
-        event.dest_port = sockaddr.sin_port;
-        event.dest_addr = sockaddr.sin_addr;
+    let _ = CONNECT_START.insert(&pid_tgid, &start, 0);
 
-        info!(
-            &ctx,
-            "Connection from PID {}: dest={}:{}", 
-            pid,
-            u32::from_be(sockaddr.sin_addr),
-            u16::from_be(sockaddr.sin_port)
-        );
-    }
+    Ok(())
+}
+
+fn try_connect_exit(ctx: TracePointContext) -> Result<(), u32> {
+    let pid_tgid = bpf_get_current_pid_tgid();
+
+    // No matching enter entry - probe attached mid-syscall, the sockaddr
+    // was NULL or unreadable at enter, or CONNECT_START was full. Drop
+    // silently rather than emit a half-populated event.
+    let Some(start) = (unsafe { CONNECT_START.get(&pid_tgid) }) else {
+        return Ok(());
+    };
+    let start = *start;
+    let _ = CONNECT_START.remove(&pid_tgid);
+
+    // sys_exit_connect's `ret` field sits at offset 16, right after the
+    // common syscall tracepoint header - per
+    // `/sys/kernel/debug/tracing/events/syscalls/sys_exit_connect/format`.
+    let ret: i64 = unsafe { ctx.read_at(16).map_err(|_| 1u32)? };
+
+    let now = unsafe { bpf_ktime_get_ns() };
+    let pid = (pid_tgid >> 32) as u32;
+    let cgroup_id = unsafe { bpf_get_current_cgroup_id() };
+
+    let event = ConnectionEvent {
+        pid,
+        cgroup_id,
+        timestamp: start.timestamp,
+        dest_addr: start.dest_addr,
+        dest_port: start.dest_port,
+        address_family: start.address_family,
+        latency_ns: now.saturating_sub(start.timestamp),
+        ret_code: ret as i32,
+        in_progress: (ret == NEG_EINPROGRESS) as u8,
+    };
+
+    // Only the v4-in-first-4-bytes case is cheap to log inline here; full
+    // v6 formatting happens userspace-side where `std::net::Ipv6Addr` is
+    // available (see `NetworkLatencyProbe`).
+    let dest_addr_v4 = u32::from_ne_bytes([
+        event.dest_addr[0],
+        event.dest_addr[1],
+        event.dest_addr[2],
+        event.dest_addr[3],
+    ]);
+    info!(
+        &ctx,
+        "Connection from PID {}: dest={}:{} family={} ret={}",
+        pid,
+        u32::from_be(dest_addr_v4),
+        u16::from_be(event.dest_port),
+        event.address_family,
+        event.ret_code,
+    );
 
     EVENTS.output(&ctx, &event, 0);
 
@@ -1,7 +1,8 @@
 use aya_ebpf::{
     EbpfContext,
+    helpers::bpf_ktime_get_ns,
     macros::{map, tracepoint},
-    maps::RingBuf,
+    maps::{HashMap, RingBuf},
     programs::TracePointContext,
 };
 use aya_log_ebpf::info;
@@ -11,34 +12,145 @@ use crate::probes::{emit_event, HoneyBeeEvent};
 
 const MAX_EVENT_SIZE: u32 = 1024 * 1024;
 
+/// Max in-flight block I/O requests tracked for start/done correlation at
+/// once.
+const MAX_INFLIGHT_IO: u32 = 10240;
+
+/// Default threshold in nanoseconds (1ms) - only requests at or above this
+/// latency get an enriched, correlated event.
+const DEFAULT_THRESHOLD_NS: u64 = 1_000_000;
+
 #[map]
 pub static BLOCK_IO_EVENTS: RingBuf = RingBuf::with_byte_size(MAX_EVENT_SIZE, 0);
 
+/// Correlates a start/issue tracepoint with its matching done/complete one.
+/// Key: `(dev as u64) << 32 | sector`. Value: the issue-time timestamp from
+/// `bpf_ktime_get_ns()`.
+#[map]
+static BLOCK_IO_START: HashMap<u64, u64> = HashMap::with_max_entries(MAX_INFLIGHT_IO, 0);
+
+/// Configurable latency threshold in nanoseconds, the single-value-map style
+/// `vfs_latency.rs`'s threshold map used before it moved to per-operation
+/// `VFS_OP_CONFIG` entries.
+#[map]
+pub static BLOCK_IO_THRESHOLD_NS: HashMap<u32, u64> = HashMap::with_max_entries(1, 0);
+
 #[tracepoint]
 pub fn honeybeepf_block_io_start(ctx: TracePointContext) -> u32 {
-    info!(&ctx, "[eBPF] block_io_start tracepoint triggered");
-    emit_event::<TracePointContext, BlockIoStart>(&BLOCK_IO_EVENTS, &ctx)
+    try_stage_block_io_start(&ctx)
 }
 
 #[tracepoint]
 pub fn honeybeepf_block_io_done(ctx: TracePointContext) -> u32 {
-    info!(&ctx, "[eBPF] block_io_done tracepoint triggered");
-    emit_event::<TracePointContext, BlockIoDone>(&BLOCK_IO_EVENTS, &ctx)
+    try_emit_block_io_done(&ctx)
 }
 
 #[tracepoint]
 pub fn honeybeepf_block_rq_issue(ctx: TracePointContext) -> u32 {
-    info!(&ctx, "[eBPF] block_rq_issue tracepoint triggered (fallback)");
-    emit_event::<TracePointContext, BlockIoStart>(&BLOCK_IO_EVENTS, &ctx)
+    try_stage_block_io_start(&ctx)
 }
 
 #[tracepoint]
 pub fn honeybeepf_block_rq_complete(ctx: TracePointContext) -> u32 {
-    info!(&ctx, "[eBPF] block_rq_complete tracepoint triggered (fallback)");
-    emit_event::<TracePointContext, BlockIoDone>(&BLOCK_IO_EVENTS, &ctx)
+    try_emit_block_io_done(&ctx)
+}
+
+/// Computes `BLOCK_IO_START`'s key for a given `(dev, sector)` pair.
+#[inline(always)]
+fn block_io_key(dev: u32, sector: u64) -> u64 {
+    ((dev as u64) << 32) | sector
+}
+
+/// Stages a start/issue tracepoint's timestamp into `BLOCK_IO_START`, keyed
+/// by `(dev, sector)`, so the matching done/complete tracepoint can compute
+/// latency. A second issue for the same `(dev, sector)` (sector reuse)
+/// simply overwrites the prior entry.
+fn try_stage_block_io_start(ctx: &TracePointContext) -> u32 {
+    info!(ctx, "[eBPF] block_io start/issue tracepoint triggered");
+
+    let Ok(trace) = read_block_io_trace(ctx) else {
+        return 0;
+    };
+
+    let key = block_io_key(trace.dev, trace.sector);
+    let start_ts = unsafe { bpf_ktime_get_ns() };
+
+    if BLOCK_IO_START.insert(&key, &start_ts, 0).is_err() {
+        // BLOCK_IO_START is full - we won't get another chance to report
+        // this request, so fall back to the raw (uncorrelated) start event
+        // instead of losing visibility into it entirely.
+        info!(ctx, "[eBPF] BLOCK_IO_START full, emitting raw start event");
+        return emit_event::<TracePointContext, BlockIoStart>(&BLOCK_IO_EVENTS, ctx);
+    }
+
+    0
+}
+
+/// Looks up the matching start/issue timestamp for a done/complete
+/// tracepoint, computes latency, and emits a single enriched event. A
+/// done/complete with no matching start (e.g. the request began before this
+/// probe attached) is dropped rather than emitted half-populated.
+fn try_emit_block_io_done(ctx: &TracePointContext) -> u32 {
+    info!(ctx, "[eBPF] block_io done/complete tracepoint triggered");
+
+    let Ok(trace) = read_block_io_trace(ctx) else {
+        return 0;
+    };
+
+    let key = block_io_key(trace.dev, trace.sector);
+
+    let Some(start_ts) = (unsafe { BLOCK_IO_START.get(&key) }).copied() else {
+        return 0;
+    };
+    let _ = BLOCK_IO_START.remove(&key);
+
+    let now = unsafe { bpf_ktime_get_ns() };
+    let latency_ns = now.saturating_sub(start_ts);
+
+    let threshold = match unsafe { BLOCK_IO_THRESHOLD_NS.get(&0) } {
+        Some(t) => *t,
+        None => DEFAULT_THRESHOLD_NS,
+    };
+    if latency_ns < threshold {
+        return 0;
+    }
+
+    let mut reservation = match BLOCK_IO_EVENTS.reserve::<BlockIoEvent>(0) {
+        Some(r) => r,
+        None => return 0,
+    };
+
+    let event = reservation.as_mut_ptr();
+    unsafe {
+        (*event).init_base();
+        (*event).dev = trace.dev;
+        (*event).sector = trace.sector;
+        (*event).nr_sector = trace.nr_sector;
+        (*event).bytes = trace.bytes;
+        (*event).rwbs = trace.rwbs;
+        (*event).comm = trace.comm;
+        (*event).event_type = BlockIoEventType::Done as u8;
+        (*event).latency_ns = latency_ns;
+    }
+
+    reservation.submit(0);
+
+    0
+}
+
+/// Reads the fixed-offset tracepoint fields shared by block_io_start/done
+/// and block_rq_issue/complete.
+#[inline(always)]
+fn read_block_io_trace(ctx: &TracePointContext) -> Result<BlockIoTrace, u32> {
+    let header_ptr = ctx.as_ptr() as *const BlockIoTrace;
+    unsafe { aya_ebpf::helpers::bpf_probe_read_kernel(header_ptr) }.map_err(|_| {
+        info!(ctx, "[eBPF] Failed to read block I/O trace header");
+        1u32
+    })
 }
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 struct BlockIoTrace {
     common_type: u16,
     common_flags: u8,
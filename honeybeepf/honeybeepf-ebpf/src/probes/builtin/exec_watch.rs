@@ -7,21 +7,42 @@ use aya_ebpf::{
     macros::{map, tracepoint},
     maps::RingBuf,
     programs::TracePointContext,
-    helpers::bpf_get_current_pid_tgid,
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_probe_read_kernel_buf},
 };
-use honeybeepf_common::ExecEvent;
+use honeybeepf_common::{ExecEvent, EXEC_FILENAME_LEN};
 use super::llm::maps::EXEC_RINGBUF_SIZE;
 
 #[map]
 pub static EXEC_EVENTS: RingBuf = RingBuf::with_byte_size(EXEC_RINGBUF_SIZE, 0);
 
+/// Offset of the `__data_loc char[] filename` field within the
+/// `sched_process_exec` tracepoint's context, per
+/// `/sys/kernel/debug/tracing/events/sched/sched_process_exec/format`. The
+/// field's own value isn't the string - it's a `(len << 16) | offset`
+/// pair locating the string elsewhere in the same record, so it's read
+/// like any other fixed-offset field and then decoded below.
+const FILENAME_DATA_LOC_OFFSET: usize = 8;
+
 /// Tracepoint for sched_process_exec - fires when a process calls exec().
 #[tracepoint]
-pub fn probe_exec(_ctx: TracePointContext) -> u32 {
+pub fn probe_exec(ctx: TracePointContext) -> u32 {
     if let Some(mut slot) = EXEC_EVENTS.reserve::<ExecEvent>(0) {
         let event = unsafe { &mut *slot.as_mut_ptr() };
         event.pid = (bpf_get_current_pid_tgid() >> 32) as u32;
-        event._pad = 0;
+        event.comm = bpf_get_current_comm().unwrap_or([0; 16]);
+        event.filename = [0; EXEC_FILENAME_LEN];
+
+        if let Ok(data_loc) = unsafe { ctx.read_at::<u32>(FILENAME_DATA_LOC_OFFSET) } {
+            let str_offset = (data_loc & 0xFFFF) as usize;
+            let str_len = (((data_loc >> 16) & 0xFFFF) as usize).min(EXEC_FILENAME_LEN);
+            let _ = unsafe {
+                bpf_probe_read_kernel_buf(
+                    (ctx.as_ptr() as *const u8).add(str_offset),
+                    &mut event.filename[..str_len],
+                )
+            };
+        }
+
         slot.submit(0);
     }
     0
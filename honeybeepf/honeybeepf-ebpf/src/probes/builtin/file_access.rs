@@ -1,7 +1,7 @@
 //! File access tracepoint for monitoring sensitive file accesses.
 
 use aya_ebpf::{
-    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_probe_read_user_str_bytes},
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_probe_read_user, bpf_probe_read_user_str_bytes},
     macros::{map, tracepoint},
     maps::{HashMap, RingBuf},
     programs::TracePointContext,
@@ -13,6 +13,7 @@ use crate::probes::{HoneyBeeEvent, emit_event};
 const MAX_EVENT_SIZE: u32 = 1024 * 1024;
 const MAX_WATCHED_PATHS: u32 = 64;
 const MAX_WATCHED_SUFFIXES: u32 = 32;
+const MAX_WATCHED_PREFIXES: u32 = 64;
 
 #[map]
 pub static FILE_ACCESS_EVENTS: RingBuf = RingBuf::with_byte_size(MAX_EVENT_SIZE, 0);
@@ -25,6 +26,122 @@ pub static WATCHED_PATHS: HashMap<u64, u8> = HashMap::with_max_entries(MAX_WATCH
 #[map]
 pub static WATCHED_SUFFIXES: HashMap<u64, u32> = HashMap::with_max_entries(MAX_WATCHED_SUFFIXES, 0);
 
+/// Map of watched directory-prefix hashes (subtree match). Key is the
+/// running FNV-1a hash of the path up to and including a `/` boundary (or
+/// the whole path, for a prefix registered without a trailing slash), value
+/// is 1 if watched. Registering the hash of `/etc/ssl/` watches everything
+/// under that directory in a single O(n) pass over the filename.
+#[map]
+pub static WATCHED_PREFIXES: HashMap<u64, u8> = HashMap::with_max_entries(MAX_WATCHED_PREFIXES, 0);
+
+/// Per-syscall `filename`/`flags`/`mode` (or, for `openat2`, `open_how`
+/// pointer) field offsets within each tracepoint's context, keyed by the
+/// `OFFSET_*` constants below. Populated from userspace (see
+/// `FileAccessProbe::resolve_tracepoint_offsets`) by parsing each
+/// tracepoint's tracefs `format` file, since these offsets are part of the
+/// syscall tracepoint ABI and shift across architectures and kernel configs.
+/// Falls back to the `DEFAULT_*_OFFSET` constants below if userspace never
+/// populates an entry (e.g. tracefs is unreadable).
+#[map]
+pub static TRACEPOINT_OFFSETS: HashMap<u32, u64> = HashMap::with_max_entries(16, 0);
+
+// sys_enter_openat
+pub const OFFSET_FILENAME: u32 = 0;
+pub const OFFSET_FLAGS: u32 = 1;
+pub const OFFSET_MODE: u32 = 2;
+// sys_enter_open
+pub const OFFSET_OPEN_FILENAME: u32 = 3;
+pub const OFFSET_OPEN_FLAGS: u32 = 4;
+pub const OFFSET_OPEN_MODE: u32 = 5;
+// sys_enter_openat2
+pub const OFFSET_OPENAT2_FILENAME: u32 = 6;
+pub const OFFSET_OPENAT2_HOW_PTR: u32 = 7;
+// sys_enter_creat
+pub const OFFSET_CREAT_FILENAME: u32 = 8;
+pub const OFFSET_CREAT_MODE: u32 = 9;
+
+const DEFAULT_FILENAME_OFFSET: u64 = 24;
+const DEFAULT_FLAGS_OFFSET: u64 = 32;
+const DEFAULT_MODE_OFFSET: u64 = 40;
+
+const DEFAULT_OPEN_FILENAME_OFFSET: u64 = 16;
+const DEFAULT_OPEN_FLAGS_OFFSET: u64 = 24;
+const DEFAULT_OPEN_MODE_OFFSET: u64 = 32;
+
+const DEFAULT_OPENAT2_FILENAME_OFFSET: u64 = 24;
+const DEFAULT_OPENAT2_HOW_PTR_OFFSET: u64 = 32;
+
+const DEFAULT_CREAT_FILENAME_OFFSET: u64 = 16;
+const DEFAULT_CREAT_MODE_OFFSET: u64 = 24;
+
+/// `creat(path, mode)` is equivalent to
+/// `open(path, O_CREAT | O_WRONLY | O_TRUNC, mode)`; it has no `flags`
+/// argument of its own, so we report the flags implied by the syscall.
+const CREAT_IMPLIED_FLAGS: u32 = libc_o_creat_wronly_trunc();
+
+const fn libc_o_creat_wronly_trunc() -> u32 {
+    const O_WRONLY: u32 = 0o1;
+    const O_CREAT: u32 = 0o100;
+    const O_TRUNC: u32 = 0o1000;
+    O_WRONLY | O_CREAT | O_TRUNC
+}
+
+/// Layout of the `open_how` struct passed by pointer to `openat2`, per the
+/// stable uapi contract (`include/uapi/linux/openat2.h`). Unlike the other
+/// tracepoints' argument offsets, this is a fixed kernel-userspace ABI and
+/// doesn't need tracefs-based resolution.
+#[repr(C)]
+struct OpenHow {
+    flags: u64,
+    mode: u64,
+    resolve: u64,
+}
+
+#[inline(always)]
+fn resolved_offset(key: u32, default: u64) -> usize {
+    unsafe { TRACEPOINT_OFFSETS.get(&key).copied().unwrap_or(default) as usize }
+}
+
+/// Runs the shared exact/prefix/suffix match logic against an already-read
+/// filename buffer and, on a match, emits `E` (whose own `fill` re-reads the
+/// tracepoint context in its syscall-specific layout) onto
+/// `FILE_ACCESS_EVENTS`.
+#[inline(always)]
+fn match_and_emit<E: HoneyBeeEvent<TracePointContext>>(
+    ctx: &TracePointContext,
+    filename_buf: &[u8; MAX_FILENAME_LEN],
+) -> u32 {
+    let hash = simple_hash(filename_buf);
+    if unsafe { WATCHED_PATHS.get(&hash).is_some() } {
+        return emit_event::<TracePointContext, E>(&FILE_ACCESS_EVENTS, ctx);
+    }
+
+    if check_prefix_match(filename_buf) {
+        return emit_event::<TracePointContext, E>(&FILE_ACCESS_EVENTS, ctx);
+    }
+
+    if check_suffix_match(filename_buf) {
+        return emit_event::<TracePointContext, E>(&FILE_ACCESS_EVENTS, ctx);
+    }
+
+    0
+}
+
+/// Reads a `filename`-shaped pointer argument at `offset` and copies the
+/// userspace string it points to into a stack buffer, for use by the match
+/// logic before an event type is chosen.
+#[inline(always)]
+fn read_filename(ctx: &TracePointContext, offset: usize) -> Result<Option<[u8; MAX_FILENAME_LEN]>, u32> {
+    let filename_ptr: u64 = unsafe { ctx.read_at(offset).map_err(|_| 1u32)? };
+    if filename_ptr == 0 {
+        return Ok(None);
+    }
+
+    let mut filename_buf = [0u8; MAX_FILENAME_LEN];
+    let _ = unsafe { bpf_probe_read_user_str_bytes(filename_ptr as *const u8, &mut filename_buf) };
+    Ok(Some(filename_buf))
+}
+
 /// Tracepoint for sys_enter_openat - fires when a process calls openat().
 #[tracepoint]
 pub fn sys_enter_openat(ctx: TracePointContext) -> u32 {
@@ -36,36 +153,99 @@ pub fn sys_enter_openat(ctx: TracePointContext) -> u32 {
 
 #[inline(always)]
 fn try_sys_enter_openat(ctx: &TracePointContext) -> Result<u32, u32> {
-    // Read filename pointer from tracepoint context
-    let filename_ptr: u64 = unsafe { ctx.read_at(24).map_err(|_| 1u32)? };
-    if filename_ptr == 0 {
-        return Ok(0);
+    let filename_off = resolved_offset(OFFSET_FILENAME, DEFAULT_FILENAME_OFFSET);
+    match read_filename(ctx, filename_off)? {
+        Some(filename_buf) => Ok(match_and_emit::<FileAccessEvent>(ctx, &filename_buf)),
+        None => Ok(0),
     }
+}
 
-    // Read filename into stack buffer
-    let mut filename_buf = [0u8; MAX_FILENAME_LEN];
-    let _ = unsafe { bpf_probe_read_user_str_bytes(filename_ptr as *const u8, &mut filename_buf) };
+/// Tracepoint for sys_enter_open - fires when a process calls the legacy
+/// open() syscall directly instead of openat().
+#[tracepoint]
+pub fn sys_enter_open(ctx: TracePointContext) -> u32 {
+    match try_sys_enter_open(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
 
-    // First, check exact path match
-    let hash = simple_hash(&filename_buf);
-    if unsafe { WATCHED_PATHS.get(&hash).is_some() } {
-        // Exact match found, emit event
-        return Ok(emit_event::<TracePointContext, FileAccessEvent>(
-            &FILE_ACCESS_EVENTS,
-            ctx,
-        ));
+#[inline(always)]
+fn try_sys_enter_open(ctx: &TracePointContext) -> Result<u32, u32> {
+    let filename_off = resolved_offset(OFFSET_OPEN_FILENAME, DEFAULT_OPEN_FILENAME_OFFSET);
+    match read_filename(ctx, filename_off)? {
+        Some(filename_buf) => Ok(match_and_emit::<OpenEvent>(ctx, &filename_buf)),
+        None => Ok(0),
     }
+}
 
-    // Second, check suffix match
-    if check_suffix_match(&filename_buf) {
-        return Ok(emit_event::<TracePointContext, FileAccessEvent>(
-            &FILE_ACCESS_EVENTS,
-            ctx,
-        ));
+/// Tracepoint for sys_enter_openat2 - fires when a process calls openat2(),
+/// which carries its flags/mode inside an `open_how` struct rather than as
+/// flat register arguments.
+#[tracepoint]
+pub fn sys_enter_openat2(ctx: TracePointContext) -> u32 {
+    match try_sys_enter_openat2(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[inline(always)]
+fn try_sys_enter_openat2(ctx: &TracePointContext) -> Result<u32, u32> {
+    let filename_off = resolved_offset(OFFSET_OPENAT2_FILENAME, DEFAULT_OPENAT2_FILENAME_OFFSET);
+    match read_filename(ctx, filename_off)? {
+        Some(filename_buf) => Ok(match_and_emit::<OpenAt2Event>(ctx, &filename_buf)),
+        None => Ok(0),
+    }
+}
+
+/// Tracepoint for sys_enter_creat - fires when a process calls creat(),
+/// equivalent to open() with O_CREAT|O_WRONLY|O_TRUNC.
+#[tracepoint]
+pub fn sys_enter_creat(ctx: TracePointContext) -> u32 {
+    match try_sys_enter_creat(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[inline(always)]
+fn try_sys_enter_creat(ctx: &TracePointContext) -> Result<u32, u32> {
+    let filename_off = resolved_offset(OFFSET_CREAT_FILENAME, DEFAULT_CREAT_FILENAME_OFFSET);
+    match read_filename(ctx, filename_off)? {
+        Some(filename_buf) => Ok(match_and_emit::<CreatEvent>(ctx, &filename_buf)),
+        None => Ok(0),
+    }
+}
+
+/// Checks whether any directory-prefix boundary along `filename` matches a
+/// watched prefix. Maintains a single running FNV-1a hash over the bytes
+/// seen so far, checking it against `WATCHED_PREFIXES` at each `/` and at
+/// the terminating NUL (so a prefix registered without a trailing slash
+/// still matches an exact subtree root). Single pass, bounded by
+/// `MAX_FILENAME_LEN` for the verifier.
+#[inline(always)]
+fn check_prefix_match(filename: &[u8; MAX_FILENAME_LEN]) -> bool {
+    let mut hash: u64 = 0xcbf29ce484222325; // FNV offset basis
+    let mut i: usize = 0;
+
+    while i < MAX_FILENAME_LEN {
+        let b = filename[i];
+        if b == 0 {
+            break;
+        }
+
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3); // FNV prime
+
+        if b == b'/' && unsafe { WATCHED_PREFIXES.get(&hash).is_some() } {
+            return true;
+        }
+
+        i += 1;
     }
 
-    // No match, skip
-    Ok(0)
+    unsafe { WATCHED_PREFIXES.get(&hash).is_some() }
 }
 
 /// Check if filename ends with any watched suffix
@@ -139,34 +319,139 @@ fn simple_hash(data: &[u8; MAX_FILENAME_LEN]) -> u64 {
     hash
 }
 
+/// Reads `flags`/`mode` out of a tracepoint context at flat, syscall-specific
+/// offsets (shared by `open`, `openat` and `creat`, which differ only in
+/// which offsets apply) and fills in the common `FileAccessEvent` fields.
+#[inline(always)]
+fn fill_from_flat_offsets(
+    event: &mut FileAccessEvent,
+    ctx: &TracePointContext,
+    filename_off: usize,
+    flags_off: Option<usize>,
+    mode_off: usize,
+) -> Result<(), u32> {
+    event.init_base();
+
+    let filename_ptr: u64 = unsafe { ctx.read_at(filename_off).map_err(|_| 1u32)? };
+
+    event.flags = match flags_off {
+        Some(off) => {
+            let flags: i64 = unsafe { ctx.read_at(off).map_err(|_| 1u32)? };
+            flags as u32
+        }
+        None => CREAT_IMPLIED_FLAGS,
+    };
+
+    let mode: i64 = unsafe { ctx.read_at(mode_off).map_err(|_| 1u32)? };
+    event.mode = mode as u32;
+    event.tid = (bpf_get_current_pid_tgid() & 0xFFFFFFFF) as u32;
+
+    if let Ok(comm) = bpf_get_current_comm() {
+        event.comm = comm;
+    }
+
+    if filename_ptr != 0 {
+        let filename_slice = &mut event.filename[..MAX_FILENAME_LEN];
+        let _ = unsafe { bpf_probe_read_user_str_bytes(filename_ptr as *const u8, filename_slice) };
+    }
+
+    Ok(())
+}
+
 impl HoneyBeeEvent<TracePointContext> for FileAccessEvent {
     fn metadata(&mut self) -> &mut EventMetadata {
         &mut self.metadata
     }
 
     fn fill(&mut self, ctx: &TracePointContext) -> Result<(), u32> {
-        self.init_base();
+        fill_from_flat_offsets(
+            self,
+            ctx,
+            resolved_offset(OFFSET_FILENAME, DEFAULT_FILENAME_OFFSET),
+            Some(resolved_offset(OFFSET_FLAGS, DEFAULT_FLAGS_OFFSET)),
+            resolved_offset(OFFSET_MODE, DEFAULT_MODE_OFFSET),
+        )
+    }
+}
+
+/// Event emitted for the legacy `open()` syscall, sharing `FileAccessEvent`'s
+/// wire layout but its own `flags`/`mode` offsets.
+#[repr(transparent)]
+pub struct OpenEvent(FileAccessEvent);
+
+impl HoneyBeeEvent<TracePointContext> for OpenEvent {
+    fn metadata(&mut self) -> &mut EventMetadata {
+        self.0.metadata()
+    }
+
+    fn fill(&mut self, ctx: &TracePointContext) -> Result<(), u32> {
+        fill_from_flat_offsets(
+            &mut self.0,
+            ctx,
+            resolved_offset(OFFSET_OPEN_FILENAME, DEFAULT_OPEN_FILENAME_OFFSET),
+            Some(resolved_offset(OFFSET_OPEN_FLAGS, DEFAULT_OPEN_FLAGS_OFFSET)),
+            resolved_offset(OFFSET_OPEN_MODE, DEFAULT_OPEN_MODE_OFFSET),
+        )
+    }
+}
+
+/// Event emitted for `creat()`, which has no `flags` argument of its own -
+/// `flags` is filled in with the flags `creat()` implies.
+#[repr(transparent)]
+pub struct CreatEvent(FileAccessEvent);
 
-        // Read openat arguments from tracepoint context
-        // Layout: header (16 bytes) + dfd (8) + filename (8) + flags (8) + mode (8)
-        let filename_ptr: u64 = unsafe { ctx.read_at(24).map_err(|_| 1u32)? };
-        let flags: i64 = unsafe { ctx.read_at(32).map_err(|_| 1u32)? };
-        let mode: i64 = unsafe { ctx.read_at(40).map_err(|_| 1u32)? };
+impl HoneyBeeEvent<TracePointContext> for CreatEvent {
+    fn metadata(&mut self) -> &mut EventMetadata {
+        self.0.metadata()
+    }
+
+    fn fill(&mut self, ctx: &TracePointContext) -> Result<(), u32> {
+        fill_from_flat_offsets(
+            &mut self.0,
+            ctx,
+            resolved_offset(OFFSET_CREAT_FILENAME, DEFAULT_CREAT_FILENAME_OFFSET),
+            None,
+            resolved_offset(OFFSET_CREAT_MODE, DEFAULT_CREAT_MODE_OFFSET),
+        )
+    }
+}
+
+/// Event emitted for `openat2()`. Unlike the other three syscalls,
+/// `flags`/`mode` aren't flat tracepoint arguments - they live inside the
+/// `open_how` struct the tracepoint's `how` argument points to, so this
+/// reads the pointer and then `bpf_probe_read_user`s the struct itself.
+#[repr(transparent)]
+pub struct OpenAt2Event(FileAccessEvent);
+
+impl HoneyBeeEvent<TracePointContext> for OpenAt2Event {
+    fn metadata(&mut self) -> &mut EventMetadata {
+        self.0.metadata()
+    }
+
+    fn fill(&mut self, ctx: &TracePointContext) -> Result<(), u32> {
+        self.0.init_base();
+
+        let filename_off = resolved_offset(OFFSET_OPENAT2_FILENAME, DEFAULT_OPENAT2_FILENAME_OFFSET);
+        let filename_ptr: u64 = unsafe { ctx.read_at(filename_off).map_err(|_| 1u32)? };
+
+        let how_off = resolved_offset(OFFSET_OPENAT2_HOW_PTR, DEFAULT_OPENAT2_HOW_PTR_OFFSET);
+        let how_ptr: u64 = unsafe { ctx.read_at(how_off).map_err(|_| 1u32)? };
+
+        if how_ptr != 0 {
+            let how: OpenHow = unsafe { bpf_probe_read_user(how_ptr as *const OpenHow).map_err(|_| 1u32)? };
+            self.0.flags = how.flags as u32;
+            self.0.mode = how.mode as u32;
+        }
 
-        self.flags = flags as u32;
-        self.mode = mode as u32;
-        self.tid = (bpf_get_current_pid_tgid() & 0xFFFFFFFF) as u32;
+        self.0.tid = (bpf_get_current_pid_tgid() & 0xFFFFFFFF) as u32;
 
-        // Read process comm
         if let Ok(comm) = bpf_get_current_comm() {
-            self.comm = comm;
+            self.0.comm = comm;
         }
 
-        // Read filename from userspace
         if filename_ptr != 0 {
-            let filename_slice = &mut self.filename[..MAX_FILENAME_LEN];
-            let _ =
-                unsafe { bpf_probe_read_user_str_bytes(filename_ptr as *const u8, filename_slice) };
+            let filename_slice = &mut self.0.filename[..MAX_FILENAME_LEN];
+            let _ = unsafe { bpf_probe_read_user_str_bytes(filename_ptr as *const u8, filename_slice) };
         }
 
         Ok(())
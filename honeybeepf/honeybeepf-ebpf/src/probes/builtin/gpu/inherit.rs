@@ -0,0 +1,159 @@
+//! GPU fd inheritance tracking across `dup`/`dup2`/`dup3` and `fork`.
+//!
+//! `GPU_FD_MAP` is keyed by `(pid << 32) | fd`, so a process that duplicates
+//! a GPU fd (or forks, inheriting its whole fd table) silently drops off
+//! GPU accounting unless we mirror those descriptor games here.
+
+use core::ffi::c_void;
+
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_for_each_map_elem, bpf_get_current_pid_tgid, bpf_probe_read_kernel},
+    macros::{map, tracepoint},
+    maps::HashMap,
+    programs::TracePointContext,
+};
+use honeybeepf_common::GpuFdInfo;
+
+use super::usage::GPU_FD_MAP;
+use crate::probes::builtin::syscall_types::SyscallTraceHeader;
+
+const MAX_PENDING_DUPS: u32 = 10240;
+
+#[repr(C)]
+struct SysEnterDup {
+    header: SyscallTraceHeader,
+    oldfd: i64,
+}
+
+#[repr(C)]
+struct SysExitRet {
+    header: SyscallTraceHeader,
+    ret: i64,
+}
+
+#[repr(C)]
+struct SchedProcessFork {
+    common_type: u16,
+    common_flags: u8,
+    common_preempt_count: u8,
+    common_pid: i32,
+    parent_comm: [u8; 16],
+    parent_pid: i32,
+    child_comm: [u8; 16],
+    child_pid: i32,
+}
+
+/// Pending `dup`/`dup3` old-fd, keyed by tid, resolved once the exit
+/// tracepoint reports the new fd.
+#[map]
+static PENDING_DUP: HashMap<u64, i64> = HashMap::with_max_entries(MAX_PENDING_DUPS, 0);
+
+#[inline(always)]
+fn fd_key(pid: u32, fd: i64) -> u64 {
+    ((pid as u64) << 32) | (fd as u32 as u64)
+}
+
+/// sys_enter_dup / sys_enter_dup2 / sys_enter_dup3: record the old fd so the
+/// exit probe can pair it with the newly allocated one. `oldfd` sits at the
+/// same offset across all three syscalls' trace formats, so one program
+/// covers all of them.
+#[tracepoint]
+pub fn honeybeepf_gpu_dup_enter(ctx: TracePointContext) -> u32 {
+    let header_ptr = ctx.as_ptr() as *const SysEnterDup;
+    let oldfd = match unsafe { bpf_probe_read_kernel(&((*header_ptr).oldfd) as *const i64) } {
+        Ok(v) => v,
+        Err(_) => return 0,
+    };
+
+    let tid = bpf_get_current_pid_tgid();
+    let _ = PENDING_DUP.insert(&tid, &oldfd, 0);
+    0
+}
+
+/// sys_exit_dup / sys_exit_dup2 / sys_exit_dup3: if the old fd was a tracked
+/// GPU fd, mirror its `GpuFdInfo` under the newly returned fd. Covers
+/// dup2/dup3(oldfd, oldfd), which return oldfd as a no-op, harmlessly.
+#[tracepoint]
+pub fn honeybeepf_gpu_dup_exit(ctx: TracePointContext) -> u32 {
+    let tid = bpf_get_current_pid_tgid();
+    let oldfd = match unsafe { PENDING_DUP.get(&tid) } {
+        Some(v) => *v,
+        None => return 0,
+    };
+    let _ = PENDING_DUP.remove(&tid);
+
+    let header_ptr = ctx.as_ptr() as *const SysExitRet;
+    let newfd = match unsafe { bpf_probe_read_kernel(&((*header_ptr).ret) as *const i64) } {
+        Ok(v) if v >= 0 => v,
+        _ => return 0,
+    };
+
+    let pid = ctx.tgid();
+    if let Some(info) = unsafe { GPU_FD_MAP.get(&fd_key(pid, oldfd)) } {
+        let _ = GPU_FD_MAP.insert(&fd_key(pid, newfd), info, 0);
+    }
+    0
+}
+
+/// sched_process_fork: the child inherits the parent's entire fd table
+/// verbatim, so any GPU fd the parent held is now also valid in the child.
+/// eBPF can't enumerate "all fds of pid X" directly; we instead scan
+/// `GPU_FD_MAP` with `bpf_for_each_map_elem` and duplicate any entry whose
+/// encoded pid matches the parent, reattributing it to the child's pid.
+/// Requires a kernel with `bpf_for_each_map_elem` (5.13+); on older kernels
+/// this is a no-op and fork'd children simply won't show up until they open
+/// a GPU device themselves.
+#[tracepoint]
+pub fn honeybeepf_gpu_fork(ctx: TracePointContext) -> u32 {
+    let header_ptr = ctx.as_ptr() as *const SchedProcessFork;
+    let parent_pid = match unsafe { bpf_probe_read_kernel(&((*header_ptr).parent_pid) as *const i32) } {
+        Ok(v) => v as u32,
+        Err(_) => return 0,
+    };
+    let child_pid = match unsafe { bpf_probe_read_kernel(&((*header_ptr).child_pid) as *const i32) } {
+        Ok(v) => v as u32,
+        Err(_) => return 0,
+    };
+
+    if parent_pid == child_pid {
+        return 0;
+    }
+
+    // `bpf_for_each_map_elem` passes (parent_pid, child_pid) through as the
+    // opaque callback context, encoded into a single u64 since the helper
+    // forwards it as a raw pointer-sized value.
+    let fork_ctx: u64 = ((parent_pid as u64) << 32) | child_pid as u64;
+    unsafe {
+        bpf_for_each_map_elem(
+            &GPU_FD_MAP,
+            copy_fd_on_fork as *mut c_void,
+            &fork_ctx as *const u64 as *mut c_void,
+            0,
+        );
+    }
+    0
+}
+
+/// Callback invoked by `bpf_for_each_map_elem` for every `GPU_FD_MAP` entry.
+/// Reattributes entries owned by the forking parent to its new child.
+extern "C" fn copy_fd_on_fork(
+    _map: *mut c_void,
+    key: *mut c_void,
+    value: *mut c_void,
+    ctx: *mut c_void,
+) -> i64 {
+    unsafe {
+        let key = *(key as *const u64);
+        let info = *(value as *const GpuFdInfo);
+        let fork_ctx = *(ctx as *const u64);
+        let parent_pid = (fork_ctx >> 32) as u32;
+        let child_pid = fork_ctx as u32;
+
+        if (key >> 32) as u32 == parent_pid {
+            let fd = (key & 0xffff_ffff) as i64;
+            let _ = GPU_FD_MAP.insert(&fd_key(child_pid, fd), &info, 0);
+        }
+    }
+    0
+}
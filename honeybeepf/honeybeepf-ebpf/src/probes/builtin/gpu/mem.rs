@@ -0,0 +1,205 @@
+//! GPU memory-accounting probes.
+//!
+//! Decodes allocation/free ioctls against fds already tracked in
+//! `GPU_FD_MAP` (populated by `usage.rs`) and maintains a running
+//! allocated-bytes total per (pid, gpu_index).
+
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_comm, bpf_probe_read_kernel, bpf_probe_read_user},
+    macros::{map, tracepoint},
+    maps::{HashMap, RingBuf},
+    programs::TracePointContext,
+};
+use honeybeepf_common::{EventMetadata, GpuMemEvent};
+
+use super::usage::GPU_FD_MAP;
+use crate::probes::{
+    HoneyBeeEvent,
+    builtin::syscall_types::SyscallTraceHeader,
+};
+
+const MAX_EVENT_SIZE: u32 = 1024 * 1024;
+const MAX_GPU_TOTALS: u32 = 10240;
+
+/// `_IOC` layout constants (see `<asm-generic/ioctl.h>`).
+const IOC_NRBITS: u32 = 8;
+const IOC_TYPEBITS: u32 = 8;
+const IOC_NRSHIFT: u32 = 0;
+const IOC_TYPESHIFT: u32 = IOC_NRSHIFT + IOC_NRBITS;
+
+#[inline(always)]
+fn ioc_type(cmd: u32) -> u32 {
+    (cmd >> IOC_TYPESHIFT) & ((1 << IOC_TYPEBITS) - 1)
+}
+
+#[inline(always)]
+fn ioc_nr(cmd: u32) -> u32 {
+    (cmd >> IOC_NRSHIFT) & ((1 << IOC_NRBITS) - 1)
+}
+
+const DRM_IOCTL_TYPE: u32 = b'd' as u32;
+
+/// A single known GPU allocation/free ioctl and where to read its size field
+/// out of the user-supplied argument struct.
+struct GpuAllocCmd {
+    ioc_type: u32,
+    nr: u32,
+    /// Byte offset of the `u64` size field inside the ioctl argument struct.
+    size_offset: usize,
+    /// Positive for allocation commands, negative for free commands.
+    sign: i64,
+}
+
+// DRM GEM_CREATE-style commands (amdgpu/i915/etc. all place the size field
+// at the head of their driver-specific create struct) plus the NVIDIA
+// UVM/RM allocation command, and their free/destroy counterparts so
+// `GPU_MEM_TOTALS` reflects currently-allocated bytes rather than a
+// monotonically-growing allocation history. Free/destroy ioctls carry no
+// size argument of their own, so they read back the size this
+// (pid, gpu_index) last allocated from `GPU_LAST_ALLOC_SIZE` instead and
+// their `size_offset` is unused - see `try_gpu_ioctl`. Offsets are
+// best-effort; unmatched commands are
+// ignored rather than guessed at.
+const GPU_ALLOC_CMDS: &[GpuAllocCmd] = &[
+    // DRM_IOCTL_AMDGPU_GEM_CREATE (nr 0x40 in the amdgpu driver range)
+    GpuAllocCmd { ioc_type: DRM_IOCTL_TYPE, nr: 0x40, size_offset: 0, sign: 1 },
+    // DRM_IOCTL_I915_GEM_CREATE
+    GpuAllocCmd { ioc_type: DRM_IOCTL_TYPE, nr: 0x1d, size_offset: 0, sign: 1 },
+    // NVIDIA RM allocation ioctl (NV_ESC_RM_ALLOC nr), size embedded at a
+    // fixed offset in the wrapped rm alloc params struct.
+    GpuAllocCmd { ioc_type: b'F' as u32, nr: 0x2b, size_offset: 16, sign: 1 },
+    // DRM_IOCTL_GEM_CLOSE (generic DRM GEM handle close, shared by
+    // amdgpu/i915/nouveau/etc.) - no size field of its own, so this
+    // falls back to `GPU_LAST_ALLOC_SIZE` below rather than reading a
+    // bogus offset.
+    GpuAllocCmd { ioc_type: DRM_IOCTL_TYPE, nr: 0x09, size_offset: 0, sign: -1 },
+    // NVIDIA RM free ioctl (NV_ESC_RM_FREE).
+    GpuAllocCmd { ioc_type: b'F' as u32, nr: 0x29, size_offset: 0, sign: -1 },
+];
+
+#[map]
+pub static GPU_MEM_EVENTS: RingBuf = RingBuf::with_byte_size(MAX_EVENT_SIZE, 0);
+
+/// Running allocated-bytes total, keyed by `(pid << 32) | gpu_index as u32`.
+#[map]
+pub static GPU_MEM_TOTALS: HashMap<u64, u64> = HashMap::with_max_entries(MAX_GPU_TOTALS, 0);
+
+/// Most recently allocated size, same key as `GPU_MEM_TOTALS`. Free/destroy
+/// ioctls (GEM_CLOSE, RM_FREE) carry a handle, not a size, so there's
+/// nothing to read out of the ioctl's own argument struct at free time -
+/// this is consulted instead. Approximate (a process with several live
+/// allocations on the same gpu_index only gets credited for its last one),
+/// matching the accounting's existing best-effort posture.
+#[map]
+static GPU_LAST_ALLOC_SIZE: HashMap<u64, u64> = HashMap::with_max_entries(MAX_GPU_TOTALS, 0);
+
+impl HoneyBeeEvent<TracePointContext> for GpuMemEvent {
+    fn metadata(&mut self) -> &mut EventMetadata {
+        &mut self.metadata
+    }
+
+    fn fill(&mut self, _ctx: &TracePointContext) -> Result<(), u32> {
+        self.init_base();
+        Ok(())
+    }
+}
+
+#[repr(C)]
+struct SysEnterIoctl {
+    header: SyscallTraceHeader,
+    fd: i64,
+    request: i64,
+    arg: i64,
+}
+
+fn find_alloc_cmd(cmd: u32) -> Option<&'static GpuAllocCmd> {
+    let t = ioc_type(cmd);
+    let nr = ioc_nr(cmd);
+    GPU_ALLOC_CMDS
+        .iter()
+        .find(|c| c.ioc_type == t && c.nr == nr)
+}
+
+#[tracepoint]
+pub fn honeybeepf_gpu_ioctl(ctx: TracePointContext) -> u32 {
+    match try_gpu_ioctl(&ctx) {
+        Ok(_) => 0,
+        Err(_) => 0, // Silent: not a GPU fd, or command isn't in the table.
+    }
+}
+
+fn try_gpu_ioctl(ctx: &TracePointContext) -> Result<(), u32> {
+    let header_ptr = ctx.as_ptr() as *const SysEnterIoctl;
+
+    let fd: i64 = unsafe { bpf_probe_read_kernel(&((*header_ptr).fd) as *const i64).map_err(|_| 1u32)? };
+    if fd < 0 {
+        return Err(1);
+    }
+
+    let pid = ctx.tgid();
+    let fd_key = ((pid as u64) << 32) | (fd as u32 as u64);
+    let fd_info = unsafe { GPU_FD_MAP.get(&fd_key).ok_or(1u32)? };
+    let gpu_index = fd_info.gpu_index;
+
+    let request: i64 = unsafe { bpf_probe_read_kernel(&((*header_ptr).request) as *const i64).map_err(|_| 1u32)? };
+    let arg: i64 = unsafe { bpf_probe_read_kernel(&((*header_ptr).arg) as *const i64).map_err(|_| 1u32)? };
+
+    // Approximate accounting is fine: commands that aren't in the table are
+    // ignored rather than guessed at.
+    let alloc_cmd = find_alloc_cmd(request as u32).ok_or(1u32)?;
+    let totals_key = ((pid as u64) << 32) | (gpu_index as u32 as u64);
+
+    let size = if alloc_cmd.sign > 0 {
+        let size: u64 = unsafe {
+            bpf_probe_read_user((arg as usize + alloc_cmd.size_offset) as *const u64)
+                .map_err(|_| 1u32)?
+        };
+        if size == 0 {
+            return Err(1);
+        }
+        let _ = unsafe { GPU_LAST_ALLOC_SIZE.insert(&totals_key, &size, 0) };
+        size
+    } else {
+        // Free/destroy command: no size in its own argument struct, so
+        // fall back to the size this (pid, gpu_index) was last seen
+        // allocating. Nothing recorded yet means we can't decrement
+        // anything meaningful - drop rather than guess.
+        let size = unsafe { GPU_LAST_ALLOC_SIZE.get(&totals_key).copied().unwrap_or(0) };
+        if size == 0 {
+            return Err(1);
+        }
+        size
+    };
+
+    let delta_bytes = alloc_cmd.sign * size as i64;
+    let prev_total = unsafe { GPU_MEM_TOTALS.get(&totals_key).copied().unwrap_or(0) };
+    let total_bytes = (prev_total as i64 + delta_bytes).max(0) as u64;
+    let _ = GPU_MEM_TOTALS.insert(&totals_key, &total_bytes, 0);
+
+    if let Some(mut slot) = GPU_MEM_EVENTS.reserve::<GpuMemEvent>(0) {
+        let event = unsafe { &mut *slot.as_mut_ptr() };
+
+        if event.fill(ctx).is_err() {
+            slot.discard(0);
+            return Err(1);
+        }
+
+        event.gpu_index = gpu_index;
+        event.delta_bytes = delta_bytes;
+        event.total_bytes = total_bytes;
+        event.comm = bpf_get_current_comm().unwrap_or([0u8; 16]);
+
+        slot.submit(0);
+    }
+
+    Ok(())
+}
+
+/// Remove any accumulated totals for a (pid, gpu_index) pair. Called from
+/// `try_gpu_close` once the matching fd is removed from `GPU_FD_MAP`.
+#[inline(always)]
+pub fn clear_gpu_mem_totals(pid: u32, gpu_index: i32) {
+    let totals_key = ((pid as u64) << 32) | (gpu_index as u32 as u64);
+    let _ = GPU_MEM_TOTALS.remove(&totals_key);
+}
@@ -4,7 +4,13 @@
 //! - `usage`: GPU device open/close tracking
 //! - `utils`: Helper functions for GPU device path parsing
 //! - `nccl`: NCCL collective communication monitoring
+//! - `mem`: Per-process GPU memory accounting via allocation ioctls
+//! - `util`: Per-process GPU busy-time tracking via dma-fence tracepoints
+//! - `inherit`: GPU fd inheritance tracking across dup/dup2/dup3 and fork
 
+pub mod inherit;
+pub mod mem;
 pub mod nccl;
 pub mod usage;
+pub mod util;
 pub mod utils;
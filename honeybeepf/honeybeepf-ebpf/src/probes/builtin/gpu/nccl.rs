@@ -4,7 +4,10 @@ use aya_ebpf::{
     maps::{HashMap, RingBuf},
     programs::{ProbeContext, RetProbeContext},
 };
-use honeybeepf_common::{EventMetadata, NcclEvent, NcclOpType, PendingNcclOp};
+use honeybeepf_common::{
+    EventMetadata, NcclCommEvent, NcclEvent, NcclOpType, PendingNcclAsyncError,
+    PendingNcclCommInit, PendingNcclOp,
+};
 
 use crate::probes::HoneyBeeEvent;
 
@@ -18,6 +21,48 @@ pub static NCCL_EVENTS: RingBuf = RingBuf::with_byte_size(MAX_EVENT_SIZE, 0);
 pub static PENDING_NCCL_OPS: HashMap<u64, PendingNcclOp> =
     HashMap::with_max_entries(MAX_PENDING_OPS, 0);
 
+#[map]
+pub static NCCL_COMM_EVENTS: RingBuf = RingBuf::with_byte_size(MAX_EVENT_SIZE, 0);
+
+/// Enter-time state for an in-flight `ncclCommInitRank`, keyed on the
+/// calling thread so the uretprobe can find it again on exit.
+#[map]
+static PENDING_NCCL_COMM_INIT: HashMap<u64, PendingNcclCommInit> =
+    HashMap::with_max_entries(MAX_PENDING_OPS, 0);
+
+/// Enter-time state for an in-flight `ncclCommGetAsyncError`, keyed on the
+/// calling thread so the uretprobe can find it again on exit.
+#[map]
+static PENDING_NCCL_ASYNC_ERROR: HashMap<u64, PendingNcclAsyncError> =
+    HashMap::with_max_entries(MAX_PENDING_OPS, 0);
+
+/// `ncclGroupStart`/`ncclGroupEnd` nesting state for one thread: `group_id`
+/// identifies the batch (derived from the entry timestamp of the outermost
+/// `ncclGroupStart`, which is unique enough per thread activation without a
+/// shared atomic counter), `depth` counts nested Start/End pairs so only the
+/// outermost `ncclGroupEnd` clears the group.
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct NcclGroupState {
+    group_id: u64,
+    depth: u32,
+}
+
+const MAX_GROUP_THREADS: u32 = 1024;
+
+#[map]
+static NCCL_GROUP_STATE: HashMap<u64, NcclGroupState> =
+    HashMap::with_max_entries(MAX_GROUP_THREADS, 0);
+
+/// Current group id for the calling thread, or 0 if it isn't inside a
+/// `ncclGroupStart`/`ncclGroupEnd` block.
+#[inline(always)]
+fn current_group_id(tid: u64) -> u64 {
+    unsafe { NCCL_GROUP_STATE.get(&tid) }
+        .map(|s| s.group_id)
+        .unwrap_or(0)
+}
+
 impl HoneyBeeEvent<RetProbeContext> for NcclEvent {
     fn metadata(&mut self) -> &mut EventMetadata {
         &mut self.metadata
@@ -29,10 +74,24 @@ impl HoneyBeeEvent<RetProbeContext> for NcclEvent {
     }
 }
 
+impl HoneyBeeEvent<RetProbeContext> for NcclCommEvent {
+    fn metadata(&mut self) -> &mut EventMetadata {
+        &mut self.metadata
+    }
+
+    fn fill(&mut self, _ctx: &RetProbeContext) -> Result<(), u32> {
+        self.init_base();
+        Ok(())
+    }
+}
+
 // ===== ncclAllReduce =====
+// signature: ncclAllReduce(const void* sendbuff, void* recvbuff,
+//                          size_t count, ncclDataType_t datatype,
+//                          ncclRedOp_t op, ncclComm_t comm, cudaStream_t stream)
 #[uprobe]
 pub fn nccl_allreduce_enter(ctx: ProbeContext) -> u32 {
-    match try_nccl_entry(&ctx, NcclOpType::AllReduce as u8, true) {
+    match try_nccl_entry(&ctx, NcclOpType::AllReduce as u8, 5) {
         Ok(_) => 0,
         Err(_) => 0,
     }
@@ -50,7 +109,7 @@ pub fn nccl_allreduce_exit(ctx: RetProbeContext) -> u32 {
 
 #[uprobe]
 pub fn nccl_broadcast_enter(ctx: ProbeContext) -> u32 {
-    match try_nccl_entry(&ctx, NcclOpType::Broadcast as u8, true) {
+    match try_nccl_entry(&ctx, NcclOpType::Broadcast as u8, 5) {
         Ok(_) => 0,
         Err(_) => 0,
     }
@@ -68,7 +127,7 @@ pub fn nccl_broadcast_exit(ctx: RetProbeContext) -> u32 {
 
 #[uprobe]
 pub fn nccl_allgather_enter(ctx: ProbeContext) -> u32 {
-    match try_nccl_entry(&ctx, NcclOpType::AllGather as u8, true) {
+    match try_nccl_entry(&ctx, NcclOpType::AllGather as u8, 4) {
         Ok(_) => 0,
         Err(_) => 0,
     }
@@ -87,7 +146,7 @@ pub fn nccl_allgather_exit(ctx: RetProbeContext) -> u32 {
 
 #[uprobe]
 pub fn nccl_reducescatter_enter(ctx: ProbeContext) -> u32 {
-    match try_nccl_entry(&ctx, NcclOpType::ReduceScatter as u8, true) {
+    match try_nccl_entry(&ctx, NcclOpType::ReduceScatter as u8, 5) {
         Ok(_) => 0,
         Err(_) => 0,
     }
@@ -136,10 +195,22 @@ pub fn nccl_recv_exit(ctx: RetProbeContext) -> u32 {
 }
 
 // ===== ncclGroupStart / ncclGroupEnd =====
-// Used to batch multiple NCCL operations
+// Used to batch multiple NCCL operations. Nesting is tracked in
+// NCCL_GROUP_STATE so the calls issued between the outermost Start/End pair
+// share a group id in their emitted events.
 
 #[uprobe]
 pub fn nccl_group_start_enter(ctx: ProbeContext) -> u32 {
+    let tid = bpf_get_current_pid_tgid();
+    let mut state = unsafe { NCCL_GROUP_STATE.get(&tid) }.copied().unwrap_or(
+        NcclGroupState {
+            group_id: unsafe { bpf_ktime_get_ns() },
+            depth: 0,
+        },
+    );
+    state.depth += 1;
+    let _ = NCCL_GROUP_STATE.insert(&tid, &state, 0);
+
     match try_nccl_entry_simple(&ctx, NcclOpType::GroupStart as u8) {
         Ok(_) => 0,
         Err(_) => 0,
@@ -153,6 +224,16 @@ pub fn nccl_group_start_exit(ctx: RetProbeContext) -> u32 {
 
 #[uprobe]
 pub fn nccl_group_end_enter(ctx: ProbeContext) -> u32 {
+    let tid = bpf_get_current_pid_tgid();
+    if let Some(mut state) = unsafe { NCCL_GROUP_STATE.get(&tid) }.copied() {
+        state.depth = state.depth.saturating_sub(1);
+        if state.depth == 0 {
+            let _ = NCCL_GROUP_STATE.remove(&tid);
+        } else {
+            let _ = NCCL_GROUP_STATE.insert(&tid, &state, 0);
+        }
+    }
+
     match try_nccl_entry_simple(&ctx, NcclOpType::GroupEnd as u8) {
         Ok(_) => 0,
         Err(_) => 0,
@@ -180,18 +261,240 @@ pub fn nccl_get_version_exit(ctx: RetProbeContext) -> u32 {
     emit_nccl_event(&ctx, NcclOpType::GetVersion as u8)
 }
 
+// ===== ncclCommInitRank =====
+// signature: ncclCommInitRank(ncclComm_t* comm, int nranks,
+//                              ncclUniqueId commId, int rank)
+// `commId` is a 128-byte struct passed by value; the SysV x86-64 ABI passes
+// aggregates that large via a hidden pointer, so arg2 here is still a
+// pointer we can `bpf_probe_read_user` from, not the struct itself.
+// `comm` is an out-parameter: `*comm` is only populated once the call
+// returns, so the resolved `ncclComm_t` is read back on exit.
+
+#[uprobe]
+pub fn nccl_comm_init_rank_enter(ctx: ProbeContext) -> u32 {
+    match try_nccl_comm_init_rank_enter(&ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_nccl_comm_init_rank_enter(ctx: &ProbeContext) -> Result<(), u32> {
+    let tid = bpf_get_current_pid_tgid();
+
+    let comm_out_ptr: u64 = ctx.arg(0).ok_or(1u32)?;
+    let nranks: i32 = ctx.arg(1).ok_or(1u32)?;
+    let unique_id_ptr: u64 = ctx.arg(2).unwrap_or(0);
+    let rank: i32 = ctx.arg(3).ok_or(1u32)?;
+
+    let unique_id_hash = if unique_id_ptr != 0 {
+        hash_unique_id(unique_id_ptr)
+    } else {
+        0
+    };
+
+    let pending = PendingNcclCommInit {
+        comm_out_ptr,
+        nranks,
+        rank,
+        unique_id_hash,
+        start_ns: unsafe { bpf_ktime_get_ns() },
+    };
+
+    PENDING_NCCL_COMM_INIT
+        .insert(&tid, &pending, 0)
+        .map_err(|_| 1u32)?;
+
+    Ok(())
+}
+
+#[uretprobe]
+pub fn nccl_comm_init_rank_exit(ctx: RetProbeContext) -> u32 {
+    match try_nccl_comm_init_rank_exit(&ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_nccl_comm_init_rank_exit(ctx: &RetProbeContext) -> Result<(), u32> {
+    let tid = bpf_get_current_pid_tgid();
+
+    let pending = unsafe { PENDING_NCCL_COMM_INIT.get(&tid).copied().ok_or(1u32)? };
+    let _ = PENDING_NCCL_COMM_INIT.remove(&tid);
+
+    let now = unsafe { bpf_ktime_get_ns() };
+    let duration_ns = now.saturating_sub(pending.start_ns);
+    let ret_code: i32 = ctx.ret().unwrap_or(-1);
+
+    // The out-param is only populated once ncclCommInitRank has actually
+    // returned; a failed call leaves it untouched, so a read failure or a
+    // still-zero value both just mean "no comm resolved".
+    let comm_ptr: u64 = unsafe {
+        aya_ebpf::helpers::bpf_probe_read_user(pending.comm_out_ptr as *const u64).unwrap_or(0)
+    };
+
+    let Some(mut slot) = NCCL_COMM_EVENTS.reserve::<NcclCommEvent>(0) else {
+        return Err(1);
+    };
+    let event = unsafe { &mut *slot.as_mut_ptr() };
+
+    if event.fill(ctx).is_err() {
+        slot.discard(0);
+        return Err(1);
+    }
+
+    event.comm_ptr = comm_ptr;
+    event.nranks = pending.nranks;
+    event.rank = pending.rank;
+    event.unique_id_hash = pending.unique_id_hash;
+    event.ret_code = ret_code;
+    event.duration_ns = duration_ns;
+    event.comm = bpf_get_current_comm().unwrap_or([0u8; 16]);
+
+    slot.submit(0);
+    Ok(())
+}
+
+/// Byte length of `ncclUniqueId::internal` per NCCL's public header.
+const NCCL_UNIQUE_ID_LEN: usize = 128;
+
+/// FNV-1a hash over the raw bytes of a `ncclUniqueId`, read from userspace
+/// in one bounded-size copy - a cheap fingerprint so communicators sharing
+/// the same bootstrap id can be correlated without carrying the whole
+/// 128-byte id through every event.
+#[inline(always)]
+fn hash_unique_id(ptr: u64) -> u64 {
+    let mut buf = [0u8; NCCL_UNIQUE_ID_LEN];
+    if unsafe { aya_ebpf::helpers::bpf_probe_read_user_buf(ptr as *const u8, &mut buf) }.is_err() {
+        return 0;
+    }
+
+    let mut hash: u64 = 0xcbf29ce484222325; // FNV offset basis
+    for b in buf {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3); // FNV prime
+    }
+    hash
+}
+
+// ===== ncclCommAbort =====
+// signature: ncclCommAbort(ncclComm_t comm)
+// Emits the comm pointer (arg0) so the userspace handler can mark that
+// communicator as aborted in the rank registry; no count/datatype is
+// meaningful for this op.
+
+#[uprobe]
+pub fn nccl_comm_abort_enter(ctx: ProbeContext) -> u32 {
+    match try_nccl_entry_comm_only(&ctx, NcclOpType::CommAbort as u8) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+#[uretprobe]
+pub fn nccl_comm_abort_exit(ctx: RetProbeContext) -> u32 {
+    emit_nccl_event(&ctx, NcclOpType::CommAbort as u8)
+}
+
+// ===== ncclCommGetAsyncError =====
+// signature: ncclCommGetAsyncError(ncclComm_t comm, ncclResult_t* asyncError)
+// `asyncError` is an out-parameter only populated once the call returns, so
+// it's read back on exit the same way ncclCommInitRank's `comm` out-param is.
+
+#[uprobe]
+pub fn nccl_comm_get_async_error_enter(ctx: ProbeContext) -> u32 {
+    match try_nccl_comm_get_async_error_enter(&ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_nccl_comm_get_async_error_enter(ctx: &ProbeContext) -> Result<(), u32> {
+    let tid = bpf_get_current_pid_tgid();
+
+    let comm_ptr: u64 = ctx.arg(0).ok_or(1u32)?;
+    let async_err_ptr: u64 = ctx.arg(1).ok_or(1u32)?;
+
+    let pending = PendingNcclAsyncError {
+        comm_ptr,
+        async_err_ptr,
+        start_ns: unsafe { bpf_ktime_get_ns() },
+    };
+
+    PENDING_NCCL_ASYNC_ERROR
+        .insert(&tid, &pending, 0)
+        .map_err(|_| 1u32)?;
+
+    Ok(())
+}
+
+#[uretprobe]
+pub fn nccl_comm_get_async_error_exit(ctx: RetProbeContext) -> u32 {
+    match try_nccl_comm_get_async_error_exit(&ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_nccl_comm_get_async_error_exit(ctx: &RetProbeContext) -> Result<(), u32> {
+    let tid = bpf_get_current_pid_tgid();
+
+    let pending = unsafe { PENDING_NCCL_ASYNC_ERROR.get(&tid).copied().ok_or(1u32)? };
+    let _ = PENDING_NCCL_ASYNC_ERROR.remove(&tid);
+
+    let now = unsafe { bpf_ktime_get_ns() };
+    let duration_ns = now.saturating_sub(pending.start_ns);
+
+    // A failed query leaves *asyncError undefined, so a read failure is
+    // reported the same as "nothing to report" (ncclSuccess).
+    let async_error: i32 = unsafe {
+        aya_ebpf::helpers::bpf_probe_read_user(pending.async_err_ptr as *const i32).unwrap_or(0)
+    };
+
+    let Some(mut slot) = NCCL_EVENTS.reserve::<NcclEvent>(0) else {
+        return Err(1);
+    };
+    let event = unsafe { &mut *slot.as_mut_ptr() };
+
+    if event.fill(ctx).is_err() {
+        slot.discard(0);
+        return Err(1);
+    }
+
+    event.op_type = NcclOpType::GetAsyncError as u8;
+    // For this op, ret_code carries the *async* error code read from the
+    // out-parameter rather than ncclCommGetAsyncError's own return value -
+    // the async error is the entire point of calling this function.
+    event.ret_code = async_error;
+    event.count = 0;
+    event.datatype_size = 0;
+    event.duration_ns = duration_ns;
+    event.bytes_transferred = 0;
+    event.comm_ptr = pending.comm_ptr;
+    event.stream_ptr = 0;
+    event.group_id = 0;
+    event.comm = bpf_get_current_comm().unwrap_or([0u8; 16]);
+
+    slot.submit(0);
+    Ok(())
+}
+
 // ===== Common entry logic =====
 
 /// Entry for collective operations with count and datatype args
-/// (AllReduce, Broadcast, AllGather, ReduceScatter)
+/// (AllReduce, Broadcast, AllGather, ReduceScatter). `comm_arg_index` is the
+/// position of the `ncclComm_t comm` argument, which varies by function
+/// (see each uprobe's signature comment above); `stream` always immediately
+/// follows `comm`.
 #[inline(always)]
-fn try_nccl_entry(ctx: &ProbeContext, op_type: u8, _has_count: bool) -> Result<(), u32> {
+fn try_nccl_entry(ctx: &ProbeContext, op_type: u8, comm_arg_index: usize) -> Result<(), u32> {
     let tid = bpf_get_current_pid_tgid();
 
     // For collective ops: arg2 = count, arg3 = datatype
     let count: u64 = ctx.arg(2).unwrap_or(0);
     let datatype: u32 = ctx.arg(3).unwrap_or(0);
     let datatype_size = nccl_datatype_to_bytes(datatype);
+    let comm_ptr: u64 = ctx.arg(comm_arg_index).unwrap_or(0);
+    let stream_ptr: u64 = ctx.arg(comm_arg_index + 1).unwrap_or(0);
 
     let pending = PendingNcclOp {
         op_type,
@@ -199,6 +502,9 @@ fn try_nccl_entry(ctx: &ProbeContext, op_type: u8, _has_count: bool) -> Result<(
         count,
         datatype_size,
         _pad2: 0,
+        comm_ptr,
+        stream_ptr,
+        group_id: current_group_id(tid),
         start_ns: unsafe { bpf_ktime_get_ns() },
     };
 
@@ -209,7 +515,9 @@ fn try_nccl_entry(ctx: &ProbeContext, op_type: u8, _has_count: bool) -> Result<(
     Ok(())
 }
 
-/// Entry for P2P operations (Send, Recv) - count is arg1, datatype is arg2
+/// Entry for P2P operations (Send, Recv) - count is arg1, datatype is arg2,
+/// comm is arg4, stream is arg5:
+/// `ncclSend/Recv(buff, count, datatype, peer, comm, stream)`
 #[inline(always)]
 fn try_nccl_entry_p2p(ctx: &ProbeContext, op_type: u8) -> Result<(), u32> {
     let tid = bpf_get_current_pid_tgid();
@@ -217,6 +525,8 @@ fn try_nccl_entry_p2p(ctx: &ProbeContext, op_type: u8) -> Result<(), u32> {
     let count: u64 = ctx.arg(1).unwrap_or(0);
     let datatype: u32 = ctx.arg(2).unwrap_or(0);
     let datatype_size = nccl_datatype_to_bytes(datatype);
+    let comm_ptr: u64 = ctx.arg(4).unwrap_or(0);
+    let stream_ptr: u64 = ctx.arg(5).unwrap_or(0);
 
     let pending = PendingNcclOp {
         op_type,
@@ -224,6 +534,9 @@ fn try_nccl_entry_p2p(ctx: &ProbeContext, op_type: u8) -> Result<(), u32> {
         count,
         datatype_size,
         _pad2: 0,
+        comm_ptr,
+        stream_ptr,
+        group_id: current_group_id(tid),
         start_ns: unsafe { bpf_ktime_get_ns() },
     };
 
@@ -246,6 +559,35 @@ fn try_nccl_entry_simple(ctx: &ProbeContext, op_type: u8) -> Result<(), u32> {
         count: 0,
         datatype_size: 0,
         _pad2: 0,
+        comm_ptr: 0,
+        stream_ptr: 0,
+        group_id: current_group_id(tid),
+        start_ns: unsafe { bpf_ktime_get_ns() },
+    };
+
+    PENDING_NCCL_OPS
+        .insert(&tid, &pending, 0)
+        .map_err(|_| 1u32)?;
+
+    Ok(())
+}
+
+/// Entry for ops that carry only a `ncclComm_t comm` argument at index 0 and
+/// no count/datatype (CommAbort).
+#[inline(always)]
+fn try_nccl_entry_comm_only(ctx: &ProbeContext, op_type: u8) -> Result<(), u32> {
+    let tid = bpf_get_current_pid_tgid();
+    let comm_ptr: u64 = ctx.arg(0).unwrap_or(0);
+
+    let pending = PendingNcclOp {
+        op_type,
+        _pad: [0; 3],
+        count: 0,
+        datatype_size: 0,
+        _pad2: 0,
+        comm_ptr,
+        stream_ptr: 0,
+        group_id: current_group_id(tid),
         start_ns: unsafe { bpf_ktime_get_ns() },
     };
 
@@ -294,6 +636,9 @@ fn emit_nccl_event(ctx: &RetProbeContext, op_type: u8) -> u32 {
         event.datatype_size = pending.datatype_size;
         event.duration_ns = duration_ns;
         event.bytes_transferred = pending.count * pending.datatype_size as u64;
+        event.comm_ptr = pending.comm_ptr;
+        event.stream_ptr = pending.stream_ptr;
+        event.group_id = pending.group_id;
         event.comm = bpf_get_current_comm().unwrap_or([0u8; 16]);
 
         slot.submit(0);
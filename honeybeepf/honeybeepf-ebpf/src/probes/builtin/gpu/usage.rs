@@ -11,7 +11,7 @@ use aya_ebpf::{
 };
 use honeybeepf_common::{EventMetadata, GpuCloseEvent, GpuFdInfo, GpuOpenEvent, PendingGpuOpen};
 
-use super::utils::get_gpu_index;
+use super::utils::get_gpu_info;
 use crate::probes::{
     HoneyBeeEvent,
     builtin::syscall_types::{SysEnterClose, SysEnterOpenat, SysExitOpenat},
@@ -94,9 +94,13 @@ fn try_gpu_open_enter(ctx: &TracePointContext) -> Result<(), u32> {
             .len()
     };
 
-    // Check if GPU device
-    let gpu_index = get_gpu_index(&filename_buf[..filename_len]);
-    if gpu_index < 0 {
+    // Check if GPU device and classify its vendor. /dev/kfd (Amd) has no
+    // per-GPU index, so only reject unmatched paths or negative indices on
+    // indexed device classes (e.g. /dev/nvidiactl, /dev/nvidia-uvm).
+    let (gpu_index, vendor) = get_gpu_info(&filename_buf[..filename_len]);
+    if vendor == super::utils::GpuVendor::Unknown
+        || (vendor != super::utils::GpuVendor::Amd && gpu_index < 0)
+    {
         return Err(EmitGpuStatus::NotGpuDevice as u32);
     }
 
@@ -110,6 +114,7 @@ fn try_gpu_open_enter(ctx: &TracePointContext) -> Result<(), u32> {
     let tid = ctx.tgid() as u64;
     let pending = PendingGpuOpen {
         gpu_index,
+        vendor: vendor as u8,
         flags: flags as i32,
         filename: filename_buf,
     };
@@ -157,10 +162,11 @@ fn try_gpu_open_exit(ctx: &TracePointContext) -> Result<(), u32> {
 
     let pid = ctx.tgid();
 
-    // Store fd -> gpu_index mapping for close tracking
+    // Store fd -> gpu_index/vendor mapping for close tracking
     let fd_key = ((pid as u64) << 32) | (fd as u32 as u64);
     let fd_info = GpuFdInfo {
         gpu_index: pending.gpu_index,
+        vendor: pending.vendor,
         _pad: 0,
     };
     let _ = GPU_FD_MAP.insert(&fd_key, &fd_info, 0);
@@ -175,6 +181,7 @@ fn try_gpu_open_exit(ctx: &TracePointContext) -> Result<(), u32> {
         }
 
         event.gpu_index = pending.gpu_index;
+        event.vendor = pending.vendor;
         event.fd = fd as i32;
         event.flags = pending.flags;
         event.comm = bpf_get_current_comm().unwrap_or([0u8; 16]);
@@ -222,6 +229,7 @@ fn try_gpu_close(ctx: &TracePointContext) -> Result<(), u32> {
 
     // Remove from GPU fd map
     let _ = GPU_FD_MAP.remove(&fd_key);
+    super::mem::clear_gpu_mem_totals(pid, gpu_index);
 
     // Emit GPU close event
     if let Some(mut slot) = GPU_CLOSE_EVENTS.reserve::<GpuCloseEvent>(0) {
@@ -0,0 +1,117 @@
+//! Per-process GPU utilization tracking via DRM scheduler / dma-fence tracepoints.
+//!
+//! Attaches to the fence-submit and fence-signal tracepoints to measure how
+//! long each in-flight job keeps the GPU busy, keyed by fence identity
+//! `(context, seqno)`. Userspace (`GpuUtilProbe::spawn_event_handler`, mirroring
+//! `OffCpuProbe`) periodically drains `GPU_BUSY_NS` over a wall-clock window to
+//! derive a rolling utilization percentage.
+
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_comm, bpf_ktime_get_ns},
+    macros::{map, tracepoint},
+    maps::HashMap,
+    programs::TracePointContext,
+};
+use honeybeepf_common::GpuAccKey;
+
+const MAX_IN_FLIGHT_FENCES: u32 = 10240;
+const MAX_GPU_ACCUMULATORS: u32 = 1024;
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct FenceStart {
+    start_ns: u64,
+    tgid: u32,
+    cgroup_id: u64,
+    comm: [u8; 16],
+}
+
+/// In-flight fences, keyed by `(context_id << 32) | seqno`.
+/// Bounded by `MAX_IN_FLIGHT_FENCES`; a full map just drops the start record,
+/// which is caught and skipped on signal (stale/unknown entries are ignored).
+#[map]
+static GPU_FENCE_START: HashMap<u64, FenceStart> = HashMap::with_max_entries(MAX_IN_FLIGHT_FENCES, 0);
+
+/// Accumulated busy nanoseconds, drained by userspace over a wall-clock
+/// window without per-event overhead.
+#[map]
+pub static GPU_BUSY_NS: HashMap<GpuAccKey, u64> = HashMap::with_max_entries(MAX_GPU_ACCUMULATORS, 0);
+
+#[repr(C)]
+struct DmaFenceTrace {
+    common_type: u16,
+    common_flags: u8,
+    common_preempt_count: u8,
+    common_pid: i32,
+    driver: [u8; 32],
+    timeline: [u8; 32],
+    context: u32,
+    seqno: u32,
+}
+
+#[inline(always)]
+fn fence_key(ctx_id: u32, seqno: u32) -> u64 {
+    ((ctx_id as u64) << 32) | seqno as u64
+}
+
+fn read_fence(ctx: &TracePointContext) -> Result<(u32, u32), u32> {
+    let header_ptr = ctx.as_ptr() as *const DmaFenceTrace;
+    unsafe {
+        let context = aya_ebpf::helpers::bpf_probe_read_kernel(&((*header_ptr).context) as *const u32)
+            .map_err(|_| 1u32)?;
+        let seqno = aya_ebpf::helpers::bpf_probe_read_kernel(&((*header_ptr).seqno) as *const u32)
+            .map_err(|_| 1u32)?;
+        Ok((context, seqno))
+    }
+}
+
+/// Job submitted to the GPU scheduler: record a start timestamp for this fence.
+#[tracepoint]
+pub fn honeybeepf_gpu_job_submit(ctx: TracePointContext) -> u32 {
+    let (context, seqno) = match read_fence(&ctx) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    let start = FenceStart {
+        start_ns: unsafe { bpf_ktime_get_ns() },
+        tgid: ctx.tgid(),
+        cgroup_id: unsafe { aya_ebpf::helpers::bpf_get_current_cgroup_id() },
+        comm: bpf_get_current_comm().unwrap_or([0u8; 16]),
+    };
+
+    let _ = GPU_FENCE_START.insert(&fence_key(context, seqno), &start, 0);
+    0
+}
+
+/// Fence signaled (job completed): compute busy time and add it into the
+/// per-(cgroup, pid) accumulator.
+#[tracepoint]
+pub fn honeybeepf_gpu_job_complete(ctx: TracePointContext) -> u32 {
+    let (context, seqno) = match read_fence(&ctx) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    let key = fence_key(context, seqno);
+    // Job may have started before this probe attached; skip rather than guess.
+    let start = match unsafe { GPU_FENCE_START.get(&key) } {
+        Some(s) => *s,
+        None => return 0,
+    };
+    let _ = GPU_FENCE_START.remove(&key);
+
+    let now = unsafe { bpf_ktime_get_ns() };
+    let busy_ns = now.saturating_sub(start.start_ns);
+
+    let acc_key = GpuAccKey {
+        cgroup_id: start.cgroup_id,
+        pid: start.tgid,
+        _pad: 0,
+    };
+    let prev = unsafe { GPU_BUSY_NS.get(&acc_key).copied().unwrap_or(0) };
+    let _ = GPU_BUSY_NS.insert(&acc_key, &(prev + busy_ns), 0);
+
+    0
+}
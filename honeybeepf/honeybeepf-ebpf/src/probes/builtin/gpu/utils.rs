@@ -5,6 +5,22 @@
 pub const NVIDIA_PREFIX: &[u8] = b"/dev/nvidia";
 pub const DRI_RENDER_PREFIX: &[u8] = b"/dev/dri/renderD";
 pub const DRI_CARD_PREFIX: &[u8] = b"/dev/dri/card";
+/// AMD's ROCm/KFD compute device. Unlike render nodes, it carries no
+/// per-GPU index in the path (a single node multiplexes all AMD GPUs).
+pub const KFD_PATH: &[u8] = b"/dev/kfd";
+
+/// Coarse GPU vendor classification derived purely from the device path.
+/// eBPF can't read `/sys/class/drm/*/device/vendor` to get a real PCI vendor
+/// ID, so DRI render/card nodes (used by AMD, Intel, Apple/Asahi, nouveau,
+/// etc.) are reported as `DriGeneric` rather than guessed at.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GpuVendor {
+    Nvidia = 0,
+    Amd = 1,
+    DriGeneric = 2,
+    Unknown = 3,
+}
 
 pub fn starts_with(filename: &[u8], prefix: &[u8]) -> bool {
     if filename.len() < prefix.len() {
@@ -75,13 +91,29 @@ fn extract_dri_gpu_index(filename: &[u8]) -> i32 {
 /// Check if a filename is a GPU device and return the GPU index
 /// Returns -1 if not a GPU device
 pub fn get_gpu_index(filename: &[u8]) -> i32 {
+    get_gpu_info(filename).0
+}
+
+/// Classify a device path as a GPU device, returning both its index (-1 if
+/// not applicable, e.g. `/dev/kfd`) and a coarse vendor tag.
+/// Returns `(-1, GpuVendor::Unknown)` if the path isn't a GPU device at all.
+pub fn get_gpu_info(filename: &[u8]) -> (i32, GpuVendor) {
     if starts_with(filename, NVIDIA_PREFIX) {
-        return extract_nvidia_gpu_index(filename);
+        return (extract_nvidia_gpu_index(filename), GpuVendor::Nvidia);
+    }
+
+    if starts_with(filename, KFD_PATH)
+        && (filename.len() == KFD_PATH.len() || filename[KFD_PATH.len()] == 0)
+    {
+        // /dev/kfd has no per-GPU index; ROCm enumerates GPUs via ioctl.
+        return (-1, GpuVendor::Amd);
     }
 
     if starts_with(filename, DRI_CARD_PREFIX) || starts_with(filename, DRI_RENDER_PREFIX) {
-        return extract_dri_gpu_index(filename);
+        // Covers AMD (amdgpu), Intel, Apple/Asahi, nouveau, and any other
+        // DRM driver's render/card nodes alike.
+        return (extract_dri_gpu_index(filename), GpuVendor::DriGeneric);
     }
 
-    -1
+    (-1, GpuVendor::Unknown)
 }
@@ -3,7 +3,7 @@ use aya_ebpf::{
     programs::RetProbeContext,
 };
 use honeybeepf_common::{LlmEvent, MAX_SSL_BUF_SIZE};
-use crate::probes::builtin::llm::maps::{START_NS, BUFS, READBYTES_PTRS};
+use crate::probes::builtin::llm::maps::{START_NS, BUFS, HANDLES, READBYTES_PTRS};
 
 #[inline(always)]
 pub fn get_current_tid() -> u32 {
@@ -14,9 +14,10 @@ pub struct Session;
 
 impl Session {
     #[inline(always)]
-    pub fn start(tid: u32, buf_addr: u64, len_ptr: Option<u64>) {
+    pub fn start(tid: u32, handle: u64, buf_addr: u64, len_ptr: Option<u64>) {
         let ts = unsafe { bpf_ktime_get_ns() };
         let _ = START_NS.insert(&tid, &ts, 0);
+        let _ = HANDLES.insert(&tid, &handle, 0);
         let _ = BUFS.insert(&tid, &buf_addr, 0);
         if let Some(lp) = len_ptr {
             let _ = READBYTES_PTRS.insert(&tid, &lp, 0);
@@ -24,18 +25,20 @@ impl Session {
     }
 
     #[inline(always)]
-    pub fn get_info(tid: u32) -> (u64, u64, Option<u64>) {
+    pub fn get_info(tid: u32) -> (u64, u64, u64, Option<u64>) {
         unsafe {
             let ts = START_NS.get(&tid).copied().unwrap_or(0);
+            let handle = HANDLES.get(&tid).copied().unwrap_or(0);
             let buf = BUFS.get(&tid).copied().unwrap_or(0);
             let len_ptr = READBYTES_PTRS.get(&tid).copied();
-            (ts, buf, len_ptr)
+            (ts, handle, buf, len_ptr)
         }
     }
 
     #[inline(always)]
     pub fn clear(tid: u32) {
         let _ = START_NS.remove(&tid);
+        let _ = HANDLES.remove(&tid);
         let _ = BUFS.remove(&tid);
         let _ = READBYTES_PTRS.remove(&tid);
     }
@@ -49,15 +52,21 @@ impl LlmEventExt for LlmEvent {
     #[inline(always)]
     fn capture_data(&mut self, ctx: &RetProbeContext, rw: u8, is_handshake: bool) -> Result<(), u32> {
         let tid = get_current_tid();
-        let (start_ts, buf_addr, len_ptr) = Session::get_info(tid);
+        let (start_ts, handle, buf_addr, len_ptr) = Session::get_info(tid);
 
         // Initialize metadata directly to avoid circular dependency with HoneyBeeEvent trait.
         let pid_tgid = bpf_get_current_pid_tgid();
         self.metadata.pid = (pid_tgid >> 32) as u32;
-        self.metadata._pad = pid_tgid as u32; // tid for userspace per-thread keying
+        self.metadata._pad = pid_tgid as u32; // tid, kept for logging only - see ssl_handle for connection keying
         self.metadata.timestamp = unsafe { bpf_ktime_get_ns() };
         self.metadata.cgroup_id = unsafe { aya_ebpf::helpers::bpf_get_current_cgroup_id() };
 
+        // The `SSL *` handle, not tid, identifies the connection: a single
+        // thread (e.g. a single-threaded async event loop) can multiplex
+        // many SSL connections, so userspace keys reassembly on this
+        // instead of `metadata._pad`.
+        self.ssl_handle = handle;
+
         self.rw = rw;
         self.is_handshake = if is_handshake { 1 } else { 0 };
         self.latency_ns = if start_ts > 0 { self.metadata.timestamp - start_ts } else { 0 };
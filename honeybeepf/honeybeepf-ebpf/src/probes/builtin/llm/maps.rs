@@ -16,5 +16,13 @@ pub static START_NS: HashMap<u32, u64> = HashMap::with_max_entries(MAX_ENTRIES,
 #[map]
 pub static BUFS: HashMap<u32, u64> = HashMap::with_max_entries(MAX_ENTRIES, 0);
 
+/// `SSL *` handle (arg0 of `SSL_read`/`SSL_write`/`SSL_do_handshake`),
+/// staged at entry so the matching exit probe can tag its event with the
+/// connection it belongs to - see `LlmEvent::ssl_handle`. Userspace keys
+/// connections on this instead of tid, since one thread can multiplex many
+/// SSL connections (e.g. a single-threaded async event loop).
+#[map]
+pub static HANDLES: HashMap<u32, u64> = HashMap::with_max_entries(MAX_ENTRIES, 0);
+
 #[map]
 pub static READBYTES_PTRS: HashMap<u32, u64> = HashMap::with_max_entries(MAX_ENTRIES, 0);
@@ -24,12 +24,13 @@ pub mod maps;
 use helpers::{LlmEventExt, Session, get_current_tid};
 use maps::*;
 
-/// Entry probe for SSL_read/SSL_write. Captures buffer pointer from arg1.
-/// Session::start overwrites any existing entry, so no clear() needed.
+/// Entry probe for SSL_read/SSL_write. Captures the `SSL *` handle (arg0)
+/// and buffer pointer (arg1). Session::start overwrites any existing entry,
+/// so no clear() needed.
 #[uprobe]
 pub fn probe_ssl_rw_enter(ctx: ProbeContext) -> u32 {
     let tid = get_current_tid();
-    Session::start(tid, ctx.arg(1).unwrap_or(0), None);
+    Session::start(tid, ctx.arg(0).unwrap_or(0), ctx.arg(1).unwrap_or(0), None);
     0
 }
 
@@ -50,7 +51,12 @@ pub fn probe_ssl_write_exit(ctx: RetProbeContext) -> u32 {
 #[uprobe]
 pub fn probe_ssl_rw_ex_enter(ctx: ProbeContext) -> u32 {
     let tid = get_current_tid();
-    Session::start(tid, ctx.arg(1).unwrap_or(0), Some(ctx.arg(3).unwrap_or(0)));
+    Session::start(
+        tid,
+        ctx.arg(0).unwrap_or(0),
+        ctx.arg(1).unwrap_or(0),
+        Some(ctx.arg(3).unwrap_or(0)),
+    );
     0
 }
 
@@ -66,11 +72,12 @@ pub fn probe_ssl_write_ex_exit(ctx: RetProbeContext) -> u32 {
     emit_llm_event(&ctx, LlmDirection::Write as u8, false)
 }
 
-/// Entry probe for SSL_do_handshake - captures start time for latency.
+/// Entry probe for SSL_do_handshake - captures the `SSL *` handle (arg0) and
+/// start time for latency.
 #[uprobe]
-pub fn probe_ssl_do_handshake_enter(_ctx: ProbeContext) -> u32 {
+pub fn probe_ssl_do_handshake_enter(ctx: ProbeContext) -> u32 {
     let tid = get_current_tid();
-    Session::start(tid, 0, None);
+    Session::start(tid, ctx.arg(0).unwrap_or(0), 0, None);
     0
 }
 
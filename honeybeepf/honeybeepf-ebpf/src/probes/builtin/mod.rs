@@ -0,0 +1,11 @@
+//! Built-in eBPF probes, one module per subsystem.
+
+pub mod block_io;
+pub mod exec_watch;
+pub mod file_access;
+pub mod gpu;
+pub mod llm;
+pub mod process_lifecycle;
+pub mod runqueue;
+pub mod syscall_types;
+pub mod vfs_latency;
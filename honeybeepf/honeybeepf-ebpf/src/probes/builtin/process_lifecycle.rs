@@ -0,0 +1,51 @@
+//! Process lifecycle tracepoints feeding the Kubernetes pod resolver.
+//!
+//! Unlike `exec_watch`'s PID-only notifications (used only to trigger
+//! targeted SSL library re-discovery), these events carry enough identity
+//! (`tgid`, `cgroup_id`, `comm`) for userspace to eagerly prime or evict its
+//! cgroup-to-pod cache in response to real process lifecycle transitions
+//! instead of lazily reading `/proc` on cache miss and polling for
+//! staleness. `sched_process_exec` fires once per new process image;
+//! `sched_process_exit` fires once per exiting task, which the refcounting
+//! on the userspace side needs since several threads can share one
+//! `cgroup_id`.
+
+use aya_ebpf::{
+    macros::{map, tracepoint},
+    maps::RingBuf,
+    programs::TracePointContext,
+    helpers::{bpf_get_current_pid_tgid, bpf_get_current_cgroup_id, bpf_get_current_comm},
+};
+use honeybeepf_common::ProcessEvent;
+
+const MAX_EVENT_SIZE: u32 = 1024 * 1024;
+
+#[map]
+pub static PROCESS_EVENTS: RingBuf = RingBuf::with_byte_size(MAX_EVENT_SIZE, 0);
+
+/// Tracepoint for sched_process_exec - fires when a process calls exec().
+#[tracepoint]
+pub fn probe_process_exec(_ctx: TracePointContext) -> u32 {
+    emit(false);
+    0
+}
+
+/// Tracepoint for sched_process_exit - fires when a task exits.
+#[tracepoint]
+pub fn probe_process_exit(_ctx: TracePointContext) -> u32 {
+    emit(true);
+    0
+}
+
+fn emit(is_exit: bool) {
+    if let Some(mut slot) = PROCESS_EVENTS.reserve::<ProcessEvent>(0) {
+        let event = unsafe { &mut *slot.as_mut_ptr() };
+        let pid_tgid = bpf_get_current_pid_tgid();
+        event.pid = (pid_tgid & 0xFFFF_FFFF) as u32;
+        event.tgid = (pid_tgid >> 32) as u32;
+        event.cgroup_id = unsafe { bpf_get_current_cgroup_id() };
+        event.comm = bpf_get_current_comm().unwrap_or([0; 16]);
+        event.is_exit = is_exit as u8;
+        slot.submit(0);
+    }
+}
@@ -0,0 +1,137 @@
+//! Run queue latency tracepoints.
+//!
+//! Measures the time a task spends runnable-but-not-running: `sched_wakeup`
+//! and `sched_wakeup_new` stamp an enqueue timestamp per pid, and
+//! `sched_switch` computes `now - enqueue_ts` for the task being switched in.
+//! An event is emitted only when that latency exceeds the configured
+//! threshold.
+//!
+//! Tracepoint argument offsets below follow the common `trace_event_raw_*`
+//! layouts (8-byte common header, then the fields in declaration order) -
+//! same approach as the `struct file`/`inode`/`dentry` offsets in
+//! `vfs_latency.rs`; a kernel that reorders these fields would need the
+//! offsets updated.
+
+use aya_ebpf::{
+    helpers::{bpf_get_current_cgroup_id, bpf_ktime_get_ns},
+    macros::{map, tracepoint},
+    maps::{HashMap, RingBuf},
+    programs::TracePointContext,
+};
+use honeybeepf_common::RunqueueEvent;
+
+const MAX_EVENT_SIZE: u32 = 1024 * 1024;
+const MAX_ENTRIES: u32 = 10240;
+
+/// Default threshold in nanoseconds (10ms)
+const DEFAULT_THRESHOLD_NS: u64 = 10_000_000;
+
+/// `trace_event_raw_sched_wakeup{,_new}`: 8-byte common header, then
+/// `comm[16]`, `pid` (the field of interest here).
+const WAKEUP_PID_OFFSET: usize = 8 + 16;
+
+/// `trace_event_raw_sched_switch`: 8-byte common header, then
+/// `prev_comm[16]`, `prev_pid`, `prev_prio`, `prev_state` (long), then
+/// `next_comm[16]`, `next_pid`.
+const SWITCH_PREV_PID_OFFSET: usize = 8 + 16;
+const SWITCH_NEXT_COMM_OFFSET: usize = 8 + 16 + 4 + 4 + 8;
+const SWITCH_NEXT_PID_OFFSET: usize = SWITCH_NEXT_COMM_OFFSET + 16;
+
+#[map]
+pub static RUNQUEUE_EVENTS: RingBuf = RingBuf::with_byte_size(MAX_EVENT_SIZE, 0);
+
+/// Configurable threshold in nanoseconds.
+#[map]
+pub static RUNQUEUE_THRESHOLD_NS: HashMap<u32, u64> = HashMap::with_max_entries(1, 0);
+
+/// Enqueue timestamp per pid, set on wakeup and consumed (and cleared) on
+/// the next `sched_switch` that runs it. Keyed strictly on pid rather than
+/// CPU, since the wakeup and the eventual switch-in can land on different
+/// CPUs.
+#[map]
+static RUNQUEUE_ENQUEUED: HashMap<u32, u64> = HashMap::with_max_entries(MAX_ENTRIES, 0);
+
+#[tracepoint]
+pub fn sched_wakeup(ctx: TracePointContext) -> u32 {
+    match try_wakeup(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[tracepoint]
+pub fn sched_wakeup_new(ctx: TracePointContext) -> u32 {
+    match try_wakeup(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[tracepoint]
+pub fn sched_switch(ctx: TracePointContext) -> u32 {
+    match try_switch(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[inline(always)]
+fn try_wakeup(ctx: &TracePointContext) -> Result<u32, u32> {
+    let pid: u32 = unsafe { ctx.read_at(WAKEUP_PID_OFFSET).map_err(|_| 1u32)? };
+    let now = unsafe { bpf_ktime_get_ns() };
+
+    RUNQUEUE_ENQUEUED.insert(&pid, &now, 0).map_err(|_| 1u32)?;
+
+    Ok(0)
+}
+
+#[inline(always)]
+fn try_switch(ctx: &TracePointContext) -> Result<u32, u32> {
+    let prev_pid: u32 = unsafe { ctx.read_at(SWITCH_PREV_PID_OFFSET).map_err(|_| 1u32)? };
+    let next_comm: [u8; 16] = unsafe { ctx.read_at(SWITCH_NEXT_COMM_OFFSET).map_err(|_| 1u32)? };
+    let next_pid: u32 = unsafe { ctx.read_at(SWITCH_NEXT_PID_OFFSET).map_err(|_| 1u32)? };
+
+    // Look up and clear the enqueue timestamp so a stale entry can't be
+    // matched against a later, unrelated wakeup of the same pid.
+    let enqueue_ts = match unsafe { RUNQUEUE_ENQUEUED.get(&next_pid) } {
+        Some(ts) => *ts,
+        None => return Ok(0),
+    };
+    let _ = RUNQUEUE_ENQUEUED.remove(&next_pid);
+
+    let now = unsafe { bpf_ktime_get_ns() };
+    let latency_ns = now.saturating_sub(enqueue_ts);
+
+    let threshold = unsafe { RUNQUEUE_THRESHOLD_NS.get(&0) }
+        .copied()
+        .unwrap_or(DEFAULT_THRESHOLD_NS);
+
+    if latency_ns < threshold {
+        return Ok(0);
+    }
+
+    let cgroup_id = unsafe { bpf_get_current_cgroup_id() };
+    let cpu = unsafe { aya_ebpf::helpers::bpf_get_smp_processor_id() };
+
+    let mut entry = match RUNQUEUE_EVENTS.reserve::<RunqueueEvent>(0) {
+        Some(entry) => entry,
+        None => return Ok(0),
+    };
+
+    let event = RunqueueEvent {
+        pid: next_pid,
+        tid: next_pid,
+        cpu,
+        prev_pid,
+        latency_ns,
+        cgroup_id,
+        comm: next_comm,
+    };
+
+    unsafe {
+        core::ptr::write_unaligned(entry.as_mut_ptr(), event);
+    }
+    entry.submit(0);
+
+    Ok(0)
+}
@@ -1,22 +1,31 @@
 //! VFS latency kprobes for monitoring slow/large file system operations.
 //!
 //! Attaches to vfs_read and vfs_write to measure I/O latency.
-//! 
-//! For vfs_read, events are emitted only when:
-//! - It's a regular file (not socket/pipe)
-//! - AND (bytes >= MIN_BYTES OR latency >= threshold)
 //!
-//! For vfs_write, events are emitted when latency exceeds the configured threshold.
+//! Each operation is gated by its own `VfsOpConfig` (`VFS_OP_CONFIG`, keyed
+//! by op type), so reads and writes can be tuned independently: an event is
+//! emitted when `bytes >= min_bytes_threshold OR latency_ns >=
+//! latency_ns_threshold`, optionally restricted to regular files only. By
+//! default this reproduces the probe's original behavior - reads require a
+//! regular file and default to a 1MB/10ms floor, writes are latency-only
+//! (10ms, no byte floor, any file type) - but either can be retuned at
+//! runtime via `VfsLatencyProbe` (userspace side).
+
+use core::sync::atomic::{AtomicU64, Ordering};
 
 use aya_ebpf::{
     helpers::{
-        bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_ktime_get_ns, bpf_probe_read_kernel,
+        bpf_get_current_cgroup_id, bpf_get_current_comm, bpf_get_current_pid_tgid,
+        bpf_ktime_get_ns, bpf_probe_read_kernel,
     },
-    macros::{kprobe, kretprobe, map},
-    maps::{HashMap, RingBuf},
-    programs::{ProbeContext, RetProbeContext},
+    macros::{fentry, fexit, kprobe, kretprobe, map},
+    maps::{Array, HashMap, RingBuf},
+    programs::{FEntryContext, FExitContext, ProbeContext, RetProbeContext},
+};
+use honeybeepf_common::{
+    CgroupAggBucket, HIST_NUM_BUCKETS, MAX_FILENAME_LEN, VfsFieldOffsets, VfsLatencyEvent,
+    VfsOpConfig, latency_bucket,
 };
-use honeybeepf_common::{MAX_FILENAME_LEN, VfsLatencyEvent};
 
 const MAX_EVENT_SIZE: u32 = 1024 * 1024;
 const MAX_ENTRIES: u32 = 10240;
@@ -27,6 +36,9 @@ const DEFAULT_THRESHOLD_NS: u64 = 10_000_000;
 /// Minimum bytes for read to be interesting (1MB)
 const MIN_READ_BYTES: u64 = 1024 * 1024;
 
+/// Max distinct cgroups tracked at once in `VFS_AGG`.
+const MAX_CGROUP_ACCUMULATORS: u32 = 4096;
+
 /// VFS operation type constants
 const VFS_OP_READ: u8 = 0;
 const VFS_OP_WRITE: u8 = 1;
@@ -39,13 +51,50 @@ const S_IFMT: u16 = 0o170000;
 pub static VFS_EVENTS: RingBuf = RingBuf::with_byte_size(MAX_EVENT_SIZE, 0);
 
 /// Map to store start time and context for in-flight operations.
-/// Key: tid (thread id), Value: (start_time_ns, op_type, file_ptr)
+/// Key: tid (thread id), Value: (start_time_ns, op_type, file_ptr, pos_ptr)
+#[map]
+static VFS_START: HashMap<u32, (u64, u8, u64, u64)> = HashMap::with_max_entries(MAX_ENTRIES, 0);
+
+/// Per-operation config, keyed by op type (`VFS_OP_READ`/`VFS_OP_WRITE`) -
+/// see `VfsOpConfig`.
+#[map]
+pub static VFS_OP_CONFIG: HashMap<u8, VfsOpConfig> = HashMap::with_max_entries(2, 0);
+
+/// Per-cgroup running totals, updated on every successful vfs_read/vfs_write
+/// exit regardless of `VFS_AGGREGATE_MODE`, but not while
+/// `VFS_HISTOGRAM_MODE` is set. Swept and zeroed periodically by
+/// `VfsLatencyProbe`'s flush task in userspace.
+#[map]
+pub static VFS_AGG: HashMap<u64, CgroupAggBucket> =
+    HashMap::with_max_entries(MAX_CGROUP_ACCUMULATORS, 0);
+
+/// When set to 1, exit handlers skip the per-event `VFS_EVENTS` ring buffer
+/// emit entirely and rely solely on `VFS_AGG`.
+#[map]
+pub static VFS_AGGREGATE_MODE: HashMap<u32, u8> = HashMap::with_max_entries(1, 0);
+
+/// Log2 latency histogram, flattened as `[op_type][bucket]`. Indexed by
+/// `op_type as u32 * HIST_NUM_BUCKETS + bucket` - see `latency_bucket` in
+/// `honeybeepf-common`. Counters are incremented atomically since, unlike
+/// `VFS_AGG`, a dropped increment here would visibly skew the exported
+/// distribution rather than just a best-effort running total.
+#[map]
+pub static VFS_HIST: Array<u64> = Array::with_max_entries((2 * HIST_NUM_BUCKETS) as u32, 0);
+
+/// When set to 1, exit handlers skip both `VFS_EVENTS` and `VFS_AGG` and
+/// only bump `VFS_HIST` - the cheapest mode, for callers who only need a
+/// steady-state latency distribution rather than individual events or
+/// per-cgroup totals.
 #[map]
-static VFS_START: HashMap<u32, (u64, u8, u64)> = HashMap::with_max_entries(MAX_ENTRIES, 0);
+pub static VFS_HISTOGRAM_MODE: HashMap<u32, u8> = HashMap::with_max_entries(1, 0);
 
-/// Configurable threshold in nanoseconds
+/// `struct file`/`struct inode` field offsets resolved from the running
+/// kernel's BTF at attach time (`resolve_vfs_field_offsets`, userspace side).
+/// `is_regular_file`/`read_filename_from_file` prefer these over their
+/// compiled-in constants whenever `configured` is set, so a single build
+/// stays correct across kernels whose struct layouts have shifted.
 #[map]
-pub static VFS_THRESHOLD_NS: HashMap<u32, u64> = HashMap::with_max_entries(1, 0);
+pub static VFS_OFFSETS: Array<VfsFieldOffsets> = Array::with_max_entries(1, 0);
 
 // ============================================================
 // vfs_read probes (filtered: regular files + large/slow only)
@@ -93,35 +142,194 @@ pub fn vfs_write_exit(ctx: RetProbeContext) -> u32 {
     }
 }
 
+// ============================================================
+// vfs_read / vfs_write fentry/fexit probes (fast path)
+//
+// On kernels with BTF and trampoline support, userspace
+// (`attach_trace_pair`) prefers these over the kprobe/kretprobe pair above:
+// fentry/fexit hook through a generated trampoline instead of the
+// breakpoint-based kprobe mechanism, at roughly half the per-call cost, and
+// give typed argument/return access without pt_regs decoding.
+// ============================================================
+
+#[fentry]
+pub fn vfs_read_entry_fentry(ctx: FEntryContext) -> u32 {
+    match try_vfs_fentry(&ctx, VFS_OP_READ) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[fexit]
+pub fn vfs_read_exit_fexit(ctx: FExitContext) -> u32 {
+    match try_vfs_read_fexit(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[fentry]
+pub fn vfs_write_entry_fentry(ctx: FEntryContext) -> u32 {
+    match try_vfs_fentry(&ctx, VFS_OP_WRITE) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[fexit]
+pub fn vfs_write_exit_fexit(ctx: FExitContext) -> u32 {
+    match try_vfs_exit_fexit(&ctx, VFS_OP_WRITE) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+// ============================================================
+// Per-cgroup aggregation
+// ============================================================
+
+/// Folds one call's `(latency_ns, bytes)` into `cgroup_id`'s running totals
+/// in `VFS_AGG`. Non-atomic read-modify-write, same as `GPU_BUSY_NS` in
+/// `gpu/util.rs` - acceptable since userspace only needs approximate totals
+/// between flush sweeps.
+#[inline(always)]
+fn record_agg(cgroup_id: u64, latency_ns: u64, bytes: u64) {
+    let mut bucket = unsafe { VFS_AGG.get(&cgroup_id) }
+        .copied()
+        .unwrap_or_default();
+
+    bucket.count += 1;
+    bucket.sum_latency_ns += latency_ns;
+    bucket.max_latency_ns = bucket.max_latency_ns.max(latency_ns);
+    bucket.sum_bytes += bytes;
+
+    let _ = VFS_AGG.insert(&cgroup_id, &bucket, 0);
+}
+
+#[inline(always)]
+fn aggregate_mode_enabled() -> bool {
+    matches!(unsafe { VFS_AGGREGATE_MODE.get(&0) }, Some(1))
+}
+
+/// Bumps `op_type`'s log2 bucket for `latency_ns` in `VFS_HIST`.
+#[inline(always)]
+fn record_histogram(op_type: u8, latency_ns: u64) {
+    let bucket = latency_bucket(latency_ns);
+    let index = (op_type as usize) * HIST_NUM_BUCKETS + bucket;
+
+    if let Some(ptr) = VFS_HIST.get_ptr_mut(index as u32) {
+        unsafe { AtomicU64::from_ptr(ptr).fetch_add(1, Ordering::Relaxed) };
+    }
+}
+
+#[inline(always)]
+fn histogram_mode_enabled() -> bool {
+    matches!(unsafe { VFS_HISTOGRAM_MODE.get(&0) }, Some(1))
+}
+
+/// `VFS_OP_CONFIG`'s entry for `op_type`, falling back to this probe's
+/// original hardcoded behavior when userspace hasn't configured it: regular
+/// files only + 1MB/10ms for reads, latency-only (no byte floor, any file
+/// type) for writes.
+#[inline(always)]
+fn op_config(op_type: u8) -> VfsOpConfig {
+    if let Some(config) = unsafe { VFS_OP_CONFIG.get(&op_type) } {
+        return *config;
+    }
+
+    if op_type == VFS_OP_READ {
+        VfsOpConfig {
+            latency_ns_threshold: DEFAULT_THRESHOLD_NS,
+            min_bytes_threshold: MIN_READ_BYTES,
+            regular_files_only: 1,
+            _pad: [0; 7],
+        }
+    } else {
+        VfsOpConfig {
+            latency_ns_threshold: DEFAULT_THRESHOLD_NS,
+            min_bytes_threshold: 0,
+            regular_files_only: 0,
+            _pad: [0; 7],
+        }
+    }
+}
+
 // ============================================================
 // Common implementation
 // ============================================================
 
 #[inline(always)]
 fn try_vfs_entry(ctx: &ProbeContext, op_type: u8) -> Result<u32, u32> {
+    // Get file pointer (first argument) and pos pointer (fourth argument) -
+    // both vfs_read(file, buf, count, pos) and vfs_write(file, buf, count,
+    // pos) take `loff_t *pos` as their fourth argument.
+    let file_ptr: u64 = ctx.arg(0).ok_or(1u32)?;
+    let pos_ptr: u64 = ctx.arg(3).ok_or(1u32)?;
+    vfs_entry_core(op_type, file_ptr, pos_ptr)
+}
+
+/// fentry argument access is typed and infallible (the trampoline's BTF
+/// signature guarantees the slot exists), unlike `ProbeContext::arg`'s
+/// `Option` from decoding raw `pt_regs`.
+#[inline(always)]
+fn try_vfs_fentry(ctx: &FEntryContext, op_type: u8) -> Result<u32, u32> {
+    let file_ptr: u64 = unsafe { ctx.arg::<*const u8>(0) } as u64;
+    let pos_ptr: u64 = unsafe { ctx.arg::<*const u8>(3) } as u64;
+    vfs_entry_core(op_type, file_ptr, pos_ptr)
+}
+
+#[inline(always)]
+fn vfs_entry_core(op_type: u8, file_ptr: u64, pos_ptr: u64) -> Result<u32, u32> {
     let tid = (bpf_get_current_pid_tgid() & 0xFFFFFFFF) as u32;
     let start_time = unsafe { bpf_ktime_get_ns() };
 
-    // Get file pointer (first argument)
-    let file_ptr: u64 = ctx.arg(0).ok_or(1u32)?;
-
-    // Store start time, op type, and file pointer
+    // Store start time, op type, file pointer, and pos pointer
     VFS_START
-        .insert(&tid, &(start_time, op_type, file_ptr), 0)
+        .insert(&tid, &(start_time, op_type, file_ptr, pos_ptr), 0)
         .map_err(|_| 1u32)?;
 
     Ok(0)
 }
 
+/// Dereference `loff_t *pos` to report the byte offset the I/O landed at.
+/// Some callers (e.g. `splice`) pass a NULL `pos`, so that's treated as
+/// offset 0 rather than an error.
+#[inline(always)]
+fn read_offset(pos_ptr: u64) -> u64 {
+    if pos_ptr == 0 {
+        return 0;
+    }
+
+    unsafe { bpf_probe_read_kernel(pos_ptr as *const i64) }
+        .map(|pos| pos as u64)
+        .unwrap_or(0)
+}
+
 /// Special exit handler for vfs_read with size-first filtering
 /// This reduces overhead by checking bytes/latency before doing expensive inode checks
 #[inline(always)]
 fn try_vfs_read_exit(ctx: &RetProbeContext) -> Result<u32, u32> {
+    let ret: i64 = ctx.ret().unwrap_or(0);
+    vfs_read_exit_core(ret)
+}
+
+/// fexit's return-value access is typed and infallible, same rationale as
+/// `try_vfs_fentry` above. The trampoline appends the return value after the
+/// traced function's own arguments, so for `vfs_read(file, buf, count, pos)`
+/// (4 args) the return value sits at index 4.
+#[inline(always)]
+fn try_vfs_read_fexit(ctx: &FExitContext) -> Result<u32, u32> {
+    let ret: i64 = unsafe { ctx.arg(4) };
+    vfs_read_exit_core(ret)
+}
+
+#[inline(always)]
+fn vfs_read_exit_core(ret: i64) -> Result<u32, u32> {
     let tid = (bpf_get_current_pid_tgid() & 0xFFFFFFFF) as u32;
     let pid = (bpf_get_current_pid_tgid() >> 32) as u32;
 
     // Look up start time
-    let (start_time, stored_op, file_ptr) = match unsafe { VFS_START.get(&tid) } {
+    let (start_time, stored_op, file_ptr, pos_ptr) = match unsafe { VFS_START.get(&tid) } {
         Some(val) => *val,
         None => return Ok(0),
     };
@@ -134,8 +342,7 @@ fn try_vfs_read_exit(ctx: &RetProbeContext) -> Result<u32, u32> {
         return Ok(0);
     }
 
-    // Get return value (bytes read, or negative error)
-    let ret: i64 = ctx.ret().unwrap_or(0);
+    // ret: bytes read, or negative error
     if ret < 0 {
         return Ok(0); // Ignore errors
     }
@@ -145,21 +352,33 @@ fn try_vfs_read_exit(ctx: &RetProbeContext) -> Result<u32, u32> {
     let end_time = unsafe { bpf_ktime_get_ns() };
     let latency_ns = end_time.saturating_sub(start_time);
 
-    // Get threshold (default 10ms)
-    let threshold = match unsafe { VFS_THRESHOLD_NS.get(&0) } {
-        Some(t) => *t,
-        None => DEFAULT_THRESHOLD_NS,
-    };
+    if histogram_mode_enabled() {
+        record_histogram(VFS_OP_READ, latency_ns);
+        return Ok(0);
+    }
+
+    // Fold into the per-cgroup running totals regardless of aggregate mode,
+    // so userspace can read cheap stats even while streaming individual
+    // events too.
+    record_agg(unsafe { bpf_get_current_cgroup_id() }, latency_ns, bytes);
+
+    if aggregate_mode_enabled() {
+        return Ok(0);
+    }
+
+    let config = op_config(VFS_OP_READ);
 
     // FAST PATH: Skip small and fast reads (99% of cases)
-    // This check is cheap - just comparing already-computed values
-    if bytes < MIN_READ_BYTES && latency_ns < threshold {
+    // This check is cheap - just comparing already-computed values.
+    // A `min_bytes_threshold` of 0 disables byte-based filtering.
+    let below_bytes = config.min_bytes_threshold == 0 || bytes < config.min_bytes_threshold;
+    if below_bytes && latency_ns < config.latency_ns_threshold {
         return Ok(0);
     }
 
     // SLOW PATH: Only for large or slow reads, check if it's a regular file
     // This involves reading kernel memory, so we do it only when necessary
-    if !is_regular_file(file_ptr) {
+    if config.regular_files_only != 0 && !is_regular_file(file_ptr) {
         return Ok(0);
     }
 
@@ -181,7 +400,7 @@ fn try_vfs_read_exit(ctx: &RetProbeContext) -> Result<u32, u32> {
         (*event).op_type = VFS_OP_READ;
         (*event).latency_ns = latency_ns;
         (*event).bytes = bytes;
-        (*event).offset = 0;
+        (*event).offset = read_offset(pos_ptr);
 
         // Get comm
         if let Ok(comm) = bpf_get_current_comm() {
@@ -199,11 +418,25 @@ fn try_vfs_read_exit(ctx: &RetProbeContext) -> Result<u32, u32> {
 
 #[inline(always)]
 fn try_vfs_exit(ctx: &RetProbeContext, op_type: u8) -> Result<u32, u32> {
+    let ret: i64 = ctx.ret().unwrap_or(0);
+    vfs_exit_core(op_type, ret)
+}
+
+/// `vfs_write(file, buf, count, pos)` also takes 4 args, so the trampoline's
+/// appended return value is likewise at index 4 - see `try_vfs_read_fexit`.
+#[inline(always)]
+fn try_vfs_exit_fexit(ctx: &FExitContext, op_type: u8) -> Result<u32, u32> {
+    let ret: i64 = unsafe { ctx.arg(4) };
+    vfs_exit_core(op_type, ret)
+}
+
+#[inline(always)]
+fn vfs_exit_core(op_type: u8, ret: i64) -> Result<u32, u32> {
     let tid = (bpf_get_current_pid_tgid() & 0xFFFFFFFF) as u32;
     let pid = (bpf_get_current_pid_tgid() >> 32) as u32;
 
     // Look up start time
-    let (start_time, stored_op, file_ptr) = match unsafe { VFS_START.get(&tid) } {
+    let (start_time, stored_op, file_ptr, pos_ptr) = match unsafe { VFS_START.get(&tid) } {
         Some(val) => *val,
         None => return Ok(0),
     };
@@ -220,23 +453,41 @@ fn try_vfs_exit(ctx: &RetProbeContext, op_type: u8) -> Result<u32, u32> {
     let end_time = unsafe { bpf_ktime_get_ns() };
     let latency_ns = end_time.saturating_sub(start_time);
 
-    // Get threshold (default 10ms)
-    let threshold = match unsafe { VFS_THRESHOLD_NS.get(&0) } {
-        Some(t) => *t,
-        None => DEFAULT_THRESHOLD_NS,
-    };
+    // ret: bytes read/written, or negative error
+    if ret < 0 {
+        return Ok(0); // Ignore errors
+    }
+    let bytes = ret as u64;
 
-    // Only emit event if latency exceeds threshold
-    if latency_ns < threshold {
+    if histogram_mode_enabled() {
+        record_histogram(op_type, latency_ns);
         return Ok(0);
     }
 
-    // Get return value (bytes read/written, or negative error)
-    let ret: i64 = ctx.ret().unwrap_or(0);
-    if ret < 0 {
-        return Ok(0); // Ignore errors
+    // Fold into the per-cgroup running totals regardless of aggregate mode,
+    // so userspace can read cheap stats even while streaming individual
+    // events too.
+    record_agg(unsafe { bpf_get_current_cgroup_id() }, latency_ns, bytes);
+
+    if aggregate_mode_enabled() {
+        return Ok(0);
+    }
+
+    let config = op_config(op_type);
+
+    // FAST PATH: same cheap bytes/latency short-circuit as the read path -
+    // disabled by default for writes (`min_bytes_threshold` is 0 unless
+    // userspace opts in), but available to operators who want it.
+    let below_bytes = config.min_bytes_threshold == 0 || bytes < config.min_bytes_threshold;
+    if below_bytes && latency_ns < config.latency_ns_threshold {
+        return Ok(0);
+    }
+
+    // SLOW PATH: only checked if configured, same as the read path - off by
+    // default for writes.
+    if config.regular_files_only != 0 && !is_regular_file(file_ptr) {
+        return Ok(0);
     }
-    let bytes = ret as u64;
 
     // Reserve space in ring buffer
     let mut reservation = match VFS_EVENTS.reserve::<VfsLatencyEvent>(0) {
@@ -256,7 +507,7 @@ fn try_vfs_exit(ctx: &RetProbeContext, op_type: u8) -> Result<u32, u32> {
         (*event).op_type = op_type;
         (*event).latency_ns = latency_ns;
         (*event).bytes = bytes;
-        (*event).offset = 0;
+        (*event).offset = read_offset(pos_ptr);
 
         // Get comm
         if let Ok(comm) = bpf_get_current_comm() {
@@ -272,19 +523,53 @@ fn try_vfs_exit(ctx: &RetProbeContext, op_type: u8) -> Result<u32, u32> {
     Ok(0)
 }
 
+/// Compiled-in fallback offsets, used when `VFS_OFFSETS` hasn't been
+/// populated from BTF (see `resolve_vfs_field_offsets`, userspace side) -
+/// e.g. kernels without `/sys/kernel/btf/vmlinux`, or a BTF walk that
+/// couldn't resolve one of the expected struct members.
+mod fallback_offsets {
+    /// `file->f_inode`, typically at offset 32 on x86_64 (after `f_path`).
+    pub const F_INODE_OFFSET: u64 = 32;
+    /// `inode->i_mode`, at offset 0 in `struct inode`.
+    pub const I_MODE_OFFSET: u64 = 0;
+    /// `file->f_path`, typically at offset 16 on x86_64.
+    pub const F_PATH_OFFSET: u64 = 16;
+    /// `path->dentry` (`struct path { struct vfsmount *mnt; struct dentry *dentry; }`).
+    pub const DENTRY_OFFSET: u64 = 8;
+    /// `dentry->d_name`.
+    pub const D_NAME_OFFSET: u64 = 32;
+    /// `qstr->name` (i.e. `d_name.name`).
+    pub const QSTR_NAME_OFFSET: u64 = 8;
+}
+
+/// Offsets used by `is_regular_file`/`read_filename_from_file`: `VFS_OFFSETS`
+/// when BTF resolution has populated it, otherwise `fallback_offsets`.
+#[inline(always)]
+fn resolved_offsets() -> VfsFieldOffsets {
+    match VFS_OFFSETS.get(0) {
+        Some(offsets) if offsets.configured != 0 => *offsets,
+        _ => VfsFieldOffsets {
+            f_inode_offset: fallback_offsets::F_INODE_OFFSET as u16,
+            f_path_dentry_offset: (fallback_offsets::F_PATH_OFFSET
+                + fallback_offsets::DENTRY_OFFSET) as u16,
+            d_name_offset: fallback_offsets::D_NAME_OFFSET as u16,
+            qstr_name_offset: fallback_offsets::QSTR_NAME_OFFSET as u16,
+            i_mode_offset: fallback_offsets::I_MODE_OFFSET as u16,
+            configured: 0,
+            _pad: [0],
+        },
+    }
+}
+
 /// Check if file is a regular file (not socket, pipe, device, etc.)
 /// Reads struct file -> f_inode -> i_mode and checks S_ISREG
 #[inline(always)]
 fn is_regular_file(file_ptr: u64) -> bool {
-    // struct file offsets (kernel version dependent)
-    // f_inode is typically at offset 32 on x86_64 (after f_path)
-    const F_INODE_OFFSET: usize = 32;
-    // i_mode is at offset 0 in struct inode
-    const I_MODE_OFFSET: usize = 0;
+    let offsets = resolved_offsets();
 
     // Read inode pointer: file->f_inode
     let inode_ptr: u64 = match unsafe {
-        bpf_probe_read_kernel((file_ptr + F_INODE_OFFSET as u64) as *const u64)
+        bpf_probe_read_kernel((file_ptr + offsets.f_inode_offset as u64) as *const u64)
     } {
         Ok(ptr) => ptr,
         Err(_) => return false,
@@ -296,7 +581,7 @@ fn is_regular_file(file_ptr: u64) -> bool {
 
     // Read i_mode: inode->i_mode
     let i_mode: u16 = match unsafe {
-        bpf_probe_read_kernel((inode_ptr + I_MODE_OFFSET as u64) as *const u16)
+        bpf_probe_read_kernel((inode_ptr + offsets.i_mode_offset as u64) as *const u16)
     } {
         Ok(mode) => mode,
         Err(_) => return false,
@@ -310,22 +595,11 @@ fn is_regular_file(file_ptr: u64) -> bool {
 /// This is kernel version dependent but works on most modern kernels
 #[inline(always)]
 fn read_filename_from_file(file_ptr: u64, filename: &mut [u8; MAX_FILENAME_LEN]) {
-    // struct file offsets (may vary by kernel version)
-    // f_path is typically at offset 16 on x86_64
-    // struct path { struct vfsmount *mnt; struct dentry *dentry; }
-    // dentry is at offset 8 within path
-    // d_name (struct qstr) contains the name
-
-    const F_PATH_OFFSET: usize = 16; // offset of f_path in struct file
-    const DENTRY_OFFSET: usize = 8; // offset of dentry in struct path
-    const D_NAME_OFFSET: usize = 32; // offset of d_name in struct dentry
-    const QSTR_NAME_OFFSET: usize = 8; // offset of name ptr in struct qstr
+    let offsets = resolved_offsets();
 
     // Read dentry pointer: file->f_path.dentry
     let dentry_ptr: u64 = match unsafe {
-        bpf_probe_read_kernel(
-            (file_ptr + F_PATH_OFFSET as u64 + DENTRY_OFFSET as u64) as *const u64,
-        )
+        bpf_probe_read_kernel((file_ptr + offsets.f_path_dentry_offset as u64) as *const u64)
     } {
         Ok(ptr) => ptr,
         Err(_) => return,
@@ -338,7 +612,8 @@ fn read_filename_from_file(file_ptr: u64, filename: &mut [u8; MAX_FILENAME_LEN])
     // Read name pointer: dentry->d_name.name
     let name_ptr: u64 = match unsafe {
         bpf_probe_read_kernel(
-            (dentry_ptr + D_NAME_OFFSET as u64 + QSTR_NAME_OFFSET as u64) as *const u64,
+            (dentry_ptr + offsets.d_name_offset as u64 + offsets.qstr_name_offset as u64)
+                as *const u64,
         )
     } {
         Ok(ptr) => ptr,
@@ -0,0 +1,62 @@
+//! Shared eBPF-side probe plumbing.
+//!
+//! Every builtin probe's event type implements [`HoneyBeeEvent`] so
+//! [`emit_event`] can reserve, fill, and submit it onto that probe's ring
+//! buffer generically, instead of each probe file duplicating the
+//! reserve/fill/submit/discard dance.
+
+pub mod builtin;
+
+use aya_ebpf::helpers::{bpf_get_current_cgroup_id, bpf_get_current_pid_tgid, bpf_ktime_get_ns};
+use aya_ebpf::maps::RingBuf;
+use honeybeepf_common::EventMetadata;
+
+/// An event type that knows how to populate itself from a probe context and
+/// expose its shared [`EventMetadata`] header.
+pub trait HoneyBeeEvent<C> {
+    /// Mutable access to the event's shared metadata header (pid, cgroup_id,
+    /// timestamp, ...).
+    fn metadata(&mut self) -> &mut EventMetadata;
+
+    /// Populates the event's fields (including, usually, its metadata via
+    /// `self.init_base()`) from `ctx`. Returns the eBPF program return code
+    /// to propagate on failure.
+    fn fill(&mut self, ctx: &C) -> Result<(), u32>;
+
+    /// Fills the shared `pid`/`cgroup_id`/`timestamp` header from the
+    /// current task, leaving `_pad` untouched. Most `fill` implementations
+    /// call this first; `llm/helpers.rs` fills its metadata by hand instead
+    /// to avoid a circular dependency on this trait and to additionally
+    /// stash the tid in `_pad`.
+    #[inline(always)]
+    fn init_base(&mut self) {
+        let pid_tgid = bpf_get_current_pid_tgid();
+        let metadata = self.metadata();
+        metadata.pid = (pid_tgid >> 32) as u32;
+        metadata.timestamp = unsafe { bpf_ktime_get_ns() };
+        metadata.cgroup_id = unsafe { bpf_get_current_cgroup_id() };
+    }
+}
+
+/// Reserves space for `E` on `ring_buf`, fills it via [`HoneyBeeEvent::fill`],
+/// and submits it - discarding the reservation instead if `fill` fails. A
+/// full ring buffer is not an error condition worth propagating (the event
+/// is simply dropped), so this returns 0 in that case same as on success.
+#[inline(always)]
+pub fn emit_event<C, E: HoneyBeeEvent<C>>(ring_buf: &RingBuf, ctx: &C) -> u32 {
+    let Some(mut slot) = ring_buf.reserve::<E>(0) else {
+        return 0;
+    };
+
+    let event = unsafe { &mut *slot.as_mut_ptr() };
+    match event.fill(ctx) {
+        Ok(()) => {
+            slot.submit(0);
+            0
+        }
+        Err(e) => {
+            slot.discard(0);
+            e
+        }
+    }
+}
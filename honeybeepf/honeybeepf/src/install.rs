@@ -0,0 +1,360 @@
+//! Interactive `init` wizard and self-install support.
+//!
+//! Lets a freshly downloaded static binary go from "just downloaded" to
+//! "running service" on an air-gapped host without the separate
+//! `install.sh`/`install-service.sh` scripts `xtask package` emits: `init`
+//! prompts for which builtin probes to enable and writes a config file the
+//! layered `Settings` loader picks up on its own, warning along the way
+//! about any probe the host doesn't look able to support; `install` places
+//! the running binary under `/usr/local/bin` and registers the systemd unit.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use log::info;
+
+use crate::probes::builtin::llm::discovery::dynamic::find_system_default_ssl;
+use crate::settings::default_config_path;
+
+const DEFAULT_CONFIG_DIR: &str = "/etc/honeybeepf";
+const DEFAULT_ENV_PATH: &str = "/etc/honeybeepf/honeybeepf.env";
+const DEFAULT_INSTALL_PATH: &str = "/usr/local/bin/honeybeepf";
+const DEFAULT_SERVICE_PATH: &str = "/etc/systemd/system/honeybeepf.service";
+
+/// Runs the interactive configuration wizard and writes the resulting
+/// settings to `config_path` (the layered loader's default search location,
+/// `/etc/honeybeepf/config.toml`, unless overridden).
+pub fn run_init_wizard(config_path: Option<&str>) -> Result<()> {
+    let default_path = default_config_path();
+    let config_path = config_path
+        .map(Path::new)
+        .unwrap_or(default_path.as_path());
+
+    println!("HoneybeePF configuration wizard");
+    println!("Press Enter to accept the default shown in [brackets].\n");
+
+    let mut network = Vec::new();
+    let mut filesystem = Vec::new();
+    let mut scheduler = Vec::new();
+    let mut builtin_top = Vec::new();
+    let mut root = Vec::new();
+
+    if prompt_bool("Enable tcp_connect probe?", true)? {
+        network.push("tcp_connect = true".to_string());
+    }
+    if prompt_bool("Enable tcp_retrans probe?", false)? {
+        network.push("tcp_retrans = true".to_string());
+    }
+    if prompt_bool("Enable dns probe?", false)? {
+        network.push("dns = true".to_string());
+    }
+
+    if prompt_bool("Enable vfs_latency probe?", false)? {
+        warn_if_tracepoint_missing("Kernel function entry/exit hooks", has_kprobe_support());
+        filesystem.push("vfs_latency = true".to_string());
+        let threshold = prompt_u32("  vfs_latency threshold (ms)", 50)?;
+        filesystem.push(format!("vfs_latency_threshold_ms = {}", threshold));
+    }
+
+    if prompt_bool("Enable file_access probe?", false)? {
+        warn_if_tracepoint_missing(
+            "syscalls:sys_enter_openat tracepoint",
+            tracepoint_exists("syscalls", "sys_enter_openat"),
+        );
+        filesystem.push("file_access = true".to_string());
+        let watched_paths = prompt_string("  Watched paths (comma-separated)", "")?;
+        if !watched_paths.is_empty() {
+            filesystem.push(format!(
+                "watched_paths = {}",
+                toml_string_array(&watched_paths)
+            ));
+        }
+        let watched_prefixes = prompt_string("  Watched directory prefixes (comma-separated)", "")?;
+        if !watched_prefixes.is_empty() {
+            filesystem.push(format!(
+                "watched_prefixes = {}",
+                toml_string_array(&watched_prefixes)
+            ));
+        }
+        if prompt_bool("  Also hook open()/openat2()/creat()?", false)? {
+            filesystem.push("file_access_open = true".to_string());
+            filesystem.push("file_access_openat2 = true".to_string());
+            filesystem.push("file_access_creat = true".to_string());
+        }
+    }
+
+    if prompt_bool("Enable runqueue probe?", false)? {
+        warn_if_tracepoint_missing(
+            "sched:sched_wakeup tracepoint",
+            tracepoint_exists("sched", "sched_wakeup"),
+        );
+        scheduler.push("runqueue = true".to_string());
+        let threshold = prompt_u32("  runqueue threshold (ms)", 10)?;
+        scheduler.push(format!("runqueue_threshold_ms = {}", threshold));
+    }
+
+    if prompt_bool("Enable offcpu probe?", false)? {
+        warn_if_tracepoint_missing(
+            "sched:sched_switch tracepoint",
+            tracepoint_exists("sched", "sched_switch"),
+        );
+        scheduler.push("offcpu = true".to_string());
+        let threshold = prompt_u32("  offcpu threshold (ms)", 100)?;
+        scheduler.push(format!("offcpu_threshold_ms = {}", threshold));
+    }
+
+    if prompt_bool("Enable llm probe?", false)? {
+        let ssl_libs = find_system_default_ssl().unwrap_or_default();
+        if ssl_libs.is_empty() {
+            println!(
+                "  Warning: no system libssl/libcrypto found via ldconfig; the llm probe \
+                 won't see any SSL traffic until a TLS library is discovered on a watched \
+                 process."
+            );
+        }
+        builtin_top.push("llm = true".to_string());
+    }
+
+    let otel_endpoint = prompt_string("OTel metrics endpoint (blank to disable)", "")?;
+    if !otel_endpoint.is_empty() {
+        root.push(format!("otel_exporter_otlp_endpoint = \"{}\"", otel_endpoint));
+    }
+
+    let config_dir = config_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new(DEFAULT_CONFIG_DIR));
+    std::fs::create_dir_all(config_dir)
+        .with_context(|| format!("Failed to create {}", config_dir.display()))?;
+
+    let contents = render_toml(&root, &builtin_top, &network, &filesystem, &scheduler);
+    std::fs::write(config_path, contents)
+        .with_context(|| format!("Failed to write {}", config_path.display()))?;
+
+    println!("\nWrote configuration to {}", config_path.display());
+    println!("Run `honeybeepf install` to install the binary and systemd unit.");
+
+    Ok(())
+}
+
+/// Assembles the wizard's answers into a TOML document matching
+/// [`crate::settings::Settings`]'s shape, omitting any section the user
+/// left entirely empty.
+fn render_toml(
+    root: &[String],
+    builtin_top: &[String],
+    network: &[String],
+    filesystem: &[String],
+    scheduler: &[String],
+) -> String {
+    let mut out = String::new();
+
+    for line in root {
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    if !builtin_top.is_empty() {
+        out.push_str("\n[builtin_probes]\n");
+        for line in builtin_top {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+
+    let mut push_section = |header: &str, lines: &[String]| {
+        if lines.is_empty() {
+            return;
+        }
+        out.push('\n');
+        out.push_str(header);
+        out.push('\n');
+        for line in lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+    };
+    push_section("[builtin_probes.network]", network);
+    push_section("[builtin_probes.filesystem]", filesystem);
+    push_section("[builtin_probes.scheduler]", scheduler);
+
+    out
+}
+
+/// Renders a comma-separated answer as a TOML string array, e.g.
+/// `"a, b"` -> `["a", "b"]`.
+fn toml_string_array(csv: &str) -> String {
+    let items: Vec<String> = csv
+        .split(',')
+        .map(|s| format!("\"{}\"", s.trim()))
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn warn_if_tracepoint_missing(what: &str, present: bool) {
+    if !present {
+        println!(
+            "  Warning: {} not found on this host; this probe may fail to attach.",
+            what
+        );
+    }
+}
+
+/// Best-effort check for whether a `subsystem:name` tracepoint exists on
+/// this host, checking both the common tracefs mount points (mirrors the
+/// same check `FileAccessProbe` does when resolving field offsets).
+fn tracepoint_exists(subsystem: &str, name: &str) -> bool {
+    [
+        format!("/sys/kernel/tracing/events/{}/{}", subsystem, name),
+        format!("/sys/kernel/debug/tracing/events/{}/{}", subsystem, name),
+    ]
+    .iter()
+    .any(|p| Path::new(p).exists())
+}
+
+/// `vfs_latency`/`tcp_connect` hook kernel functions via
+/// kprobes rather than a fixed tracepoint, so there's no single event path
+/// to check for; approximate "can we attach kprobes at all" by checking
+/// that kprobe support is compiled into this kernel.
+fn has_kprobe_support() -> bool {
+    Path::new("/sys/kernel/debug/kprobes/list").exists()
+        || Path::new("/sys/kernel/tracing/kprobe_events").exists()
+        || Path::new("/sys/kernel/debug/tracing/kprobe_events").exists()
+}
+
+/// Copies the running binary to `/usr/local/bin`, ensures the config
+/// directory/env file exist, and registers + enables the systemd unit -
+/// everything `install.sh` + `install-service.sh` do together, without
+/// needing to ssh into the host running them.
+pub fn self_install(env_path: Option<&str>) -> Result<()> {
+    let env_path = env_path.unwrap_or(DEFAULT_ENV_PATH);
+
+    let current_exe = std::env::current_exe().context("Failed to determine current executable path")?;
+    if current_exe == Path::new(DEFAULT_INSTALL_PATH) {
+        bail!(
+            "Already running from {}; nothing to install",
+            DEFAULT_INSTALL_PATH
+        );
+    }
+
+    info!("Installing binary to {}", DEFAULT_INSTALL_PATH);
+    std::fs::copy(&current_exe, DEFAULT_INSTALL_PATH)
+        .with_context(|| format!("Failed to copy binary to {}", DEFAULT_INSTALL_PATH))?;
+    set_executable(DEFAULT_INSTALL_PATH)?;
+
+    if !Path::new(env_path).exists() {
+        info!("No config found at {}, run `honeybeepf init` first for a guided setup", env_path);
+        std::fs::create_dir_all(DEFAULT_CONFIG_DIR)
+            .with_context(|| format!("Failed to create {}", DEFAULT_CONFIG_DIR))?;
+        std::fs::write(env_path, "")
+            .with_context(|| format!("Failed to create empty {}", env_path))?;
+    }
+
+    let service = generate_systemd_service(Some(env_path));
+    std::fs::write(DEFAULT_SERVICE_PATH, service)
+        .with_context(|| format!("Failed to write {}", DEFAULT_SERVICE_PATH))?;
+
+    run_systemctl(&["daemon-reload"])?;
+    run_systemctl(&["enable", "honeybeepf"])?;
+
+    println!("Installed {} and registered the honeybeepf systemd unit.", DEFAULT_INSTALL_PATH);
+    println!("Start it with: sudo systemctl start honeybeepf");
+
+    Ok(())
+}
+
+/// Mirrors `xtask`'s `generate_systemd_service` - there's no shared crate
+/// between `xtask` and this binary to hang a single copy off of, so keep the
+/// two in sync by hand if the unit file ever changes.
+fn generate_systemd_service(config: Option<&str>) -> String {
+    let env_line = config
+        .map(|c| format!("EnvironmentFile={}", c))
+        .unwrap_or_default();
+
+    format!(
+        r#"[Unit]
+Description=HoneybeePF eBPF Monitoring
+After=network.target
+
+[Service]
+Type=simple
+ExecStart={install_path}
+Restart=on-failure
+RestartSec=5
+{env_line}
+
+# Security hardening
+NoNewPrivileges=no
+CapabilityBoundingSet=CAP_SYS_ADMIN CAP_BPF CAP_PERFMON CAP_NET_ADMIN
+AmbientCapabilities=CAP_SYS_ADMIN CAP_BPF CAP_PERFMON CAP_NET_ADMIN
+
+[Install]
+WantedBy=multi-user.target
+"#,
+        install_path = DEFAULT_INSTALL_PATH,
+        env_line = env_line,
+    )
+}
+
+fn set_executable(path: &str) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = std::fs::metadata(path)?.permissions();
+        perms.set_mode(0o755);
+        std::fs::set_permissions(path, perms)?;
+    }
+    Ok(())
+}
+
+fn run_systemctl(args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new("systemctl")
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run systemctl {}", args.join(" ")))?;
+    if !status.success() {
+        bail!("systemctl {} failed", args.join(" "));
+    }
+    Ok(())
+}
+
+fn prompt_bool(question: &str, default: bool) -> Result<bool> {
+    let suffix = if default { "[Y/n]" } else { "[y/N]" };
+    let answer = read_line(&format!("{} {} ", question, suffix))?;
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "" => default,
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+fn prompt_string(question: &str, default: &str) -> Result<String> {
+    let answer = read_line(&format!("{} [{}]: ", question, default))?;
+    let trimmed = answer.trim();
+    Ok(if trimmed.is_empty() {
+        default.to_string()
+    } else {
+        trimmed.to_string()
+    })
+}
+
+fn prompt_u32(question: &str, default: u32) -> Result<u32> {
+    let answer = read_line(&format!("{} [{}]: ", question, default))?;
+    let trimmed = answer.trim();
+    if trimmed.is_empty() {
+        return Ok(default);
+    }
+    trimmed
+        .parse()
+        .with_context(|| format!("\"{}\" is not a number", trimmed))
+}
+
+fn read_line(prompt: &str) -> Result<String> {
+    print!("{}", prompt);
+    io::stdout().flush().ok();
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).context("Failed to read from stdin")?;
+    Ok(line)
+}
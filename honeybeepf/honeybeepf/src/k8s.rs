@@ -4,19 +4,30 @@
 //! 1. Parsing `/proc/{pid}/cgroup` to extract container IDs
 //! 2. Watching pods on the current node via the K8s API
 //! 3. Caching the mapping: cgroup_id → container_id → PodInfo
+//!
+//! The cgroup cache itself is kept fresh in real time rather than by
+//! polling: [`start_process_lifecycle_listener`] attaches the
+//! `sched_process_exec`/`sched_process_exit` tracepoints (see
+//! `ProcessEvent` in `honeybeepf_common`) and primes/evicts `cgroup_cache`
+//! as processes come and go, instead of the old lazy-read-plus-periodic-sweep
+//! approach.
 
 use std::{
     collections::HashMap,
-    sync::{Arc, RwLock, atomic::Ordering},
-    time::Duration,
+    sync::{Arc, RwLock},
 };
 
 use anyhow::{Context, Result};
+use aya::{Ebpf, programs::TracePoint};
 use futures::StreamExt;
-use k8s_openapi::api::core::v1::Pod;
+use honeybeepf_common::ProcessEvent;
+use k8s_openapi::api::{apps::v1::ReplicaSet, batch::v1::Job, core::v1::Pod};
 use kube::{Api, Client, runtime::watcher};
 use log::warn;
 
+use crate::probes::spawn_ringbuf_handler;
+use crate::process::{ProcessInfo, ProcessResolver};
+
 /// Kubernetes pod identity resolved from a PID/cgroup_id.
 #[derive(Debug, Clone)]
 pub struct PodInfo {
@@ -45,6 +56,26 @@ pub struct PodResolver {
     /// container_id (short 12-char prefix) → PodInfo.
     /// Populated and updated by the K8s watcher task.
     pod_store: RwLock<HashMap<String, Arc<PodInfo>>>,
+    /// Number of still-running tasks (threads/processes) last seen execing
+    /// into each cgroup_id, maintained by [`handle_process_event`]. A
+    /// `cgroup_cache` entry is only evicted once its cgroup's refcount drops
+    /// to zero, since many threads (and, for a shared-cgroup workload,
+    /// several processes) can share one `cgroup_id`.
+    ///
+    /// [`handle_process_event`]: PodResolver::handle_process_event
+    cgroup_refcount: RwLock<HashMap<u64, u32>>,
+    /// (namespace, ReplicaSet name) → (owner kind, owner name), populated
+    /// from each ReplicaSet's own `ownerReferences` so a pod owned by a
+    /// ReplicaSet can be resolved to its real top-level controller (usually
+    /// a Deployment) instead of guessed from the ReplicaSet's name.
+    replicaset_owner: RwLock<HashMap<(String, String), (String, String)>>,
+    /// (namespace, Job name) → (owner kind, owner name), the Job-owned
+    /// equivalent of `replicaset_owner` - resolves a pod owned by a Job to
+    /// its owning CronJob, when the Job was created by one.
+    job_owner: RwLock<HashMap<(String, String), (String, String)>>,
+    /// Per-PID process metadata (comm, cmdline, ppid, uid, container ID),
+    /// resolved and cached alongside pod identity - see `process::ProcessResolver`.
+    process_resolver: ProcessResolver,
 }
 
 impl Default for PodResolver {
@@ -58,9 +89,20 @@ impl PodResolver {
         Self {
             cgroup_cache: RwLock::new(HashMap::new()),
             pod_store: RwLock::new(HashMap::new()),
+            cgroup_refcount: RwLock::new(HashMap::new()),
+            replicaset_owner: RwLock::new(HashMap::new()),
+            job_owner: RwLock::new(HashMap::new()),
+            process_resolver: ProcessResolver::new(),
         }
     }
 
+    /// Resolve a PID + cgroup_id to pod metadata, plus that PID's process
+    /// metadata (comm, cmdline, ppid, uid - see `process::ProcessInfo`) in
+    /// the same call, since callers that want one almost always want both.
+    pub fn resolve_with_process(&self, pid: u32, cgroup_id: u64) -> (Option<Arc<PodInfo>>, Option<ProcessInfo>) {
+        (self.resolve(pid, cgroup_id), self.process_resolver.resolve(pid))
+    }
+
     /// Resolve a PID + cgroup_id to pod metadata.
     ///
     /// Returns cached result in O(1) for known cgroup_ids.
@@ -102,12 +144,19 @@ impl PodResolver {
 
         // Pods are namespaced resources, but we want to watch across all namespaces on this node.
         // Therefore, we use Api::all() to get cluster-wide visibility.
-        let api: Api<Pod> = Api::all(client);
+        let api: Api<Pod> = Api::all(client.clone());
         let watcher_config = watcher::Config {
             field_selector: Some(format!("spec.nodeName={}", node_name)),
             ..Default::default()
         };
 
+        // ReplicaSets and Jobs have no `spec.nodeName` of their own (that's
+        // a Pod-scheduling field), so their owner-resolution watchers can't
+        // be node-scoped the way the pod watcher is above; they watch
+        // cluster-wide instead.
+        self.spawn_replicaset_owner_watcher(client.clone());
+        self.spawn_job_owner_watcher(client);
+
         let resolver = Arc::clone(self);
 
         let handle = tokio::spawn(async move {
@@ -133,12 +182,89 @@ impl PodResolver {
             warn!("K8s pod watcher stream ended");
         });
 
-        // Start cache cleanup task
-        self.start_cache_cleanup_task();
-
         Ok(handle)
     }
 
+    /// Attach the `sched_process_exec`/`sched_process_exit` tracepoints and
+    /// route their events into [`handle_process_event`], keeping
+    /// `cgroup_cache` fresh without a lazy `/proc` read on the hot path or a
+    /// periodic sweep for staleness.
+    ///
+    /// [`handle_process_event`]: PodResolver::handle_process_event
+    pub fn start_process_lifecycle_listener(self: &Arc<Self>, bpf: &mut Ebpf) -> Result<()> {
+        for (prog_name, tracepoint) in [
+            ("probe_process_exec", "sched_process_exec"),
+            ("probe_process_exit", "sched_process_exit"),
+        ] {
+            let program: &mut TracePoint = bpf
+                .program_mut(prog_name)
+                .with_context(|| format!("Failed to find {} program", prog_name))?
+                .try_into()?;
+            program.load()?;
+            program.attach("sched", tracepoint)?;
+        }
+
+        let resolver = Arc::clone(self);
+        spawn_ringbuf_handler(bpf, "PROCESS_EVENTS", move |event: ProcessEvent| {
+            resolver.handle_process_event(&event);
+        })?;
+
+        Ok(())
+    }
+
+    /// Prime or evict `cgroup_cache` in response to a process lifecycle
+    /// event. On exec, eagerly resolves the container ID so the first
+    /// `resolve()` call for this cgroup is already a cache hit; on exit,
+    /// decrements `cgroup_refcount` and only evicts the cache entry once
+    /// the last task sharing that cgroup has exited.
+    ///
+    /// Always overwrites `cgroup_cache` on exec rather than checking for an
+    /// existing entry first, since the kernel can reuse both `cgroup_id`
+    /// and `pid` once their previous owners are gone - a stale mapping from
+    /// a prior occupant must never survive into the next one's lookups.
+    pub fn handle_process_event(&self, event: &ProcessEvent) {
+        if event.is_exit != 0 {
+            self.on_task_exit(event.cgroup_id);
+            self.process_resolver.evict(event.pid);
+        } else {
+            self.on_task_exec(event.tgid, event.cgroup_id);
+        }
+    }
+
+    fn on_task_exec(&self, tgid: u32, cgroup_id: u64) {
+        let container_id = extract_container_id(tgid);
+        if let Ok(mut cache) = self.cgroup_cache.write() {
+            cache.insert(cgroup_id, container_id);
+        }
+        if let Ok(mut refcount) = self.cgroup_refcount.write() {
+            *refcount.entry(cgroup_id).or_insert(0) += 1;
+        }
+    }
+
+    fn on_task_exit(&self, cgroup_id: u64) {
+        let is_last = match self.cgroup_refcount.write() {
+            Ok(mut refcount) => match refcount.get_mut(&cgroup_id) {
+                Some(count) if *count > 1 => {
+                    *count -= 1;
+                    false
+                }
+                Some(_) => {
+                    refcount.remove(&cgroup_id);
+                    true
+                }
+                // No exec was ever observed for this cgroup (e.g. it existed
+                // before this listener attached) - evict eagerly rather than
+                // leak a refcount entry that will never reach zero.
+                None => true,
+            },
+            Err(_) => return,
+        };
+
+        if is_last && let Ok(mut cache) = self.cgroup_cache.write() {
+            cache.remove(&cgroup_id);
+        }
+    }
+
     /// Process a pod event from the K8s API watcher.
     fn apply_pod_event(&self, pod: &Pod, is_delete: bool) {
         let metadata = &pod.metadata;
@@ -166,27 +292,24 @@ impl PodResolver {
                 });
             }
         } else {
-            // Extract workload info from ownerReferences
+            // Extract workload info from ownerReferences, following the
+            // chain up to the true top-level controller: a ReplicaSet-owned
+            // pod resolves to that ReplicaSet's own owner (normally a
+            // Deployment), and a Job-owned pod resolves to that Job's own
+            // owner (normally a CronJob), falling back to the immediate
+            // owner's kind/name if the parent isn't cached yet.
             let (workload_kind, workload_name) = metadata
                 .owner_references
                 .as_ref()
                 .and_then(|refs| refs.first())
-                .map(|owner| {
-                    if owner.kind == "ReplicaSet" {
-                        // ReplicaSet is owned by Deployment; strip the hash suffix.
-                        // "my-app-7d4b8c9f5" → "my-app"
-                        // Note: This strips one suffix segment, which works for standard Deployment-generated
-                        // RS names, but could misfire for custom names like "my-app-v2-7d4b8c9f5" -> "my-app-v2".
-                        // True resolution would require following the ownerReferences chain up to Deployment.
-                        let name = owner
-                            .name
-                            .rsplit_once('-')
-                            .map(|(prefix, _)| prefix.to_string())
-                            .unwrap_or_else(|| owner.name.clone());
-                        ("Deployment".to_string(), name)
-                    } else {
-                        (owner.kind.clone(), owner.name.clone())
-                    }
+                .map(|owner| match owner.kind.as_str() {
+                    "ReplicaSet" => self
+                        .lookup_owner(&self.replicaset_owner, &namespace, &owner.name)
+                        .unwrap_or_else(|| (owner.kind.clone(), owner.name.clone())),
+                    "Job" => self
+                        .lookup_owner(&self.job_owner, &namespace, &owner.name)
+                        .unwrap_or_else(|| (owner.kind.clone(), owner.name.clone())),
+                    _ => (owner.kind.clone(), owner.name.clone()),
                 })
                 .map(|(k, n)| (Some(k), Some(n)))
                 .unwrap_or((None, None));
@@ -206,32 +329,132 @@ impl PodResolver {
         }
     }
 
-    /// Periodically evict stale cgroup_cache entries whose container_id
-    /// no longer exists in pod_store.
-    fn start_cache_cleanup_task(self: &Arc<Self>) {
+    /// Looks up `(namespace, name)`'s recorded owner in `map`, returning
+    /// `(kind, name)` of that owner if it's been observed.
+    fn lookup_owner(
+        &self,
+        map: &RwLock<HashMap<(String, String), (String, String)>>,
+        namespace: &str,
+        name: &str,
+    ) -> Option<(String, String)> {
+        map.read().ok()?.get(&(namespace.to_string(), name.to_string())).cloned()
+    }
+
+    /// Watches ReplicaSets cluster-wide and keeps `replicaset_owner` updated
+    /// from each one's own `ownerReferences`, so a pod owned by a
+    /// ReplicaSet can resolve to the ReplicaSet's owner (normally a
+    /// Deployment) instead of the ReplicaSet itself.
+    fn spawn_replicaset_owner_watcher(self: &Arc<Self>, client: Client) {
+        let api: Api<ReplicaSet> = Api::all(client);
         let resolver = Arc::clone(self);
-        let shutdown = crate::probes::shutdown_flag();
 
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(60));
-            loop {
-                interval.tick().await;
-                if shutdown.load(Ordering::Relaxed) {
-                    break;
+            let stream = watcher(api, watcher::Config::default());
+            tokio::pin!(stream);
+
+            while let Some(event) = stream.next().await {
+                match event {
+                    Ok(watcher::Event::Apply(rs) | watcher::Event::InitApply(rs)) => {
+                        resolver.apply_replicaset_event(&rs, false);
+                    }
+                    Ok(watcher::Event::Delete(rs)) => {
+                        resolver.apply_replicaset_event(&rs, true);
+                    }
+                    Ok(watcher::Event::Init | watcher::Event::InitDone) => {}
+                    Err(e) => {
+                        warn!("K8s ReplicaSet watcher error: {}. Will retry.", e);
+                    }
                 }
+            }
+
+            warn!("K8s ReplicaSet watcher stream ended");
+        });
+    }
+
+    /// Watches Jobs cluster-wide and keeps `job_owner` updated from each
+    /// one's own `ownerReferences`, so a pod owned by a Job can resolve to
+    /// the Job's owner (normally a CronJob) instead of the Job itself.
+    fn spawn_job_owner_watcher(self: &Arc<Self>, client: Client) {
+        let api: Api<Job> = Api::all(client);
+        let resolver = Arc::clone(self);
 
-                if let (Ok(pod_store), Ok(mut cgroup_cache)) =
-                    (resolver.pod_store.read(), resolver.cgroup_cache.write())
-                {
-                    cgroup_cache.retain(|_, v| {
-                        v.as_ref()
-                            .map(|cid| pod_store.contains_key(cid))
-                            .unwrap_or(true)
-                    });
+        tokio::spawn(async move {
+            let stream = watcher(api, watcher::Config::default());
+            tokio::pin!(stream);
+
+            while let Some(event) = stream.next().await {
+                match event {
+                    Ok(watcher::Event::Apply(job) | watcher::Event::InitApply(job)) => {
+                        resolver.apply_job_event(&job, false);
+                    }
+                    Ok(watcher::Event::Delete(job)) => {
+                        resolver.apply_job_event(&job, true);
+                    }
+                    Ok(watcher::Event::Init | watcher::Event::InitDone) => {}
+                    Err(e) => {
+                        warn!("K8s Job watcher error: {}. Will retry.", e);
+                    }
                 }
             }
+
+            warn!("K8s Job watcher stream ended");
         });
     }
+
+    /// Records (or, on deletion, forgets) a ReplicaSet's own owner in
+    /// `replicaset_owner`.
+    fn apply_replicaset_event(&self, rs: &ReplicaSet, is_delete: bool) {
+        let Some(name) = rs.metadata.name.clone() else { return };
+        let namespace = rs.metadata.namespace.clone().unwrap_or_default();
+        let key = (namespace, name);
+
+        if is_delete {
+            if let Ok(mut map) = self.replicaset_owner.write() {
+                map.remove(&key);
+            }
+            return;
+        }
+
+        let owner = rs
+            .metadata
+            .owner_references
+            .as_ref()
+            .and_then(|refs| refs.first())
+            .map(|o| (o.kind.clone(), o.name.clone()));
+
+        if let Some(owner) = owner
+            && let Ok(mut map) = self.replicaset_owner.write()
+        {
+            map.insert(key, owner);
+        }
+    }
+
+    /// Records (or, on deletion, forgets) a Job's own owner in `job_owner`.
+    fn apply_job_event(&self, job: &Job, is_delete: bool) {
+        let Some(name) = job.metadata.name.clone() else { return };
+        let namespace = job.metadata.namespace.clone().unwrap_or_default();
+        let key = (namespace, name);
+
+        if is_delete {
+            if let Ok(mut map) = self.job_owner.write() {
+                map.remove(&key);
+            }
+            return;
+        }
+
+        let owner = job
+            .metadata
+            .owner_references
+            .as_ref()
+            .and_then(|refs| refs.first())
+            .map(|o| (o.kind.clone(), o.name.clone()));
+
+        if let Some(owner) = owner
+            && let Ok(mut map) = self.job_owner.write()
+        {
+            map.insert(key, owner);
+        }
+    }
 }
 
 /// Extract container IDs from a pod's status.
@@ -401,4 +624,139 @@ mod tests {
         };
         assert_eq!(info.service_name(), "standalone-pod");
     }
+
+    fn process_event(pid: u32, tgid: u32, cgroup_id: u64, is_exit: bool) -> ProcessEvent {
+        ProcessEvent { pid, tgid, cgroup_id, comm: [0; 16], is_exit: is_exit as u8 }
+    }
+
+    #[test]
+    fn test_exec_event_primes_cgroup_cache() {
+        let resolver = PodResolver::new();
+        resolver.handle_process_event(&process_event(100, 100, 42, false));
+        assert!(resolver.cgroup_cache.read().unwrap().contains_key(&42));
+    }
+
+    #[test]
+    fn test_cgroup_cache_survives_exit_while_sibling_threads_remain() {
+        let resolver = PodResolver::new();
+        // Two threads (same tgid) exec into the same cgroup.
+        resolver.handle_process_event(&process_event(100, 100, 42, false));
+        resolver.handle_process_event(&process_event(101, 100, 42, false));
+
+        resolver.handle_process_event(&process_event(101, 100, 42, true));
+        assert!(
+            resolver.cgroup_cache.read().unwrap().contains_key(&42),
+            "cache entry should survive while a sibling thread is still running"
+        );
+
+        resolver.handle_process_event(&process_event(100, 100, 42, true));
+        assert!(
+            !resolver.cgroup_cache.read().unwrap().contains_key(&42),
+            "cache entry should be evicted once the last task in the cgroup exits"
+        );
+    }
+
+    #[test]
+    fn test_exit_with_no_prior_exec_evicts_eagerly() {
+        let resolver = PodResolver::new();
+        resolver.handle_process_event(&process_event(100, 100, 42, false));
+        resolver.handle_process_event(&process_event(100, 100, 42, true));
+        // Unobserved exec for the same cgroup_id (e.g. reused by the kernel
+        // before this listener attached) should not leak a stale entry:
+        // re-cache it through the lazy path, with no exec behind it.
+        let _ = resolver.resolve(200, 42);
+        resolver.handle_process_event(&process_event(200, 200, 42, true));
+        assert!(!resolver.cgroup_cache.read().unwrap().contains_key(&42));
+    }
+
+    #[test]
+    fn test_exec_overwrites_stale_cgroup_id_reuse() {
+        let resolver = PodResolver::new();
+        resolver.handle_process_event(&process_event(100, 100, 42, false));
+        resolver.handle_process_event(&process_event(100, 100, 42, true));
+        // cgroup_id 42 reused by a new, unrelated process.
+        resolver.handle_process_event(&process_event(300, 300, 42, false));
+        assert!(resolver.cgroup_cache.read().unwrap().contains_key(&42));
+    }
+
+    fn owner_ref(kind: &str, name: &str) -> k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference {
+        k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference {
+            api_version: "v1".to_string(),
+            kind: kind.to_string(),
+            name: name.to_string(),
+            uid: "00000000-0000-0000-0000-000000000000".to_string(),
+            controller: Some(true),
+            block_owner_deletion: Some(true),
+        }
+    }
+
+    fn replicaset(namespace: &str, name: &str, owner: Option<(&str, &str)>) -> ReplicaSet {
+        ReplicaSet {
+            metadata: k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta {
+                namespace: Some(namespace.to_string()),
+                name: Some(name.to_string()),
+                owner_references: owner.map(|(k, n)| vec![owner_ref(k, n)]),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn job(namespace: &str, name: &str, owner: Option<(&str, &str)>) -> Job {
+        Job {
+            metadata: k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta {
+                namespace: Some(namespace.to_string()),
+                name: Some(name.to_string()),
+                owner_references: owner.map(|(k, n)| vec![owner_ref(k, n)]),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_replicaset_owner_resolves_to_deployment() {
+        let resolver = PodResolver::new();
+        resolver.apply_replicaset_event(
+            &replicaset("default", "my-app-7d4b8c9f5", Some(("Deployment", "my-app"))),
+            false,
+        );
+
+        let owner = resolver.lookup_owner(&resolver.replicaset_owner, "default", "my-app-7d4b8c9f5");
+        assert_eq!(owner, Some(("Deployment".to_string(), "my-app".to_string())));
+    }
+
+    #[test]
+    fn test_replicaset_owner_resolves_correctly_for_versioned_names() {
+        // The old heuristic (strip the last `-`-separated segment) would
+        // mangle this to "my-app-v2" -> "my-app" instead of keeping it, but
+        // following the real ownerReferences chain gets it right regardless
+        // of how the Deployment happens to be named.
+        let resolver = PodResolver::new();
+        resolver.apply_replicaset_event(
+            &replicaset("default", "my-app-v2-7d4b8c9f5", Some(("Deployment", "my-app-v2"))),
+            false,
+        );
+
+        let owner = resolver.lookup_owner(&resolver.replicaset_owner, "default", "my-app-v2-7d4b8c9f5");
+        assert_eq!(owner, Some(("Deployment".to_string(), "my-app-v2".to_string())));
+    }
+
+    #[test]
+    fn test_replicaset_owner_removed_on_delete() {
+        let resolver = PodResolver::new();
+        resolver.apply_replicaset_event(&replicaset("default", "rs1", Some(("Deployment", "my-app"))), false);
+        resolver.apply_replicaset_event(&replicaset("default", "rs1", None), true);
+
+        assert_eq!(resolver.lookup_owner(&resolver.replicaset_owner, "default", "rs1"), None);
+    }
+
+    #[test]
+    fn test_job_owner_resolves_to_cronjob() {
+        let resolver = PodResolver::new();
+        resolver.apply_job_event(&job("default", "backup-28800000", Some(("CronJob", "backup"))), false);
+
+        let owner = resolver.lookup_owner(&resolver.job_owner, "default", "backup-28800000");
+        assert_eq!(owner, Some(("CronJob".to_string(), "backup".to_string())));
+    }
 }
@@ -1,9 +1,13 @@
+pub mod k8s;
+pub mod metrics;
+pub mod process;
+pub mod reload;
 pub mod settings;
 pub mod telemetry;
 
-use std::{collections::HashSet, sync::atomic::Ordering, time::Duration};
+use std::{collections::HashSet, sync::Arc, sync::atomic::Ordering, time::Duration};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use aya::Ebpf;
 use aya_log::EbpfLogger;
 use log::{info, warn};
@@ -16,11 +20,11 @@ use crate::probes::{
     Probe,
     builtin::llm::{
         ExecNotify, ExecPidQueue, LlmProbe, attach_new_targets_for_pids, discovery,
-        setup_exec_watch,
+        exec_allowlist, exec_target_allowed, setup_exec_watch,
     },
     builtin::{
-        DnsProbe, FileAccessProbe, OffCpuProbe, RunqueueLatencyProbe, TcpConnectProbe,
-        TcpRetransProbe, VfsLatencyProbe,
+        DnsProbe, FileAccessProbe, GpuMemProbe, GpuUsageProbe, GpuUtilProbe, NcclCommProbe,
+        OffCpuProbe, RunqueueLatencyProbe, TcpConnectProbe, TcpRetransProbe, VfsLatencyProbe,
     },
     request_shutdown, shutdown_flag,
 };
@@ -50,6 +54,31 @@ impl HoneyBeeEngine {
 
         self.attach_probes()?;
 
+        if !telemetry::probes_ready() {
+            bail!(
+                "No requested probes attached successfully; the eBPF subsystem appears \
+                 unusable on this kernel"
+            );
+        }
+
+        self.start_pod_resolver();
+
+        if let Some(addr) = self.settings.metrics_http_addr.as_deref() {
+            match addr.parse() {
+                Ok(socket_addr) => metrics::start_server(socket_addr),
+                Err(e) => warn!("Invalid metrics_http_addr {:?}: {}", addr, e),
+            }
+        }
+
+        match crate::reload::ConfigReloader::new(&mut self.bpf, self.settings.to_common_config()) {
+            Ok(reloader) => {
+                tokio::spawn(reloader.run());
+            }
+            Err(e) => {
+                warn!("Failed to start config hot-reload, probe config is now fixed for this run: {}", e);
+            }
+        }
+
         // Start LLM dynamic discovery if enabled
         if self.settings.builtin_probes.llm.unwrap_or(false) {
             let (queue, notify) = setup_exec_watch(&mut self.bpf)?;
@@ -70,6 +99,7 @@ impl HoneyBeeEngine {
 
         // Seed with initial targets to avoid duplicate attachments
         let mut known_targets: HashSet<String> = discovery::find_all_targets().unwrap_or_default();
+        let allowlist = exec_allowlist();
         let shutdown = shutdown_flag();
 
         info!("LLM discovery active. Press Ctrl-C to exit.");
@@ -83,7 +113,10 @@ impl HoneyBeeEngine {
 
                     let pids: Vec<u32> = {
                         let mut q = queue.lock().unwrap_or_else(|e| e.into_inner());
-                        q.drain(..).collect()
+                        q.drain(..)
+                            .filter(|target| exec_target_allowed(target, &allowlist))
+                            .map(|target| target.pid)
+                            .collect()
                     };
 
                     if !pids.is_empty()
@@ -103,35 +136,96 @@ impl HoneyBeeEngine {
         Ok(())
     }
 
+    /// Starts Kubernetes pod identity resolution so connection metrics can
+    /// be labeled by namespace/workload instead of raw cgroup_id (see
+    /// `telemetry::set_pod_resolver`). Best-effort: requires `NODE_NAME` to
+    /// be set (the conventional downward-API env var for "which node am I
+    /// running on") and a reachable K8s API; absence of either just leaves
+    /// connection metrics falling back to cgroup_id-derived labels.
+    fn start_pod_resolver(&mut self) {
+        let resolver = Arc::new(k8s::PodResolver::new());
+
+        if let Err(e) = resolver.start_process_lifecycle_listener(&mut self.bpf) {
+            warn!("Failed to start process-lifecycle listener for pod resolution: {}", e);
+        }
+
+        match std::env::var("NODE_NAME") {
+            Ok(node_name) => {
+                let watcher_resolver = resolver.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = watcher_resolver.start_k8s_watcher(node_name).await {
+                        warn!("Failed to start Kubernetes pod watcher: {}", e);
+                    }
+                });
+            }
+            Err(_) => info!(
+                "NODE_NAME not set; connection metrics will report cgroup_id instead of pod/workload labels"
+            ),
+        }
+
+        telemetry::set_pod_resolver(resolver);
+    }
+
     fn attach_probes(&mut self) -> Result<()> {
         let builtin = &self.settings.builtin_probes;
 
-        if builtin.network.tcp_connect.unwrap_or(false)
-            && let Err(e) = TcpConnectProbe::default().attach(&mut self.bpf)
-        {
-            warn!("Failed to attach tcp_connect probe: {}", e);
+        if builtin.network.tcp_connect.unwrap_or(false) {
+            let mut probe = TcpConnectProbe::default();
+            probe.aggregate = builtin.network.tcp_connect_aggregate.unwrap_or(false);
+            probe.histogram = builtin.network.tcp_connect_histogram.unwrap_or(false);
+            let ok = probe.attach(&mut self.bpf);
+            if let Err(e) = &ok {
+                warn!("Failed to attach tcp_connect probe: {}", e);
+                telemetry::record_active_probe("tcp_connect", 0);
+            }
+            telemetry::record_probe_attach_result("tcp_connect", ok.is_ok());
         }
 
-        if builtin.network.tcp_retrans.unwrap_or(false)
-            && let Err(e) = TcpRetransProbe::default().attach(&mut self.bpf)
-        {
-            warn!("Failed to attach tcp_retrans probe: {}", e);
+        if builtin.network.tcp_retrans.unwrap_or(false) {
+            let ok = TcpRetransProbe::default().attach(&mut self.bpf);
+            if let Err(e) = &ok {
+                warn!("Failed to attach tcp_retrans probe: {}", e);
+                telemetry::record_active_probe("tcp_retrans", 0);
+            }
+            telemetry::record_probe_attach_result("tcp_retrans", ok.is_ok());
         }
 
-        if builtin.network.dns.unwrap_or(false)
-            && let Err(e) = DnsProbe::default().attach(&mut self.bpf)
-        {
-            warn!("Failed to attach dns probe: {}", e);
+        if builtin.network.dns.unwrap_or(false) {
+            let ok = DnsProbe::default().attach(&mut self.bpf);
+            if let Err(e) = &ok {
+                warn!("Failed to attach dns probe: {}", e);
+                telemetry::record_active_probe("dns", 0);
+            }
+            telemetry::record_probe_attach_result("dns", ok.is_ok());
         }
 
         if builtin.filesystem.vfs_latency.unwrap_or(false) {
             let mut probe = VfsLatencyProbe::default();
             if let Some(threshold_ms) = builtin.filesystem.vfs_latency_threshold_ms {
-                probe.threshold_ns = u64::from(threshold_ms) * 1_000_000;
+                let latency_ns_threshold = u64::from(threshold_ms) * 1_000_000;
+                probe.read.latency_ns_threshold = latency_ns_threshold;
+                probe.write.latency_ns_threshold = latency_ns_threshold;
+            }
+            if let Some(write_threshold_ms) = builtin.filesystem.vfs_latency_write_threshold_ms {
+                probe.write.latency_ns_threshold = u64::from(write_threshold_ms) * 1_000_000;
+            }
+            if let Some(min_bytes) = builtin.filesystem.vfs_read_min_bytes {
+                probe.read.min_bytes_threshold = min_bytes;
+            }
+            if let Some(min_bytes) = builtin.filesystem.vfs_write_min_bytes {
+                probe.write.min_bytes_threshold = min_bytes;
+            }
+            if let Some(regular_files_only) = builtin.filesystem.vfs_write_regular_files_only {
+                probe.write.regular_files_only = regular_files_only;
             }
-            if let Err(e) = probe.attach(&mut self.bpf) {
+            probe.aggregate = builtin.filesystem.vfs_latency_aggregate.unwrap_or(false);
+            probe.histogram = builtin.filesystem.vfs_latency_histogram.unwrap_or(false);
+            let ok = probe.attach(&mut self.bpf);
+            if let Err(e) = &ok {
                 warn!("Failed to attach vfs_latency probe: {}", e);
+                telemetry::record_active_probe("vfs_latency", 0);
             }
+            telemetry::record_probe_attach_result("vfs_latency", ok.is_ok());
         }
 
         if builtin.filesystem.file_access.unwrap_or(false) {
@@ -139,9 +233,18 @@ impl HoneyBeeEngine {
             if let Some(watched_paths) = builtin.filesystem.watched_paths.clone() {
                 probe.watched_paths = watched_paths;
             }
-            if let Err(e) = probe.attach(&mut self.bpf) {
+            if let Some(watched_prefixes) = builtin.filesystem.watched_prefixes.clone() {
+                probe.watched_prefixes = watched_prefixes;
+            }
+            probe.watch_open = builtin.filesystem.file_access_open.unwrap_or(false);
+            probe.watch_openat2 = builtin.filesystem.file_access_openat2.unwrap_or(false);
+            probe.watch_creat = builtin.filesystem.file_access_creat.unwrap_or(false);
+            let ok = probe.attach(&mut self.bpf);
+            if let Err(e) = &ok {
                 warn!("Failed to attach file_access probe: {}", e);
+                telemetry::record_active_probe("file_access", 0);
             }
+            telemetry::record_probe_attach_result("file_access", ok.is_ok());
         }
 
         if builtin.scheduler.runqueue.unwrap_or(false) {
@@ -149,9 +252,12 @@ impl HoneyBeeEngine {
             if let Some(threshold_ms) = builtin.scheduler.runqueue_threshold_ms {
                 probe.threshold_ns = u64::from(threshold_ms) * 1_000_000;
             }
-            if let Err(e) = probe.attach(&mut self.bpf) {
+            let ok = probe.attach(&mut self.bpf);
+            if let Err(e) = &ok {
                 warn!("Failed to attach runqueue probe: {}", e);
+                telemetry::record_active_probe("runqueue", 0);
             }
+            telemetry::record_probe_attach_result("runqueue", ok.is_ok());
         }
 
         if builtin.scheduler.offcpu.unwrap_or(false) {
@@ -159,14 +265,63 @@ impl HoneyBeeEngine {
             if let Some(threshold_ms) = builtin.scheduler.offcpu_threshold_ms {
                 probe.threshold_ns = u64::from(threshold_ms) * 1_000_000;
             }
-            if let Err(e) = probe.attach(&mut self.bpf) {
+            let ok = probe.attach(&mut self.bpf);
+            if let Err(e) = &ok {
                 warn!("Failed to attach offcpu probe: {}", e);
+                telemetry::record_active_probe("offcpu", 0);
             }
+            telemetry::record_probe_attach_result("offcpu", ok.is_ok());
+        }
+
+        if builtin.gpu.mem.unwrap_or(false) {
+            let ok = GpuMemProbe.attach(&mut self.bpf);
+            if let Err(e) = &ok {
+                warn!("Failed to attach gpu_mem probe: {}", e);
+                telemetry::record_active_probe("gpu_mem", 0);
+            }
+            telemetry::record_probe_attach_result("gpu_mem", ok.is_ok());
+        }
+
+        if builtin.gpu.usage.unwrap_or(false) {
+            let ok = GpuUsageProbe.attach(&mut self.bpf);
+            if let Err(e) = &ok {
+                warn!("Failed to attach gpu_usage probe: {}", e);
+                telemetry::record_active_probe("gpu_usage", 0);
+            }
+            telemetry::record_probe_attach_result("gpu_usage", ok.is_ok());
+        }
+
+        if builtin.gpu.util.unwrap_or(false) {
+            let ok = GpuUtilProbe::default().attach(&mut self.bpf);
+            if let Err(e) = &ok {
+                warn!("Failed to attach gpu_util probe: {}", e);
+                telemetry::record_active_probe("gpu_util", 0);
+            }
+            telemetry::record_probe_attach_result("gpu_util", ok.is_ok());
+        }
+
+        if builtin.gpu.nccl.unwrap_or(false) {
+            let ok = NcclCommProbe.attach(&mut self.bpf);
+            if let Err(e) = &ok {
+                warn!("Failed to attach nccl probe: {}", e);
+                telemetry::record_active_probe("nccl", 0);
+            }
+            telemetry::record_probe_attach_result("nccl", ok.is_ok());
         }
 
         if builtin.llm.unwrap_or(false) {
-            LlmProbe.attach(&mut self.bpf)?;
-            telemetry::record_active_probe("llm", 1);
+            crate::probes::builtin::llm::http::set_custom_provider_config_path(
+                self.settings.custom_probe_config.clone(),
+            );
+            crate::probes::builtin::llm::http::start_provider_watcher();
+            let ok = LlmProbe.attach(&mut self.bpf);
+            if let Err(e) = &ok {
+                warn!("Failed to attach llm probe: {}", e);
+                telemetry::record_active_probe("llm", 0);
+            } else {
+                telemetry::record_active_probe("llm", 1);
+            }
+            telemetry::record_probe_attach_result("llm", ok.is_ok());
         }
 
         Ok(())
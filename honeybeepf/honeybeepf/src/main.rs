@@ -1,20 +1,58 @@
+mod install;
+
 use anyhow::{Context, Result};
 use aya::maps::perf::PerfEventArray;
 use aya::programs::TracePoint;
 use aya::util::online_cpus;
 use aya::{Bpf, include_bytes_aligned};
 use bytes::BytesMut;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use log::{info, warn};
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use tokio::signal;
 use honeybeepf_common::ConnectionEvent;
 
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+
+/// Reconstructs the destination IP from a raw `ConnectionEvent`'s
+/// `dest_addr`/`address_family` - see `ConnectionEvent::dest_addr`'s doc
+/// comment for the byte layout of each family.
+fn format_dest_ip(event: &ConnectionEvent) -> String {
+    match event.address_family {
+        AF_INET => {
+            let octets: [u8; 4] = event.dest_addr[..4].try_into().expect("slice is 4 bytes");
+            Ipv4Addr::from(octets).to_string()
+        }
+        AF_INET6 => Ipv6Addr::from(event.dest_addr).to_string(),
+        family => format!("<unknown family {family}>"),
+    }
+}
+
 #[derive(Debug, Parser)]
 struct Opt {
     /// Verbose output
     #[clap(short, long)]
     verbose: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Interactively generate /etc/honeybeepf/config.toml
+    Init {
+        /// Where to write the generated config file
+        #[clap(long)]
+        config_path: Option<String>,
+    },
+    /// Install this binary under /usr/local/bin and register the systemd unit
+    Install {
+        /// Env file the systemd unit should load
+        #[clap(long)]
+        env_path: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -26,6 +64,16 @@ async fn main() -> Result<()> {
     ))
     .init();
 
+    match opt.command {
+        Some(Command::Init { config_path }) => {
+            return install::run_init_wizard(config_path.as_deref());
+        }
+        Some(Command::Install { env_path }) => {
+            return install::self_install(env_path.as_deref());
+        }
+        None => {}
+    }
+
 
     // eBPF maps are stored in locked kernel memory (can't be swapped to disk). 
     // - `RLIMIT_MEMLOCK` - resource limit for locked-in-memory pages
@@ -48,21 +96,28 @@ async fn main() -> Result<()> {
     //     warn!("Failed to initialize eBPF logger: {}", e);
     // }
 
-    // Load and attach the tracepoint program
-    let program: &mut TracePoint = bpf
-        .program_mut("honeybeepf")
-        .context("Failed to find honeybeepf program")?
+    // Load and attach the enter/exit tracepoint pair - `connect_exit` needs
+    // `connect_enter`'s CONNECT_START entry to compute latency, so both must
+    // be attached for EVENTS to ever receive anything.
+    let enter_program: &mut TracePoint = bpf
+        .program_mut("connect_enter")
+        .context("Failed to find connect_enter program")?
         .try_into()?;
-    
-    program.load()?;
-
-    // Category: syscalls
-    // Event: sys_enter_connect
-    // Full path: /sys/kernel/debug/tracing/events/syscalls/sys_enter_connect/
-    program.attach("syscalls", "sys_enter_connect")
-        .context("Failed to attach tracepoint")?;
+    enter_program.load()?;
+    enter_program
+        .attach("syscalls", "sys_enter_connect")
+        .context("Failed to attach connect_enter tracepoint")?;
+
+    let exit_program: &mut TracePoint = bpf
+        .program_mut("connect_exit")
+        .context("Failed to find connect_exit program")?
+        .try_into()?;
+    exit_program.load()?;
+    exit_program
+        .attach("syscalls", "sys_exit_connect")
+        .context("Failed to attach connect_exit tracepoint")?;
 
-    info!("Tracepoint attached to syscalls:sys_enter_connect");
+    info!("Tracepoints attached to syscalls:sys_enter_connect and syscalls:sys_exit_connect");
 
     
     // 1. `bpf.take_map("EVENTS")` - gets the map by name from eBPF program
@@ -104,18 +159,20 @@ async fn main() -> Result<()> {
                             let ptr = buf.as_ptr() as *const ConnectionEvent;
                             let event = unsafe { ptr.read_unaligned() };
 
-                            // Convert network byte order to host byte order
-                            let dest_ip = Ipv4Addr::from(u32::from_be(event.dest_addr));
+                            let dest_ip = format_dest_ip(&event);
                             let dest_port = u16::from_be(event.dest_port);
 
                             println!(
-                                "[CPU {}] PID {} connecting to {}:{} (cgroup_id={}, ts={})",
+                                "[CPU {}] PID {} connecting to {}:{} (cgroup_id={}, ts={}, latency_ns={}, ret={}, in_progress={})",
                                 cpu_id,
                                 event.pid,
                                 dest_ip,
                                 dest_port,
                                 event.cgroup_id,
-                                event.timestamp
+                                event.timestamp,
+                                event.latency_ns,
+                                event.ret_code,
+                                event.in_progress != 0,
                             );
 
                             // TODO
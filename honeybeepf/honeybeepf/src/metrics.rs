@@ -0,0 +1,158 @@
+//! Prometheus-format metrics export for connection events.
+//!
+//! Complements `telemetry`'s OTLP push export with a pull-based `/metrics`
+//! endpoint, served over HTTP via a small `hyper` server, labeled by
+//! resolved pod identity (`namespace`, `workload`) rather than raw PIDs -
+//! for operators who scrape Prometheus directly instead of running an OTLP
+//! collector.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Mutex, OnceLock};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use log::{info, warn};
+
+/// Bucket label used for a workload's connections once
+/// `MAX_DESTINATIONS_PER_WORKLOAD` distinct destinations have already been
+/// seen, so a noisy or scanning process can't make the registry's memory
+/// grow unbounded.
+const OVERFLOW_DEST_LABEL: &str = "other";
+
+/// Caps the number of distinct `(dest_ip, dest_port)` pairs tracked per
+/// `(namespace, workload)` before further destinations collapse into the
+/// `other` bucket.
+const MAX_DESTINATIONS_PER_WORKLOAD: usize = 64;
+
+#[derive(Default)]
+struct ConnectionBucket {
+    count: u64,
+    latency_ns_sum: u64,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// (namespace, workload, dest_ip, dest_port) → bucket.
+    connections: HashMap<(String, String, String, u16), ConnectionBucket>,
+    /// Number of distinct destinations already tracked per (namespace,
+    /// workload), kept alongside `connections` so the overflow check below
+    /// doesn't need to re-scan the whole map on every connection.
+    distinct_destinations: HashMap<(String, String), usize>,
+}
+
+static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+
+fn registry() -> &'static Mutex<Registry> {
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+/// Records one connection event, labeled by resolved pod identity and
+/// destination. `latency_ns` is the connect() latency the tcp_connect probe
+/// measured for this connection.
+pub fn record_connection(namespace: &str, workload: &str, dest_ip: &str, dest_port: u16, latency_ns: u64) {
+    let mut reg = registry().lock().unwrap_or_else(|e| e.into_inner());
+
+    let workload_key = (namespace.to_string(), workload.to_string());
+    let mut dest_key = (namespace.to_string(), workload.to_string(), dest_ip.to_string(), dest_port);
+
+    if !reg.connections.contains_key(&dest_key) {
+        let distinct = reg.distinct_destinations.entry(workload_key).or_insert(0);
+        if *distinct >= MAX_DESTINATIONS_PER_WORKLOAD {
+            dest_key.2 = OVERFLOW_DEST_LABEL.to_string();
+            dest_key.3 = 0;
+        } else {
+            *distinct += 1;
+        }
+    }
+
+    let bucket = reg.connections.entry(dest_key).or_default();
+    bucket.count += 1;
+    bucket.latency_ns_sum += latency_ns;
+}
+
+/// Renders the current registry as Prometheus text-exposition format.
+fn render() -> String {
+    let reg = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let mut out = String::new();
+
+    out.push_str("# HELP honeybeepf_connections_total Connections observed, labeled by pod identity and destination.\n");
+    out.push_str("# TYPE honeybeepf_connections_total counter\n");
+    for ((namespace, workload, dest_ip, dest_port), bucket) in &reg.connections {
+        out.push_str(&format!(
+            "honeybeepf_connections_total{{namespace=\"{namespace}\",workload=\"{workload}\",dest_ip=\"{dest_ip}\",dest_port=\"{dest_port}\"}} {}\n",
+            bucket.count
+        ));
+    }
+
+    out.push_str("# HELP honeybeepf_connection_latency_ns_sum Cumulative connect() latency in nanoseconds, labeled by pod identity and destination.\n");
+    out.push_str("# TYPE honeybeepf_connection_latency_ns_sum counter\n");
+    for ((namespace, workload, dest_ip, dest_port), bucket) in &reg.connections {
+        out.push_str(&format!(
+            "honeybeepf_connection_latency_ns_sum{{namespace=\"{namespace}\",workload=\"{workload}\",dest_ip=\"{dest_ip}\",dest_port=\"{dest_port}\"}} {}\n",
+            bucket.latency_ns_sum
+        ));
+    }
+
+    out
+}
+
+async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.uri().path() == "/metrics" {
+        Ok(Response::new(Body::from(render())))
+    } else {
+        Ok(Response::builder()
+            .status(404)
+            .body(Body::from("not found"))
+            .unwrap_or_default())
+    }
+}
+
+/// Starts the `/metrics` HTTP server in the background. Safe to call once
+/// at startup; the server runs for the lifetime of the process.
+pub fn start_server(addr: SocketAddr) {
+    tokio::spawn(async move {
+        let make_svc = make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(handle)) });
+
+        info!("Prometheus metrics server listening on {}", addr);
+        if let Err(e) = Server::bind(&addr).serve(make_svc).await {
+            warn!("Prometheus metrics server error: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_connection_accumulates_same_destination() {
+        // Each test touches the process-global REGISTRY, so use distinct
+        // namespace/workload labels to avoid cross-test interference.
+        record_connection("ns-accum", "svc-accum", "10.0.0.1", 443, 1_000);
+        record_connection("ns-accum", "svc-accum", "10.0.0.1", 443, 2_000);
+
+        let reg = registry().lock().unwrap();
+        let bucket = reg
+            .connections
+            .get(&("ns-accum".to_string(), "svc-accum".to_string(), "10.0.0.1".to_string(), 443))
+            .expect("bucket should exist");
+        assert_eq!(bucket.count, 2);
+        assert_eq!(bucket.latency_ns_sum, 3_000);
+    }
+
+    #[test]
+    fn test_record_connection_overflows_to_other_bucket() {
+        for i in 0..(MAX_DESTINATIONS_PER_WORKLOAD + 5) {
+            let ip = format!("10.1.{}.1", i);
+            record_connection("ns-overflow", "svc-overflow", &ip, 443, 0);
+        }
+
+        let reg = registry().lock().unwrap();
+        let overflow = reg
+            .connections
+            .get(&("ns-overflow".to_string(), "svc-overflow".to_string(), OVERFLOW_DEST_LABEL.to_string(), 0));
+        assert_eq!(overflow.map(|b| b.count), Some(5));
+    }
+}
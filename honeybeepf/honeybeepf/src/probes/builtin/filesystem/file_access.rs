@@ -1,17 +1,85 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use aya::Ebpf;
 use aya::maps::{HashMap, RingBuf};
 use aya::programs::TracePoint;
 use honeybeepf_common::FileAccessEvent;
-use log::info;
+use log::{info, warn};
+use std::path::Path;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::probes::Probe;
 use crate::telemetry;
 
+// Keys into the eBPF `TRACEPOINT_OFFSETS` map; must match
+// `honeybeepf-ebpf/src/probes/builtin/file_access.rs`'s `OFFSET_*` constants.
+const OFFSET_FILENAME: u32 = 0;
+const OFFSET_FLAGS: u32 = 1;
+const OFFSET_MODE: u32 = 2;
+const OFFSET_OPEN_FILENAME: u32 = 3;
+const OFFSET_OPEN_FLAGS: u32 = 4;
+const OFFSET_OPEN_MODE: u32 = 5;
+const OFFSET_OPENAT2_FILENAME: u32 = 6;
+const OFFSET_OPENAT2_HOW_PTR: u32 = 7;
+const OFFSET_CREAT_FILENAME: u32 = 8;
+const OFFSET_CREAT_MODE: u32 = 9;
+
+/// Describes one tracepoint's fields to resolve from tracefs: its name, the
+/// tracefs `format` field name for each argument we care about, and the
+/// `OFFSET_*` map key that argument's offset should be written to.
+struct TracepointSpec {
+    tracepoint: &'static str,
+    fields: &'static [(&'static str, u32)],
+}
+
+const TRACEPOINT_SPECS: &[TracepointSpec] = &[
+    TracepointSpec {
+        tracepoint: "sys_enter_openat",
+        fields: &[
+            ("filename", OFFSET_FILENAME),
+            ("flags", OFFSET_FLAGS),
+            ("mode", OFFSET_MODE),
+        ],
+    },
+    TracepointSpec {
+        tracepoint: "sys_enter_open",
+        fields: &[
+            ("filename", OFFSET_OPEN_FILENAME),
+            ("flags", OFFSET_OPEN_FLAGS),
+            ("mode", OFFSET_OPEN_MODE),
+        ],
+    },
+    TracepointSpec {
+        tracepoint: "sys_enter_openat2",
+        fields: &[
+            ("filename", OFFSET_OPENAT2_FILENAME),
+            // `openat2`'s flags/mode live inside the `open_how` struct this
+            // pointer refers to (a fixed uapi ABI, not tracefs-resolved);
+            // see `OpenHow` on the eBPF side.
+            ("how", OFFSET_OPENAT2_HOW_PTR),
+        ],
+    },
+    TracepointSpec {
+        tracepoint: "sys_enter_creat",
+        fields: &[
+            // creat()'s path argument is named `pathname`, not `filename`.
+            ("pathname", OFFSET_CREAT_FILENAME),
+            ("mode", OFFSET_CREAT_MODE),
+        ],
+    },
+];
+
 pub struct FileAccessProbe {
     pub watched_paths: Vec<String>,
+    /// Directory prefixes to watch the whole subtree of, e.g. `/etc/ssl/`
+    /// watches every file under `/etc/ssl/`.
+    pub watched_prefixes: Vec<String>,
+    /// Also hook the legacy `open()` syscall, not just `openat()`.
+    pub watch_open: bool,
+    /// Also hook `openat2()`.
+    pub watch_openat2: bool,
+    /// Also hook `creat()`.
+    pub watch_creat: bool,
     running: Arc<AtomicBool>,
 }
 
@@ -19,6 +87,10 @@ impl FileAccessProbe {
     pub fn new(watched_paths: Vec<String>) -> Self {
         Self {
             watched_paths,
+            watched_prefixes: Vec::new(),
+            watch_open: false,
+            watch_openat2: false,
+            watch_creat: false,
             running: Arc::new(AtomicBool::new(true)),
         }
     }
@@ -28,6 +100,10 @@ impl Default for FileAccessProbe {
     fn default() -> Self {
         Self {
             watched_paths: Vec::new(),
+            watched_prefixes: Vec::new(),
+            watch_open: false,
+            watch_openat2: false,
+            watch_creat: false,
             running: Arc::new(AtomicBool::new(true)), // must be true for event loop
         }
     }
@@ -37,23 +113,29 @@ impl Probe for FileAccessProbe {
     fn attach(&self, bpf: &mut Ebpf) -> Result<()> {
         // Populate watched paths map BEFORE loading the program
         self.populate_watched_paths(bpf)?;
+        self.populate_watched_prefixes(bpf)?;
+        self.populate_tracepoint_offsets(bpf);
 
-        let program: &mut TracePoint = bpf
-            .program_mut("sys_enter_openat")
-            .context("Failed to find sys_enter_openat program")?
-            .try_into()
-            .context("Program is not a TracePoint")?;
+        self.attach_tracepoint(bpf, "sys_enter_openat")?;
 
-        program.load()?;
-        program
-            .attach("syscalls", "sys_enter_openat")
-            .context("Failed to attach sys_enter_openat tracepoint")?;
+        if self.watch_open {
+            self.attach_tracepoint(bpf, "sys_enter_open")?;
+        }
+        if self.watch_openat2 {
+            self.attach_tracepoint(bpf, "sys_enter_openat2")?;
+        }
+        if self.watch_creat {
+            self.attach_tracepoint(bpf, "sys_enter_creat")?;
+        }
 
-        info!("Attached tracepoint: syscalls/sys_enter_openat");
         info!("Watching {} sensitive paths", self.watched_paths.len());
         for path in &self.watched_paths {
             info!("  - {}", path);
         }
+        info!("Watching {} subtree prefixes", self.watched_prefixes.len());
+        for prefix in &self.watched_prefixes {
+            info!("  - {}*", prefix);
+        }
 
         self.spawn_event_handler(bpf)?;
 
@@ -65,6 +147,22 @@ impl Probe for FileAccessProbe {
 }
 
 impl FileAccessProbe {
+    fn attach_tracepoint(&self, bpf: &mut Ebpf, name: &str) -> Result<()> {
+        let program: &mut TracePoint = bpf
+            .program_mut(name)
+            .with_context(|| format!("Failed to find {} program", name))?
+            .try_into()
+            .context("Program is not a TracePoint")?;
+
+        program.load()?;
+        program
+            .attach("syscalls", name)
+            .with_context(|| format!("Failed to attach {} tracepoint", name))?;
+
+        info!("Attached tracepoint: syscalls/{}", name);
+        Ok(())
+    }
+
     /// Populate the WATCHED_PATHS eBPF map with exact path hashes.
     fn populate_watched_paths(&self, bpf: &mut Ebpf) -> Result<()> {
         let mut watched_map: HashMap<_, u64, u8> = bpf
@@ -81,6 +179,146 @@ impl FileAccessProbe {
 
         Ok(())
     }
+
+    /// Populate the WATCHED_PREFIXES eBPF map with directory-prefix hashes.
+    /// Each hash must be computed exactly the way `check_prefix_match` builds
+    /// its running hash in the eBPF program: FNV-1a over the prefix's raw
+    /// bytes, with no normalization — if `prefix` doesn't end in `/`, it only
+    /// matches that exact path, not its children.
+    fn populate_watched_prefixes(&self, bpf: &mut Ebpf) -> Result<()> {
+        let mut prefixes_map: HashMap<_, u64, u8> = bpf
+            .map_mut("WATCHED_PREFIXES")
+            .context("Failed to find WATCHED_PREFIXES map")?
+            .try_into()
+            .context("WATCHED_PREFIXES is not a HashMap")?;
+
+        for prefix in &self.watched_prefixes {
+            let hash = simple_hash(prefix.as_bytes());
+            prefixes_map.insert(hash, 1, 0)?;
+            info!("Added watched prefix: {} (hash: {:#x})", prefix, hash);
+        }
+
+        Ok(())
+    }
+
+    /// Resolves every tracepoint in `TRACEPOINT_SPECS`' field offsets from
+    /// tracefs and writes them into the `TRACEPOINT_OFFSETS` map, so the eBPF
+    /// side reads the ABI that's actually in effect on this kernel/
+    /// architecture instead of the hardcoded x86_64 layout. Best-effort per
+    /// tracepoint: a failure to resolve one tracepoint's offsets only warns
+    /// and leaves its entries unpopulated, so the eBPF program falls back to
+    /// its own hardcoded defaults for that tracepoint alone.
+    ///
+    /// Proper CO-RE field relocation via `tp_btf` (reading offsets straight
+    /// out of `/sys/kernel/btf/vmlinux` with no sysfs text parsing) would be
+    /// the more robust long-term approach, but isn't wired up here yet — this
+    /// still fixes the common case of non-x86_64 kernels without it.
+    fn populate_tracepoint_offsets(&self, bpf: &mut Ebpf) {
+        let map = match bpf.map_mut("TRACEPOINT_OFFSETS") {
+            Some(map) => map,
+            None => {
+                warn!("TRACEPOINT_OFFSETS map not found; using built-in default offsets");
+                return;
+            }
+        };
+        let mut offsets_map: HashMap<_, u32, u64> = match map.try_into() {
+            Ok(map) => map,
+            Err(e) => {
+                warn!("TRACEPOINT_OFFSETS is not a HashMap: {}", e);
+                return;
+            }
+        };
+
+        for spec in TRACEPOINT_SPECS {
+            let offsets = match Self::resolve_tracepoint_offsets(spec) {
+                Ok(offsets) => offsets,
+                Err(e) => {
+                    warn!(
+                        "Failed to resolve {} field offsets from tracefs, falling back to \
+                         built-in defaults: {}",
+                        spec.tracepoint, e
+                    );
+                    continue;
+                }
+            };
+
+            for (key, offset) in offsets {
+                if let Err(e) = offsets_map.insert(key, offset, 0) {
+                    warn!("Failed to write tracepoint offset (key {}): {}", key, e);
+                }
+            }
+        }
+    }
+
+    /// Parses the first readable tracefs `format` file for `spec.tracepoint`
+    /// and returns its fields' `offset:` values, keyed by their `OFFSET_*`
+    /// map key.
+    fn resolve_tracepoint_offsets(spec: &TracepointSpec) -> Result<Vec<(u32, u64)>> {
+        let format_path = [
+            format!(
+                "/sys/kernel/tracing/events/syscalls/{}/format",
+                spec.tracepoint
+            ),
+            format!(
+                "/sys/kernel/debug/tracing/events/syscalls/{}/format",
+                spec.tracepoint
+            ),
+        ]
+        .into_iter()
+        .find(|p| Path::new(p).exists())
+        .with_context(|| {
+            format!(
+                "No {} tracefs format file found (tracing/debugfs not mounted?)",
+                spec.tracepoint
+            )
+        })?;
+
+        let contents = std::fs::read_to_string(&format_path)
+            .with_context(|| format!("Failed to read {}", format_path))?;
+
+        let mut offsets = Vec::new();
+
+        for line in contents.lines() {
+            // Lines look like:
+            //   field:const char * filename;	offset:24;	size:8;	signed:0;
+            let Some(rest) = line.trim().strip_prefix("field:") else {
+                continue;
+            };
+            let Some((decl, tail)) = rest.split_once(';') else {
+                continue;
+            };
+            let Some(name) = decl.trim().rsplit(|c: char| c.is_whitespace() || c == '*').next() else {
+                continue;
+            };
+            let Some((_, key)) = spec.fields.iter().find(|(field, _)| *field == name) else {
+                continue;
+            };
+
+            let Some(offset_str) = tail.trim().strip_prefix("offset:") else {
+                continue;
+            };
+            let Some(offset_str) = offset_str.split(';').next() else {
+                continue;
+            };
+            let Ok(offset) = offset_str.trim().parse::<u64>() else {
+                continue;
+            };
+
+            offsets.push((*key, offset));
+        }
+
+        if offsets.len() != spec.fields.len() {
+            bail!(
+                "Only resolved {}/{} fields from {}",
+                offsets.len(),
+                spec.fields.len(),
+                format_path
+            );
+        }
+
+        Ok(offsets)
+    }
+
     fn spawn_event_handler(&self, bpf: &mut Ebpf) -> Result<()> {
         let ring_buf = RingBuf::try_from(
             bpf.take_map("FILE_ACCESS_EVENTS")
@@ -1,25 +1,92 @@
 use anyhow::{Context, Result};
+use aya::Btf;
 use aya::Ebpf;
-use aya::maps::{HashMap, RingBuf};
-use aya::programs::KProbe;
-use honeybeepf_common::{VfsLatencyEvent, VfsOpType};
-use log::info;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-
-use crate::probes::Probe;
+use aya::maps::{Array, HashMap};
+use aya::programs::{FEntry, FExit, KProbe};
+use honeybeepf_common::{CgroupAggBucket, VfsFieldOffsets, VfsLatencyEvent, VfsOpConfig, VfsOpType};
+use log::{info, warn};
+
+use crate::probes::{
+    Probe, resolve_kernel_symbol, spawn_agg_flush_task, spawn_histogram_handler,
+    spawn_ringbuf_handler,
+};
 use crate::telemetry;
 
+/// Mirrors `VFS_OP_READ`/`VFS_OP_WRITE` in the eBPF crate's `vfs_latency.rs` -
+/// the key space `VFS_OP_CONFIG` is indexed by.
+const VFS_OP_READ: u8 = 0;
+const VFS_OP_WRITE: u8 = 1;
+
+/// Per-operation tunables pushed into `VFS_OP_CONFIG` - the userspace-side
+/// counterpart of `honeybeepf_common::VfsOpConfig`, kept as a separate type
+/// so `bool` reads naturally here instead of the wire format's `u8`.
+#[derive(Debug, Clone, Copy)]
+pub struct VfsOpSettings {
+    pub latency_ns_threshold: u64,
+    pub min_bytes_threshold: u64,
+    pub regular_files_only: bool,
+}
+
+impl VfsOpSettings {
+    /// vfs_read's original hardcoded behavior: regular files only, 1MB/10ms.
+    fn default_read() -> Self {
+        Self {
+            latency_ns_threshold: 10_000_000,
+            min_bytes_threshold: 1024 * 1024,
+            regular_files_only: true,
+        }
+    }
+
+    /// vfs_write's original hardcoded behavior: latency-only, any file type.
+    fn default_write() -> Self {
+        Self {
+            latency_ns_threshold: 10_000_000,
+            min_bytes_threshold: 0,
+            regular_files_only: false,
+        }
+    }
+
+    fn to_map_value(self) -> VfsOpConfig {
+        VfsOpConfig {
+            latency_ns_threshold: self.latency_ns_threshold,
+            min_bytes_threshold: self.min_bytes_threshold,
+            regular_files_only: self.regular_files_only as u8,
+            _pad: [0; 7],
+        }
+    }
+}
+
 pub struct VfsLatencyProbe {
-    pub threshold_ns: u64,
-    running: Arc<AtomicBool>,
+    /// vfs_read's config - see `VfsOpSettings`.
+    pub read: VfsOpSettings,
+    /// vfs_write's config - independently tunable from `read`, e.g. a lower
+    /// latency floor or byte-based filtering that reads have always had but
+    /// writes previously couldn't opt into.
+    pub write: VfsOpSettings,
+    /// When true, exit handlers skip per-event streaming and only update the
+    /// in-kernel `VFS_AGG` per-cgroup totals, which this probe's flush task
+    /// then drains and rolls up periodically instead.
+    pub aggregate: bool,
+    /// When true, exit handlers skip both per-event streaming and `VFS_AGG`
+    /// and only bump the in-kernel `VFS_HIST` log2 latency histogram, which
+    /// this probe's flush task periodically snapshots and exports as
+    /// cumulative `le`-bucketed counters. Takes priority over `aggregate`
+    /// when both are set.
+    pub histogram: bool,
 }
 
 impl VfsLatencyProbe {
+    /// Applies `threshold_ms` as both operations' latency threshold,
+    /// keeping their other defaults (`VfsOpSettings::default_read`/
+    /// `default_write`) - set `read`/`write` directly afterwards for
+    /// independent tuning.
     pub fn new(threshold_ms: u32) -> Self {
+        let latency_ns_threshold = (threshold_ms as u64) * 1_000_000;
         Self {
-            threshold_ns: (threshold_ms as u64) * 1_000_000,
-            running: Arc::new(AtomicBool::new(true)),
+            read: VfsOpSettings { latency_ns_threshold, ..VfsOpSettings::default_read() },
+            write: VfsOpSettings { latency_ns_threshold, ..VfsOpSettings::default_write() },
+            aggregate: false,
+            histogram: false,
         }
     }
 }
@@ -27,32 +94,59 @@ impl VfsLatencyProbe {
 impl Default for VfsLatencyProbe {
     fn default() -> Self {
         Self {
-            threshold_ns: 10_000_000, // 10ms default
-            running: Arc::new(AtomicBool::new(true)),
+            read: VfsOpSettings::default_read(),
+            write: VfsOpSettings::default_write(),
+            aggregate: false,
+            histogram: false,
         }
     }
 }
 
 impl Probe for VfsLatencyProbe {
     fn attach(&self, bpf: &mut Ebpf) -> Result<()> {
-        // Set threshold in eBPF map
-        self.set_threshold(bpf)?;
+        // Set per-operation config in eBPF map
+        self.set_op_config(bpf)?;
+        self.set_aggregate_mode(bpf)?;
+        self.set_histogram_mode(bpf)?;
+        self.set_field_offsets(bpf)?;
 
         // Attach to vfs_write (always)
-        attach_kprobe_pair(bpf, "vfs_write_entry", "vfs_write_exit", "vfs_write")?;
-        info!("Attached kprobe pair: vfs_write");
+        attach_trace_pair(
+            bpf,
+            "vfs_write_entry_fentry",
+            "vfs_write_exit_fexit",
+            "vfs_write_entry",
+            "vfs_write_exit",
+            &["vfs_write"],
+        )?;
 
         // Attach to vfs_read (with smart filtering in eBPF)
         // eBPF filters: regular files only + (large read OR slow read)
-        attach_kprobe_pair(bpf, "vfs_read_entry", "vfs_read_exit", "vfs_read")?;
-        info!("Attached kprobe pair: vfs_read (filtered: regular files, large/slow only)");
+        attach_trace_pair(
+            bpf,
+            "vfs_read_entry_fentry",
+            "vfs_read_exit_fexit",
+            "vfs_read_entry",
+            "vfs_read_exit",
+            &["vfs_read"],
+        )?;
+        info!("vfs_read filtering: regular files, large/slow only");
 
-        self.spawn_event_handler(bpf)?;
+        if self.histogram {
+            self.spawn_histogram_flush(bpf)?;
+        } else if self.aggregate {
+            self.spawn_agg_flush(bpf)?;
+        } else {
+            self.spawn_event_handler(bpf)?;
+        }
 
         telemetry::record_active_probe("vfs_latency", 1);
         info!(
-            "VfsLatencyProbe attached (threshold={}ms, read+write)",
-            self.threshold_ns / 1_000_000
+            "VfsLatencyProbe attached (read_threshold={}ms, write_threshold={}ms, aggregate={}, histogram={})",
+            self.read.latency_ns_threshold / 1_000_000,
+            self.write.latency_ns_threshold / 1_000_000,
+            self.aggregate,
+            self.histogram
         );
 
         Ok(())
@@ -60,80 +154,153 @@ impl Probe for VfsLatencyProbe {
 }
 
 impl VfsLatencyProbe {
-    fn set_threshold(&self, bpf: &mut Ebpf) -> Result<()> {
-        let mut threshold_map: HashMap<_, u32, u64> = bpf
-            .map_mut("VFS_THRESHOLD_NS")
-            .context("Failed to find VFS_THRESHOLD_NS map")?
+    fn set_op_config(&self, bpf: &mut Ebpf) -> Result<()> {
+        let mut config_map: HashMap<_, u8, VfsOpConfig> = bpf
+            .map_mut("VFS_OP_CONFIG")
+            .context("Failed to find VFS_OP_CONFIG map")?
             .try_into()
-            .context("VFS_THRESHOLD_NS is not a HashMap")?;
+            .context("VFS_OP_CONFIG is not a HashMap")?;
 
-        threshold_map.insert(0, self.threshold_ns, 0)?;
-        info!("Set VFS latency threshold to {}ns", self.threshold_ns);
+        config_map.insert(VFS_OP_READ, self.read.to_map_value(), 0)?;
+        config_map.insert(VFS_OP_WRITE, self.write.to_map_value(), 0)?;
+        info!(
+            "Set VFS op config: read={:?}, write={:?}",
+            self.read, self.write
+        );
 
         Ok(())
     }
 
-    fn spawn_event_handler(&self, bpf: &mut Ebpf) -> Result<()> {
-        let ring_buf = RingBuf::try_from(
-            bpf.take_map("VFS_EVENTS")
-                .context("Failed to find VFS_EVENTS map")?,
-        )?;
+    fn set_aggregate_mode(&self, bpf: &mut Ebpf) -> Result<()> {
+        let mut mode_map: HashMap<_, u32, u8> = bpf
+            .map_mut("VFS_AGGREGATE_MODE")
+            .context("Failed to find VFS_AGGREGATE_MODE map")?
+            .try_into()
+            .context("VFS_AGGREGATE_MODE is not a HashMap")?;
 
-        let running = self.running.clone();
-
-        std::thread::spawn(move || {
-            let mut ring_buf = ring_buf;
-
-            while running.load(Ordering::Relaxed) {
-                if let Some(item) = ring_buf.next() {
-                    if item.len() >= std::mem::size_of::<VfsLatencyEvent>() {
-                        let event: VfsLatencyEvent = unsafe {
-                            std::ptr::read_unaligned(item.as_ptr() as *const VfsLatencyEvent)
-                        };
-
-                        let comm = std::str::from_utf8(&event.comm)
-                            .unwrap_or("<invalid>")
-                            .trim_matches(char::from(0));
-
-                        let filename = std::str::from_utf8(&event.filename)
-                            .unwrap_or("<invalid>")
-                            .trim_matches(char::from(0));
-
-                        let op = match VfsOpType::from(event.op_type) {
-                            VfsOpType::Read => "READ",
-                            VfsOpType::Write => "WRITE",
-                        };
-
-                        // Categorize file type
-                        let category = categorize_file(filename);
-
-                        info!(
-                            "VFS_{} pid={} comm={} file={} bytes={} latency={} category={} cgroup={}",
-                            op,
-                            event.metadata.pid,
-                            comm,
-                            filename,
-                            format_bytes(event.bytes),
-                            format_duration(event.latency_ns),
-                            category,
-                            event.metadata.cgroup_id,
-                        );
-
-                        telemetry::record_vfs_event(
-                            op.to_lowercase().as_str(),
-                            filename,
-                            event.bytes,
-                            event.latency_ns,
-                            event.metadata.cgroup_id,
-                        );
-                    }
-                }
-                std::thread::sleep(std::time::Duration::from_millis(10));
-            }
-        });
+        mode_map.insert(0, self.aggregate as u8, 0)?;
+
+        Ok(())
+    }
+
+    fn set_histogram_mode(&self, bpf: &mut Ebpf) -> Result<()> {
+        let mut mode_map: HashMap<_, u32, u8> = bpf
+            .map_mut("VFS_HISTOGRAM_MODE")
+            .context("Failed to find VFS_HISTOGRAM_MODE map")?
+            .try_into()
+            .context("VFS_HISTOGRAM_MODE is not a HashMap")?;
+
+        mode_map.insert(0, self.histogram as u8, 0)?;
+
+        Ok(())
+    }
+
+    /// Resolves `struct file`/`struct inode` field offsets from the running
+    /// kernel's BTF and pushes them into `VFS_OFFSETS`, so `is_regular_file`/
+    /// `read_filename_from_file` (eBPF side) don't depend on the compiled-in,
+    /// kernel-version-dependent offset constants they otherwise fall back
+    /// to. Best-effort: if BTF is unavailable or any expected field can't be
+    /// found, `VFS_OFFSETS` is left unconfigured and the probes keep using
+    /// those fallback constants.
+    fn set_field_offsets(&self, bpf: &mut Ebpf) -> Result<()> {
+        let Ok(btf) = Btf::from_sys_fs() else {
+            info!("No kernel BTF available, VFS probes will use compiled-in field offsets");
+            return Ok(());
+        };
+
+        let Some(offsets) = resolve_vfs_field_offsets(&btf) else {
+            warn!(
+                "Couldn't resolve one or more VFS struct field offsets from BTF, \
+                 falling back to compiled-in offsets"
+            );
+            return Ok(());
+        };
+
+        let mut offsets_map: Array<_, VfsFieldOffsets> = bpf
+            .map_mut("VFS_OFFSETS")
+            .context("Failed to find VFS_OFFSETS map")?
+            .try_into()
+            .context("VFS_OFFSETS is not an Array")?;
+
+        offsets_map.set(0, offsets, 0)?;
+        info!("Resolved VFS struct field offsets from kernel BTF");
 
         Ok(())
     }
+
+    /// Histogram-mode equivalent of `spawn_event_handler`: periodically
+    /// snapshots `VFS_HIST` and exports each bucket's delta as a cumulative
+    /// `le`-bucketed counter instead of streaming one event per call.
+    fn spawn_histogram_flush(&self, bpf: &mut Ebpf) -> Result<()> {
+        spawn_histogram_handler(bpf, "VFS_HIST", 2, |op_type, bucket, delta| {
+            let op = match VfsOpType::from(op_type) {
+                VfsOpType::Read => "read",
+                VfsOpType::Write => "write",
+            };
+            telemetry::record_histogram_bucket("vfs_latency", op, bucket, delta);
+        })
+    }
+
+    /// Aggregated-mode equivalent of `spawn_event_handler`: drains and
+    /// cgroup-rolls-up `VFS_AGG` on a timer instead of streaming one event
+    /// per call.
+    fn spawn_agg_flush(&self, bpf: &mut Ebpf) -> Result<()> {
+        spawn_agg_flush_task(bpf, "VFS_AGG", |cgroup_id: u64, bucket: CgroupAggBucket| {
+            let avg_latency_ns = bucket
+                .sum_latency_ns
+                .checked_div(bucket.count)
+                .unwrap_or(0);
+
+            info!(
+                "VFS_AGG cgroup={} count={} avg_latency={} max_latency={} bytes={}",
+                cgroup_id,
+                bucket.count,
+                format_duration(avg_latency_ns),
+                format_duration(bucket.max_latency_ns),
+                format_bytes(bucket.sum_bytes),
+            );
+        })
+    }
+
+    fn spawn_event_handler(&self, bpf: &mut Ebpf) -> Result<()> {
+        spawn_ringbuf_handler(bpf, "VFS_EVENTS", move |event: VfsLatencyEvent| {
+            let comm = std::str::from_utf8(&event.comm)
+                .unwrap_or("<invalid>")
+                .trim_matches(char::from(0));
+
+            let filename = std::str::from_utf8(&event.filename)
+                .unwrap_or("<invalid>")
+                .trim_matches(char::from(0));
+
+            let op = match VfsOpType::from(event.op_type) {
+                VfsOpType::Read => "READ",
+                VfsOpType::Write => "WRITE",
+            };
+
+            // Categorize file type
+            let category = categorize_file(filename);
+
+            info!(
+                "VFS_{} pid={} comm={} file={} bytes={} latency={} category={} cgroup={}",
+                op,
+                event.metadata.pid,
+                comm,
+                filename,
+                format_bytes(event.bytes),
+                format_duration(event.latency_ns),
+                category,
+                event.metadata.cgroup_id,
+            );
+
+            telemetry::record_vfs_event(
+                op.to_lowercase().as_str(),
+                filename,
+                event.bytes,
+                event.latency_ns,
+                event.metadata.cgroup_id,
+            );
+        })
+    }
 }
 
 /// Categorize file by extension for model/dataset identification
@@ -181,29 +348,158 @@ fn format_bytes(bytes: u64) -> String {
     }
 }
 
+/// Attaches `entry_name`/`exit_name` to whichever of `target_fn_candidates`
+/// actually exists on this kernel, resolved via `resolve_kernel_symbol`
+/// (exact name first, then `.constprop.`/`.isra.` suffixed variants) so
+/// compiler-cloned or renamed kernel functions don't silently fail to
+/// attach.
 fn attach_kprobe_pair(
     bpf: &mut Ebpf,
     entry_name: &str,
     exit_name: &str,
-    target_fn: &str,
+    target_fn_candidates: &[&str],
 ) -> Result<()> {
+    let target_fn = resolve_kernel_symbol(target_fn_candidates)?;
+
     let entry: &mut KProbe = bpf
         .program_mut(entry_name)
         .context(format!("Failed to find {} program", entry_name))?
         .try_into()?;
     entry.load()?;
-    entry.attach(target_fn, 0)?;
+    entry.attach(&target_fn, 0)?;
 
     let exit: &mut KProbe = bpf
         .program_mut(exit_name)
         .context(format!("Failed to find {} program", exit_name))?
         .try_into()?;
     exit.load()?;
-    exit.attach(target_fn, 0)?;
+    exit.attach(&target_fn, 0)?;
 
     Ok(())
 }
 
+/// True if this kernel exposes its own BTF, a precondition for fentry/fexit
+/// trampoline programs. Whether `target_fn` specifically has a BTF func
+/// proto isn't checked separately here - `FEntry`/`FExit::load` fails
+/// cleanly when it doesn't, and that failure is what actually drives
+/// `attach_trace_pair`'s fallback to kprobe/kretprobe.
+fn btf_available() -> bool {
+    std::path::Path::new("/sys/kernel/btf/vmlinux").exists()
+}
+
+/// Attaches `fentry_name`/`fexit_name` (fentry/fexit) to `target_fn` on
+/// kernels with BTF/trampoline support - roughly half the per-call overhead
+/// of the kprobe/kretprobe pair, since the hook runs through a generated
+/// trampoline instead of a breakpoint. Falls back to
+/// `kprobe_entry_name`/`kprobe_exit_name` (kprobe/kretprobe, via
+/// `attach_kprobe_pair`) when BTF is unavailable or the fentry/fexit load
+/// fails for any other reason (e.g. `target_fn` inlined/missing from BTF on
+/// this kernel build).
+fn attach_trace_pair(
+    bpf: &mut Ebpf,
+    fentry_name: &str,
+    fexit_name: &str,
+    kprobe_entry_name: &str,
+    kprobe_exit_name: &str,
+    target_fn_candidates: &[&str],
+) -> Result<()> {
+    // fentry/fexit attach against BTF, which only knows a function under its
+    // canonical (first-candidate) name - the `.constprop`/`.isra` renames
+    // `resolve_kernel_symbol` works around are purely a kprobe/kallsyms
+    // concern.
+    let target_fn = target_fn_candidates[0];
+
+    if btf_available() {
+        match attach_fentry_pair(bpf, fentry_name, fexit_name, target_fn) {
+            Ok(()) => {
+                info!("Attached {} via fentry/fexit (fast path)", target_fn);
+                return Ok(());
+            }
+            Err(e) => {
+                warn!(
+                    "fentry/fexit attach for {} failed ({}), falling back to kprobe/kretprobe",
+                    target_fn, e
+                );
+            }
+        }
+    } else {
+        info!(
+            "No kernel BTF at /sys/kernel/btf/vmlinux, using kprobe/kretprobe for {}",
+            target_fn
+        );
+    }
+
+    attach_kprobe_pair(
+        bpf,
+        kprobe_entry_name,
+        kprobe_exit_name,
+        target_fn_candidates,
+    )?;
+    info!("Attached {} via kprobe/kretprobe (fallback path)", target_fn);
+
+    Ok(())
+}
+
+fn attach_fentry_pair(
+    bpf: &mut Ebpf,
+    fentry_name: &str,
+    fexit_name: &str,
+    target_fn: &str,
+) -> Result<()> {
+    let btf = Btf::from_sys_fs().context("Failed to load kernel BTF")?;
+
+    let entry: &mut FEntry = bpf
+        .program_mut(fentry_name)
+        .context(format!("Failed to find {} program", fentry_name))?
+        .try_into()?;
+    entry.load(target_fn, &btf)?;
+    entry.attach()?;
+
+    let exit: &mut FExit = bpf
+        .program_mut(fexit_name)
+        .context(format!("Failed to find {} program", fexit_name))?
+        .try_into()?;
+    exit.load(target_fn, &btf)?;
+    exit.attach()?;
+
+    Ok(())
+}
+
+/// Walks the given BTF's type table for the `struct file`/`struct path`/
+/// `struct dentry`/`struct qstr`/`struct inode` members `is_regular_file`/
+/// `read_filename_from_file` need, folding `file->f_path` and `path->dentry`
+/// into a single `f_path_dentry_offset` since the probes only ever chase
+/// that pair as one hop.
+fn resolve_vfs_field_offsets(btf: &Btf) -> Option<VfsFieldOffsets> {
+    let f_inode_offset = btf_member_offset(btf, "file", "f_inode")?;
+    let f_path_offset = btf_member_offset(btf, "file", "f_path")?;
+    let dentry_offset = btf_member_offset(btf, "path", "dentry")?;
+    let d_name_offset = btf_member_offset(btf, "dentry", "d_name")?;
+    let qstr_name_offset = btf_member_offset(btf, "qstr", "name")?;
+    let i_mode_offset = btf_member_offset(btf, "inode", "i_mode")?;
+
+    Some(VfsFieldOffsets {
+        f_inode_offset,
+        f_path_dentry_offset: f_path_offset + dentry_offset,
+        d_name_offset,
+        qstr_name_offset,
+        i_mode_offset,
+        configured: 1,
+        _pad: [0],
+    })
+}
+
+/// Looks up `struct_name`'s BTF type and returns `field_name`'s byte offset
+/// within it, or `None` if the kernel's BTF doesn't describe that struct/field
+/// (e.g. an out-of-tree or heavily backported kernel).
+fn btf_member_offset(btf: &Btf, struct_name: &str, field_name: &str) -> Option<u16> {
+    let members = btf.resolve_struct_members(struct_name).ok()?;
+    members
+        .into_iter()
+        .find(|member| member.name == field_name)
+        .map(|member| (member.bit_offset / 8) as u16)
+}
+
 pub fn format_duration(ns: u64) -> String {
     if ns >= 1_000_000_000 {
         format!("{:.2}s", ns as f64 / 1_000_000_000.0)
@@ -3,14 +3,65 @@
 //! Attaches uprobes to libnccl.so functions to monitor GPU collective
 //! communication patterns in AI training clusters.
 
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use aya::{Ebpf, programs::UProbe};
-use honeybeepf_common::{NcclEvent, NcclOpType};
+use aya::Ebpf;
+use aya::maps::HashMap as AyaHashMap;
+use aya::programs::UProbe;
+use honeybeepf_common::{NcclCommEvent, NcclEvent, NcclOpType, PendingNcclOp};
 use log::{info, warn};
 
-use crate::probes::{Probe, spawn_ringbuf_handler};
+use crate::probes::{Probe, shutdown_flag, spawn_ringbuf_handler};
+
+/// How often the stall-sweep thread rescans `PENDING_NCCL_OPS`.
+const STALL_SWEEP_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Default "this op has probably stalled" threshold; override with
+/// `HONEYBEEPF_NCCL_STALL_THRESHOLD_MS`.
+const DEFAULT_STALL_THRESHOLD_MS: u64 = 10_000;
+
+/// Minimum time between repeated warnings for the same still-stalled op, so
+/// a multi-minute hang logs escalating updates rather than one line every
+/// sweep interval.
+const STALL_REPORT_BACKOFF: Duration = Duration::from_secs(10);
+
+/// A pending NCCL op that has been running longer than the stall threshold
+/// without returning - the signature of a straggler rank or a deadlocked
+/// ring, which `emit_nccl_event` never surfaces on its own since it only
+/// runs once the call actually returns.
+#[derive(Clone, Copy)]
+struct NcclStallEvent {
+    tid: u64,
+    op_type: u8,
+    count: u64,
+    comm_ptr: u64,
+    stalled_ns: u64,
+}
+
+/// Rank/world-size info for a resolved communicator, recorded from a
+/// `NcclCommEvent` and keyed by `comm_ptr` so later collective events
+/// carrying the same pointer can be annotated with it instead of just
+/// `pid`/`comm`.
+#[derive(Clone, Copy)]
+struct CommInfo {
+    rank: i32,
+    nranks: i32,
+    unique_id_hash: u64,
+    /// Set once a `ncclCommAbort` event is observed for this `comm_ptr`, so
+    /// later events referencing the same communicator can be flagged as
+    /// belonging to a dead comm instead of silently looking healthy.
+    aborted: bool,
+}
+
+/// `comm_ptr -> CommInfo` registry, shared between the `NCCL_COMM_EVENTS`
+/// handler (which populates it) and the `NCCL_EVENTS` handler (which reads
+/// it to annotate collective/P2P ops).
+type CommRegistry = Arc<Mutex<HashMap<u64, CommInfo>>>;
 
 /// NCCL library search paths (ordered by priority)
 const NCCL_LIB_PATHS: &[&str] = &[
@@ -116,6 +167,21 @@ const NCCL_PROBES: &[NcclProbeConfig] = &[
         entry_prog: "nccl_get_version_enter",
         exit_prog: "nccl_get_version_exit",
     },
+    NcclProbeConfig {
+        symbol: "ncclCommInitRank",
+        entry_prog: "nccl_comm_init_rank_enter",
+        exit_prog: "nccl_comm_init_rank_exit",
+    },
+    NcclProbeConfig {
+        symbol: "ncclCommAbort",
+        entry_prog: "nccl_comm_abort_enter",
+        exit_prog: "nccl_comm_abort_exit",
+    },
+    NcclProbeConfig {
+        symbol: "ncclCommGetAsyncError",
+        entry_prog: "nccl_comm_get_async_error_enter",
+        exit_prog: "nccl_comm_get_async_error_exit",
+    },
 ];
 
 fn op_type_name(op: NcclOpType) -> &'static str {
@@ -130,6 +196,8 @@ fn op_type_name(op: NcclOpType) -> &'static str {
         NcclOpType::GroupStart => "GroupStart",
         NcclOpType::GroupEnd => "GroupEnd",
         NcclOpType::CommInitRank => "CommInitRank",
+        NcclOpType::CommAbort => "CommAbort",
+        NcclOpType::GetAsyncError => "GetAsyncError",
         NcclOpType::GetVersion => "GetVersion",
         NcclOpType::Unknown => "Unknown",
     }
@@ -159,6 +227,80 @@ fn format_duration(ns: u64) -> String {
     }
 }
 
+/// Per-op correction factor relating algorithm bandwidth (`algbw`, the raw
+/// bytes-moved-by-the-caller rate) to bus bandwidth (`busbw`, the rate data
+/// actually moves over the interconnect) - the same factors `nccl-tests`
+/// uses, since different collectives move different multiples of the
+/// user's buffer size over the wire for a given `n` (world size). `None`
+/// means busbw isn't meaningful here: a single-rank communicator moves no
+/// data over any interconnect, and ops with no defined factor (group
+/// markers, version queries, comm init) never carry a byte count anyway.
+fn busbw_correction_factor(op: NcclOpType, nranks: i32) -> Option<f64> {
+    if nranks <= 1 {
+        return None;
+    }
+    let n = f64::from(nranks);
+    match op {
+        NcclOpType::AllReduce => Some(2.0 * (n - 1.0) / n),
+        NcclOpType::AllGather | NcclOpType::ReduceScatter | NcclOpType::AllToAll => {
+            Some((n - 1.0) / n)
+        }
+        NcclOpType::Broadcast | NcclOpType::Send | NcclOpType::Recv => Some(1.0),
+        _ => None,
+    }
+}
+
+fn format_bandwidth(bytes_per_sec: f64) -> String {
+    if bytes_per_sec >= 1e9 {
+        format!("{:.2} GB/s", bytes_per_sec / 1e9)
+    } else if bytes_per_sec >= 1e6 {
+        format!("{:.2} MB/s", bytes_per_sec / 1e6)
+    } else if bytes_per_sec >= 1e3 {
+        format!("{:.2} KB/s", bytes_per_sec / 1e3)
+    } else {
+        format!("{:.2} B/s", bytes_per_sec)
+    }
+}
+
+/// `HONEYBEEPF_NCCL_STALL_THRESHOLD_MS` override, falling back to
+/// `DEFAULT_STALL_THRESHOLD_MS` if unset or unparsable.
+fn stall_threshold_ms() -> u64 {
+    std::env::var("HONEYBEEPF_NCCL_STALL_THRESHOLD_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_STALL_THRESHOLD_MS)
+}
+
+/// Nanoseconds since boot, in the same clock base `bpf_ktime_get_ns()` uses
+/// in-kernel (`CLOCK_MONOTONIC`), read with `clock_gettime`.
+fn monotonic_ns_since_boot() -> Option<u64> {
+    let mut ts: libc::timespec = unsafe { std::mem::zeroed() };
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) } != 0 {
+        return None;
+    }
+    Some(ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64)
+}
+
+fn report_stall(event: NcclStallEvent, comm_registry: &CommRegistry) {
+    let op_name = op_type_name(NcclOpType::from(event.op_type));
+    let rank_suffix = comm_registry
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&event.comm_ptr)
+        .map(|info| format!(" rank={}/{}", info.rank, info.nranks))
+        .unwrap_or_default();
+
+    warn!(
+        "NCCL_STALL tid={} op={}{} count={} stalled={} (threshold={}ms)",
+        event.tid,
+        op_name,
+        rank_suffix,
+        event.count,
+        format_duration(event.stalled_ns),
+        stall_threshold_ms(),
+    );
+}
+
 pub struct NcclCommProbe;
 
 impl Probe for NcclCommProbe {
@@ -222,8 +364,50 @@ impl Probe for NcclCommProbe {
 
         info!("Successfully attached {} NCCL probe pairs", attached_count);
 
+        let comm_registry: CommRegistry = Arc::new(Mutex::new(HashMap::new()));
+
+        self.spawn_stall_watchdog(bpf, comm_registry.clone())?;
+
+        // Handle ncclCommInitRank events, building the comm_ptr -> CommInfo
+        // registry the NCCL_EVENTS handler below annotates collectives with.
+        let registry_for_comm_init = comm_registry.clone();
+        spawn_ringbuf_handler(bpf, "NCCL_COMM_EVENTS", move |event: NcclCommEvent| {
+            let comm = std::str::from_utf8(&event.comm)
+                .unwrap_or("<invalid>")
+                .trim_matches(char::from(0));
+
+            info!(
+                "NCCL_CommInitRank pid={} comm={} comm_ptr={:#x} rank={}/{} unique_id_hash={:#x} duration={} ret={} cgroup_id={}",
+                event.metadata.pid,
+                comm,
+                event.comm_ptr,
+                event.rank,
+                event.nranks,
+                event.unique_id_hash,
+                format_duration(event.duration_ns),
+                event.ret_code,
+                event.metadata.cgroup_id,
+            );
+
+            if event.ret_code == 0 && event.comm_ptr != 0 {
+                let mut registry = registry_for_comm_init
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                registry.insert(
+                    event.comm_ptr,
+                    CommInfo {
+                        rank: event.rank,
+                        nranks: event.nranks,
+                        unique_id_hash: event.unique_id_hash,
+                        aborted: false,
+                    },
+                );
+            }
+        })?;
+
         // Handle NCCL events
-        spawn_ringbuf_handler(bpf, "NCCL_EVENTS", |event: NcclEvent| {
+        let registry_for_collectives = comm_registry;
+        spawn_ringbuf_handler(bpf, "NCCL_EVENTS", move |event: NcclEvent| {
             let comm = std::str::from_utf8(&event.comm)
                 .unwrap_or("<invalid>")
                 .trim_matches(char::from(0));
@@ -231,26 +415,128 @@ impl Probe for NcclCommProbe {
             let op_type = NcclOpType::from(event.op_type);
             let op_name = op_type_name(op_type);
 
+            if event.op_type == NcclOpType::CommAbort as u8 {
+                let mut registry = registry_for_collectives
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                registry
+                    .entry(event.comm_ptr)
+                    .and_modify(|info| info.aborted = true)
+                    .or_insert(CommInfo {
+                        rank: -1,
+                        nranks: 0,
+                        unique_id_hash: 0,
+                        aborted: true,
+                    });
+                drop(registry);
+
+                warn!(
+                    "NCCL_CommAbort pid={} comm={} comm_ptr={:#x} ret={} cgroup_id={}",
+                    event.metadata.pid,
+                    comm,
+                    event.comm_ptr,
+                    event.ret_code,
+                    event.metadata.cgroup_id,
+                );
+                return;
+            }
+
+            let comm_info = registry_for_collectives
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&event.comm_ptr)
+                .copied();
+            let rank_suffix = comm_info
+                .map(|info| format!(" rank={}/{}", info.rank, info.nranks))
+                .unwrap_or_default();
+
+            if event.op_type == NcclOpType::GetAsyncError as u8 {
+                // For this op, event.ret_code carries the *async* error code
+                // read from the out-parameter, not the query call's own
+                // return value - see try_nccl_comm_get_async_error_exit.
+                let async_error = event.ret_code;
+                if async_error != 0 {
+                    warn!(
+                        "NCCL_AsyncError pid={} comm={}{} comm_ptr={:#x} async_error={} cgroup_id={}",
+                        event.metadata.pid,
+                        comm,
+                        rank_suffix,
+                        event.comm_ptr,
+                        async_error,
+                        event.metadata.cgroup_id,
+                    );
+                } else {
+                    info!(
+                        "NCCL_GetAsyncError pid={} comm={}{} comm_ptr={:#x} cgroup_id={}",
+                        event.metadata.pid, comm, rank_suffix, event.comm_ptr, event.metadata.cgroup_id,
+                    );
+                }
+                return;
+            }
+
+            // `stream` attributes the op to a concurrent CUDA stream;
+            // `group` ties together the batch of calls issued between a
+            // ncclGroupStart/ncclGroupEnd pair so they read as one logical
+            // communication phase. Both are 0 (omitted) when not applicable.
+            let stream_suffix = (event.stream_ptr != 0)
+                .then(|| format!(" stream={:#x}", event.stream_ptr))
+                .unwrap_or_default();
+            let group_suffix = (event.group_id != 0)
+                .then(|| format!(" group={:#x}", event.group_id))
+                .unwrap_or_default();
+
             // Format output based on operation type
             if event.bytes_transferred > 0 {
+                // algbw: the rate the caller's buffer moved at. busbw
+                // additionally corrects for how many multiples of that
+                // buffer size the collective actually pushes over the
+                // interconnect - skipped (duration == 0) or only partially
+                // known (nranks unresolved) both fall back gracefully.
+                let algbw_bps = (event.duration_ns > 0)
+                    .then(|| event.bytes_transferred as f64 / (event.duration_ns as f64 / 1e9));
+
+                let bw_suffix = match (algbw_bps, comm_info) {
+                    (Some(algbw), Some(info)) => {
+                        match busbw_correction_factor(op_type, info.nranks) {
+                            Some(factor) => format!(
+                                " algbw={} busbw={}",
+                                format_bandwidth(algbw),
+                                format_bandwidth(algbw * factor)
+                            ),
+                            None => format!(" algbw={}", format_bandwidth(algbw)),
+                        }
+                    }
+                    (Some(algbw), None) => {
+                        format!(" algbw={} (nranks unknown, no busbw)", format_bandwidth(algbw))
+                    }
+                    (None, _) => String::new(),
+                };
+
                 info!(
-                    "NCCL_{} pid={} comm={} count={} bytes={} duration={} ret={} cgroup_id={}",
+                    "NCCL_{} pid={} comm={}{}{}{} count={} bytes={} duration={}{} ret={} cgroup_id={}",
                     op_name,
                     event.metadata.pid,
                     comm,
+                    rank_suffix,
+                    stream_suffix,
+                    group_suffix,
                     event.count,
                     format_bytes(event.bytes_transferred),
                     format_duration(event.duration_ns),
+                    bw_suffix,
                     event.ret_code,
                     event.metadata.cgroup_id,
                 );
             } else {
                 // Simple operations like GroupStart/End, GetVersion
                 info!(
-                    "NCCL_{} pid={} comm={} duration={} ret={} cgroup_id={}",
+                    "NCCL_{} pid={} comm={}{}{}{} duration={} ret={} cgroup_id={}",
                     op_name,
                     event.metadata.pid,
                     comm,
+                    rank_suffix,
+                    stream_suffix,
+                    group_suffix,
                     format_duration(event.duration_ns),
                     event.ret_code,
                     event.metadata.cgroup_id,
@@ -262,6 +548,72 @@ impl Probe for NcclCommProbe {
     }
 }
 
+impl NcclCommProbe {
+    /// Spawns a background thread that periodically rescans
+    /// `PENDING_NCCL_OPS` for entries older than `stall_threshold_ms()` and
+    /// warns about them - the only way to surface a collective that never
+    /// returns, since `emit_nccl_event` (and everything downstream of it)
+    /// only runs once a call actually completes. Modeled on
+    /// `GpuUsageProbe::spawn_fd_map_fill_sampler`'s periodic-rescan pattern.
+    fn spawn_stall_watchdog(&self, bpf: &mut Ebpf, comm_registry: CommRegistry) -> Result<()> {
+        let map = bpf
+            .take_map("PENDING_NCCL_OPS")
+            .context("Failed to find PENDING_NCCL_OPS map")?;
+        let pending_ops: AyaHashMap<_, u64, PendingNcclOp> = AyaHashMap::try_from(map)?;
+
+        let shutdown = shutdown_flag();
+        std::thread::spawn(move || {
+            let threshold_ns = stall_threshold_ms() * 1_000_000;
+            let mut last_reported: HashMap<u64, Instant> = HashMap::new();
+
+            while !shutdown.load(Ordering::Relaxed) {
+                let Some(now_ns) = monotonic_ns_since_boot() else {
+                    std::thread::sleep(STALL_SWEEP_INTERVAL);
+                    continue;
+                };
+
+                let mut still_pending: HashSet<u64> = HashSet::new();
+
+                for entry in pending_ops.iter() {
+                    let Ok((tid, pending)) = entry else {
+                        continue;
+                    };
+
+                    let stalled_ns = now_ns.saturating_sub(pending.start_ns);
+                    if stalled_ns < threshold_ns {
+                        continue;
+                    }
+
+                    still_pending.insert(tid);
+
+                    let should_report = last_reported
+                        .get(&tid)
+                        .is_none_or(|last| last.elapsed() >= STALL_REPORT_BACKOFF);
+                    if should_report {
+                        report_stall(
+                            NcclStallEvent {
+                                tid,
+                                op_type: pending.op_type,
+                                count: pending.count,
+                                comm_ptr: pending.comm_ptr,
+                                stalled_ns,
+                            },
+                            &comm_registry,
+                        );
+                        last_reported.insert(tid, Instant::now());
+                    }
+                }
+
+                last_reported.retain(|tid, _| still_pending.contains(tid));
+
+                std::thread::sleep(STALL_SWEEP_INTERVAL);
+            }
+        });
+
+        Ok(())
+    }
+}
+
 /// Attach a uprobe or uretprobe to a function
 fn attach_uprobe(bpf: &mut Ebpf, program_name: &str, target: &str, symbol: &str) -> Result<()> {
     let program: &mut UProbe = bpf
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use aya::Ebpf;
+use honeybeepf_common::GpuMemEvent;
+use log::info;
+
+use crate::probes::{Probe, TracepointConfig, attach_tracepoint, spawn_ringbuf_handler};
+use crate::telemetry;
+
+fn format_bytes(bytes: i64) -> String {
+    let sign = if bytes < 0 { "-" } else { "" };
+    let abs = bytes.unsigned_abs();
+    if abs >= 1024 * 1024 * 1024 {
+        format!("{}{:.2} GB", sign, abs as f64 / (1024.0 * 1024.0 * 1024.0))
+    } else if abs >= 1024 * 1024 {
+        format!("{}{:.2} MB", sign, abs as f64 / (1024.0 * 1024.0))
+    } else if abs >= 1024 {
+        format!("{}{:.2} KB", sign, abs as f64 / 1024.0)
+    } else {
+        format!("{}{} B", sign, abs)
+    }
+}
+
+pub struct GpuMemProbe;
+
+impl Probe for GpuMemProbe {
+    fn attach(&self, bpf: &mut Ebpf) -> Result<()> {
+        info!("Attaching GPU memory-accounting probes...");
+
+        attach_tracepoint(
+            bpf,
+            TracepointConfig {
+                program_name: "honeybeepf_gpu_ioctl",
+                category: "syscalls",
+                name: "sys_enter_ioctl",
+            },
+        )?;
+
+        spawn_ringbuf_handler(bpf, "GPU_MEM_EVENTS", move |event: GpuMemEvent| {
+            let comm = std::str::from_utf8(&event.comm)
+                .unwrap_or("<invalid>")
+                .trim_matches(char::from(0));
+
+            info!(
+                "GPU_MEM pid={} comm={} gpu_index={} delta={} total={} cgroup_id={}",
+                event.metadata.pid,
+                comm,
+                event.gpu_index,
+                format_bytes(event.delta_bytes),
+                format_bytes(event.total_bytes as i64),
+                event.metadata.cgroup_id,
+            );
+
+            telemetry::record_gpu_mem_event(
+                event.gpu_index,
+                event.delta_bytes,
+                event.total_bytes,
+                comm,
+            );
+        })?;
+
+        Ok(())
+    }
+}
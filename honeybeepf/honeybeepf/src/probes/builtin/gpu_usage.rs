@@ -1,25 +1,42 @@
-use anyhow::Result;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Context, Result};
 use aya::Ebpf;
-use honeybeepf_common::{GpuCloseEvent, GpuOpenEvent};
+use aya::maps::HashMap as AyaHashMap;
+use honeybeepf_common::{GpuCloseEvent, GpuFdInfo, GpuOpenEvent};
 use log::info;
 
-use crate::probes::{IdentityResolver, Probe, TracepointConfig, attach_tracepoint, spawn_ringbuf_handler};
+use crate::probes::{
+    Probe, TracepointConfig, attach_tracepoint, shutdown_flag, spawn_ringbuf_handler,
+};
 use crate::telemetry;
 
-fn get_gpu_type(filename: &str) -> &'static str {
-    if filename.starts_with("/dev/nvidia") {
-        "NVIDIA"
-    } else if filename.starts_with("/dev/dri/") {
-        "DRI"
-    } else {
-        "Unknown"
+/// Matches `MAX_GPU_FDS` in the eBPF-side `GPU_FD_MAP` definition.
+const MAX_GPU_FDS: u64 = 10240;
+
+/// How often the fill-ratio sampler re-counts `GPU_FD_MAP` entries. The
+/// count feeds an atomic that `register_map_fill_ratio`'s closure reads on
+/// every export, so this only needs to keep pace with the exporter, not
+/// every call.
+const FD_MAP_SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Maps the vendor tag classified in-kernel (`get_gpu_info`) to a display
+/// name. DRI render/card nodes are shared by AMD, Intel, Apple/Asahi, and
+/// other DRM drivers, so they're reported generically rather than guessed at.
+fn get_gpu_type(vendor: u8) -> &'static str {
+    match vendor {
+        0 => "NVIDIA",
+        1 => "AMD (ROCm)",
+        2 => "DRI (generic/Apple)",
+        _ => "Unknown",
     }
 }
 
 pub struct GpuUsageProbe;
 
 impl Probe for GpuUsageProbe {
-    fn attach(&self, bpf: &mut Ebpf, resolver: IdentityResolver) -> Result<()> {
+    fn attach(&self, bpf: &mut Ebpf) -> Result<()> {
         info!("Attaching GPU usage probes...");
 
         // Attach sys_enter_openat (check if GPU, store pending)
@@ -52,8 +69,39 @@ impl Probe for GpuUsageProbe {
             },
         )?;
 
+        // Track GPU fd inheritance across dup/dup2/dup3 so ownership survives
+        // descriptor games.
+        for dup_syscall in ["dup", "dup2", "dup3"] {
+            attach_tracepoint(
+                bpf,
+                TracepointConfig {
+                    program_name: "honeybeepf_gpu_dup_enter",
+                    category: "syscalls",
+                    name: &format!("sys_enter_{dup_syscall}"),
+                },
+            )?;
+            attach_tracepoint(
+                bpf,
+                TracepointConfig {
+                    program_name: "honeybeepf_gpu_dup_exit",
+                    category: "syscalls",
+                    name: &format!("sys_exit_{dup_syscall}"),
+                },
+            )?;
+        }
+
+        // Track GPU fd inheritance across fork so ownership survives into
+        // the child process.
+        attach_tracepoint(
+            bpf,
+            TracepointConfig {
+                program_name: "honeybeepf_gpu_fork",
+                category: "sched",
+                name: "sched_process_fork",
+            },
+        )?;
+
         // Handle GPU open events
-        let open_resolver = resolver.clone();
         spawn_ringbuf_handler(bpf, "GPU_OPEN_EVENTS", move |event: GpuOpenEvent| {
             let comm = std::str::from_utf8(&event.comm)
                 .unwrap_or("<invalid>")
@@ -61,9 +109,7 @@ impl Probe for GpuUsageProbe {
             let filename = std::str::from_utf8(&event.filename)
                 .unwrap_or("<invalid>")
                 .trim_matches(char::from(0));
-            let gpu_type = get_gpu_type(filename);
-
-            let pod_info = open_resolver.resolve_pod(event.metadata.pid, event.metadata.cgroup_id);
+            let gpu_type = get_gpu_type(event.vendor);
 
             info!(
                 "GPU_OPEN pid={} comm={} gpu_index={} fd={} type={} file={} cgroup_id={}",
@@ -76,22 +122,17 @@ impl Probe for GpuUsageProbe {
                 event.metadata.cgroup_id,
             );
 
-            telemetry::record_gpu_open_event(
-                filename,
-                comm,
-                #[cfg(feature = "k8s")]
-                pod_info.as_deref(),
-            );
+            telemetry::record_gpu_open_event(filename);
         })?;
 
+        self.spawn_fd_map_fill_sampler(bpf)?;
+
         // Handle GPU close events
         spawn_ringbuf_handler(bpf, "GPU_CLOSE_EVENTS", move |event: GpuCloseEvent| {
             let comm = std::str::from_utf8(&event.comm)
                 .unwrap_or("<invalid>")
                 .trim_matches(char::from(0));
 
-            let _pod_info = resolver.resolve_pod(event.metadata.pid, event.metadata.cgroup_id);
-
             info!(
                 "GPU_CLOSE pid={} comm={} gpu_index={} fd={} cgroup_id={}",
                 event.metadata.pid, comm, event.gpu_index, event.fd, event.metadata.cgroup_id,
@@ -101,3 +142,35 @@ impl Probe for GpuUsageProbe {
         Ok(())
     }
 }
+
+impl GpuUsageProbe {
+    /// Registers `gpu_fd_map` as a permille-fill self-metric and spawns a
+    /// background thread that periodically re-counts live `GPU_FD_MAP`
+    /// entries into an atomic the metric reads on every export. Modeled on
+    /// `GpuUtilProbe::spawn_event_handler`'s periodic-drain pattern, but
+    /// counts rather than drains since other probes still own the entries.
+    fn spawn_fd_map_fill_sampler(&self, bpf: &mut Ebpf) -> Result<()> {
+        let map = bpf
+            .take_map("GPU_FD_MAP")
+            .context("Failed to find GPU_FD_MAP map")?;
+        let fd_map: AyaHashMap<_, u64, GpuFdInfo> = AyaHashMap::try_from(map)?;
+
+        let count = Arc::new(AtomicU64::new(0));
+
+        telemetry::register_map_fill_ratio("gpu_fd_map", MAX_GPU_FDS, {
+            let count = count.clone();
+            move || count.load(Ordering::Relaxed)
+        });
+
+        let shutdown = shutdown_flag();
+        std::thread::spawn(move || {
+            while !shutdown.load(Ordering::Relaxed) {
+                let n = fd_map.keys().filter(|k| k.is_ok()).count() as u64;
+                count.store(n, Ordering::Relaxed);
+                std::thread::sleep(FD_MAP_SAMPLE_INTERVAL);
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result};
+use aya::Ebpf;
+use aya::maps::HashMap as AyaHashMap;
+use aya::programs::TracePoint;
+use honeybeepf_common::GpuAccKey;
+use log::{info, warn};
+
+use crate::probes::Probe;
+use crate::telemetry;
+
+/// Wall-clock window over which busy-ns accumulators are drained into a
+/// utilization percentage.
+const SAMPLE_WINDOW: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// DRM scheduler / dma-fence tracepoint to attach to. Kernels that only
+/// expose `i915`-specific tracepoints (rather than the generic `dma_fence`
+/// ones) can override these.
+pub struct GpuUtilAttachPoint {
+    pub category: &'static str,
+    pub submit_name: &'static str,
+    pub complete_name: &'static str,
+}
+
+const DEFAULT_ATTACH: GpuUtilAttachPoint = GpuUtilAttachPoint {
+    category: "dma_fence",
+    submit_name: "dma_fence_emit",
+    complete_name: "dma_fence_signaled",
+};
+
+pub struct GpuUtilProbe {
+    pub attach_point: GpuUtilAttachPoint,
+    running: Arc<AtomicBool>,
+}
+
+impl Default for GpuUtilProbe {
+    fn default() -> Self {
+        Self {
+            attach_point: DEFAULT_ATTACH,
+            running: Arc::new(AtomicBool::new(true)),
+        }
+    }
+}
+
+impl Probe for GpuUtilProbe {
+    fn attach(&self, bpf: &mut Ebpf) -> Result<()> {
+        let submit: &mut TracePoint = bpf
+            .program_mut("honeybeepf_gpu_job_submit")
+            .context("Failed to find honeybeepf_gpu_job_submit program")?
+            .try_into()?;
+        submit.load()?;
+        submit.attach(self.attach_point.category, self.attach_point.submit_name)?;
+
+        let complete: &mut TracePoint = bpf
+            .program_mut("honeybeepf_gpu_job_complete")
+            .context("Failed to find honeybeepf_gpu_job_complete program")?
+            .try_into()?;
+        complete.load()?;
+        complete.attach(self.attach_point.category, self.attach_point.complete_name)?;
+
+        info!(
+            "Attached tracepoints: {}/{}, {}/{} (GPU utilization tracking)",
+            self.attach_point.category,
+            self.attach_point.submit_name,
+            self.attach_point.category,
+            self.attach_point.complete_name,
+        );
+
+        self.spawn_event_handler(bpf)?;
+
+        telemetry::record_active_probe("gpu_util", 1);
+        Ok(())
+    }
+}
+
+impl GpuUtilProbe {
+    /// Periodically reads the `GPU_BUSY_NS` accumulator over `SAMPLE_WINDOW`
+    /// and reports `busy_ns` and `busy_ns / window_ns * 100` as utilization.
+    /// Modeled on `OffCpuProbe::spawn_event_handler`.
+    fn spawn_event_handler(&self, bpf: &mut Ebpf) -> Result<()> {
+        let map = bpf
+            .take_map("GPU_BUSY_NS")
+            .context("Failed to find GPU_BUSY_NS map")?;
+        let mut busy_ns: AyaHashMap<_, GpuAccKey, u64> = AyaHashMap::try_from(map)?;
+
+        let running = self.running.clone();
+
+        std::thread::spawn(move || {
+            let window_ns = SAMPLE_WINDOW.as_nanos() as u64;
+
+            while running.load(Ordering::Relaxed) {
+                std::thread::sleep(SAMPLE_WINDOW);
+
+                let mut drained = Vec::new();
+                for entry in busy_ns.iter() {
+                    match entry {
+                        Ok((key, busy)) => drained.push((key, busy)),
+                        Err(e) => {
+                            warn!("Failed to read GPU_BUSY_NS entry: {}", e);
+                            continue;
+                        }
+                    }
+                }
+
+                for (key, busy) in drained {
+                    let _ = busy_ns.remove(&key);
+
+                    let utilization_pct =
+                        (busy as f64 / window_ns as f64 * 100.0).min(100.0);
+
+                    info!(
+                        "GPU_UTIL pid={} cgroup_id={} busy={}ms utilization={:.1}%",
+                        key.pid,
+                        key.cgroup_id,
+                        busy / 1_000_000,
+                        utilization_pct,
+                    );
+
+                    telemetry::record_gpu_util(key.pid, busy, utilization_pct);
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+//! TLS backend abstraction.
+//!
+//! OpenSSL/BoringSSL, GnuTLS, and NSS all expose a read/write pair with the
+//! same calling convention a plain `SSL_read`/`SSL_write` has -- the buffer
+//! pointer in arg1, the length in arg2, the byte count as the return value
+//! -- just under different symbol names. That means the existing
+//! `probe_ssl_rw_enter`/`probe_ssl_read_exit`/`probe_ssl_write_exit` eBPF
+//! programs can capture all of them; only the attach-time symbol names
+//! change. BoringSSL keeps OpenSSL's exact symbol names, so it falls out of
+//! `TlsBackend::OpenSsl` for free.
+
+/// A TLS/SSL implementation recognized by its shared-library soname.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsBackend {
+    /// OpenSSL or BoringSSL: API-compatible, same symbol names.
+    OpenSsl,
+    GnuTls,
+    Nss,
+}
+
+/// Entry-point symbol names for a backend's read/write/handshake
+/// functions, in the shape `attach_backend_probes` expects.
+pub struct BackendSymbols {
+    pub read: &'static str,
+    pub write: &'static str,
+    /// `_ex`-style variant that reports its byte count via an out-param
+    /// rather than the return value (OpenSSL only).
+    pub read_ex: Option<&'static str>,
+    pub write_ex: Option<&'static str>,
+    pub handshake: Option<&'static str>,
+}
+
+impl TlsBackend {
+    /// Classifies a discovered library path by soname. Returns `None` for
+    /// paths that aren't a recognized TLS backend (e.g. `libcrypto`, which
+    /// carries OpenSSL's crypto primitives but not `SSL_read`/`SSL_write`);
+    /// callers should skip those.
+    pub fn classify(path: &str) -> Option<Self> {
+        let soname = path.rsplit('/').next().unwrap_or(path);
+        if soname.contains("libssl") && !soname.contains("libssl3") {
+            Some(TlsBackend::OpenSsl)
+        } else if soname.contains("libgnutls") {
+            Some(TlsBackend::GnuTls)
+        } else if soname.contains("libnss3") || soname.contains("libssl3") {
+            Some(TlsBackend::Nss)
+        } else {
+            None
+        }
+    }
+
+    pub fn symbols(self) -> BackendSymbols {
+        match self {
+            TlsBackend::OpenSsl => BackendSymbols {
+                read: "SSL_read",
+                write: "SSL_write",
+                read_ex: Some("SSL_read_ex"),
+                write_ex: Some("SSL_write_ex"),
+                handshake: Some("SSL_do_handshake"),
+            },
+            TlsBackend::GnuTls => BackendSymbols {
+                // gnutls_record_recv(session, data, data_size) / _send
+                // share OpenSSL's (handle, buf, len) shape and return the
+                // byte count directly, so the plain rw probes apply as-is.
+                read: "gnutls_record_recv",
+                write: "gnutls_record_send",
+                read_ex: None,
+                write_ex: None,
+                handshake: Some("gnutls_handshake"),
+            },
+            TlsBackend::Nss => BackendSymbols {
+                // NSS funnels its TLS I/O through NSPR's PR_Read/PR_Write,
+                // which take the same (handle, buf, len) shape.
+                read: "PR_Read",
+                write: "PR_Write",
+                read_ex: None,
+                write_ex: None,
+                // NSS's handshake entry point (ssl_Do1stHandshake et al.)
+                // isn't a stable exported symbol across builds, so
+                // handshake/SNI capture is OpenSSL/GnuTLS-only for now.
+                handshake: None,
+            },
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TlsBackend::OpenSsl => "OpenSSL/BoringSSL",
+            TlsBackend::GnuTls => "GnuTLS",
+            TlsBackend::Nss => "NSS",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_known_sonames() {
+        assert_eq!(TlsBackend::classify("/usr/lib/libssl.so.3"), Some(TlsBackend::OpenSsl));
+        assert_eq!(TlsBackend::classify("/usr/lib/libgnutls.so.30"), Some(TlsBackend::GnuTls));
+        assert_eq!(TlsBackend::classify("/usr/lib/libnss3.so"), Some(TlsBackend::Nss));
+        assert_eq!(TlsBackend::classify("/usr/lib/x86_64-linux-gnu/libssl3.so"), Some(TlsBackend::Nss));
+    }
+
+    #[test]
+    fn does_not_classify_libcrypto() {
+        assert_eq!(TlsBackend::classify("/usr/lib/libcrypto.so.3"), None);
+    }
+}
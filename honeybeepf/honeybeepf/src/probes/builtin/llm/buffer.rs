@@ -0,0 +1,156 @@
+//! Chunked byte buffer for request/response bodies.
+//!
+//! A plain `Vec<u8>` that grows to several megabytes needs an occasional
+//! full reallocation+copy, and a long-lived SSE/chunked response keeps
+//! every byte it has ever seen resident even after the streaming decoder
+//! (see `http::streaming`) has already consumed them. `SegmentedBuffer`
+//! stores data as a queue of fixed-size chunks instead: appends only ever
+//! touch the tail chunk, and `release_through` can drop whole head chunks
+//! that a caller's parse cursor has moved past, bounding memory to the
+//! unconsumed tail rather than the full stream.
+
+use std::collections::VecDeque;
+
+const CHUNK_SIZE: usize = 64 * 1024; // 64KB
+
+pub struct SegmentedBuffer {
+    chunks: VecDeque<Vec<u8>>,
+    /// Total bytes ever pushed, including ones already released.
+    pushed_len: usize,
+    /// Bytes released from the front (no longer held in `chunks`).
+    released_len: usize,
+}
+
+impl SegmentedBuffer {
+    pub fn new() -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            pushed_len: 0,
+            released_len: 0,
+        }
+    }
+
+    /// Bytes currently retained (excludes anything already released).
+    pub fn len(&self) -> usize {
+        self.pushed_len - self.released_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total bytes ever appended, including released ones. A persistent
+    /// parse cursor is expressed in this offset space, since it stays
+    /// stable across `release_through` calls.
+    pub fn total_pushed(&self) -> usize {
+        self.pushed_len
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.pushed_len += data.len();
+        let mut remaining = data;
+        while !remaining.is_empty() {
+            if self.chunks.back().map_or(true, |c| c.len() >= CHUNK_SIZE) {
+                self.chunks.push_back(Vec::with_capacity(CHUNK_SIZE));
+            }
+            let tail = self.chunks.back_mut().expect("just ensured a tail chunk exists");
+            let space = CHUNK_SIZE - tail.len();
+            let take = space.min(remaining.len());
+            tail.extend_from_slice(&remaining[..take]);
+            remaining = &remaining[take..];
+        }
+    }
+
+    /// Materializes the retained bytes as one contiguous buffer, for
+    /// parsers that still need "the whole thing so far" (e.g. judging
+    /// whether a non-streaming JSON response is complete, which requires
+    /// scanning from the start no matter how the bytes are stored).
+    pub fn to_contiguous(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.len());
+        for chunk in &self.chunks {
+            out.extend_from_slice(chunk);
+        }
+        out
+    }
+
+    /// Drops whole head chunks entirely covered by bytes already consumed
+    /// up to `parsed_offset` (an absolute offset in `total_pushed()`
+    /// space). A chunk straddling the cursor is left in place until the
+    /// cursor moves past its end.
+    pub fn release_through(&mut self, parsed_offset: usize) {
+        while let Some(front) = self.chunks.front() {
+            let chunk_end = self.released_len + front.len();
+            if chunk_end > parsed_offset {
+                break;
+            }
+            self.released_len = chunk_end;
+            self.chunks.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.released_len = self.pushed_len;
+    }
+}
+
+impl Default for SegmentedBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_and_len_track_across_chunk_boundaries() {
+        let mut buf = SegmentedBuffer::new();
+        let data = vec![0u8; CHUNK_SIZE + 100];
+        buf.push(&data);
+        assert_eq!(buf.len(), CHUNK_SIZE + 100);
+        assert_eq!(buf.chunks.len(), 2);
+    }
+
+    #[test]
+    fn to_contiguous_preserves_byte_order() {
+        let mut buf = SegmentedBuffer::new();
+        buf.push(b"hello, ");
+        buf.push(&vec![b'x'; CHUNK_SIZE]);
+        buf.push(b"world");
+
+        let flat = buf.to_contiguous();
+        assert!(flat.starts_with(b"hello, "));
+        assert!(flat.ends_with(b"world"));
+        assert_eq!(flat.len(), buf.len());
+    }
+
+    #[test]
+    fn release_through_frees_fully_consumed_head_chunks_only() {
+        let mut buf = SegmentedBuffer::new();
+        buf.push(&vec![1u8; CHUNK_SIZE]);
+        buf.push(&vec![2u8; CHUNK_SIZE]);
+        buf.push(b"tail");
+        assert_eq!(buf.chunks.len(), 3);
+
+        // Cursor lands mid-way through the second chunk: only the first
+        // (fully-consumed) chunk should be released.
+        buf.release_through(CHUNK_SIZE + 10);
+        assert_eq!(buf.chunks.len(), 2);
+        assert_eq!(buf.len(), CHUNK_SIZE + 4);
+
+        buf.release_through(CHUNK_SIZE * 2 + 4);
+        assert_eq!(buf.chunks.len(), 0);
+        assert_eq!(buf.len(), 0);
+    }
+
+    #[test]
+    fn clear_resets_to_empty() {
+        let mut buf = SegmentedBuffer::new();
+        buf.push(b"some bytes");
+        buf.clear();
+        assert!(buf.is_empty());
+        assert_eq!(buf.total_pushed(), 10);
+    }
+}
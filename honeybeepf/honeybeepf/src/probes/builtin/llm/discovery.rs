@@ -6,16 +6,20 @@ use regex::Regex;
 
 use crate::probes::discovery;
 
-/// SSL library pattern for libssl and libcrypto.
-static SSL_PATTERN: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"libssl\.so\..*|libcrypto\.so\..*").unwrap());
+/// TLS library pattern: OpenSSL/BoringSSL (`libssl`/`libcrypto`), GnuTLS
+/// (`libgnutls`), and NSS (`libnss3`/`libssl3`, NSS's own libssl). Anything
+/// matched here is further classified by `backend::TlsBackend::classify`.
+static TLS_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"libssl\.so\..*|libcrypto\.so\..*|libgnutls\.so\..*|libnss3\.so.*|libssl3\.so.*")
+        .unwrap()
+});
 
-/// Find all SSL libraries across running processes and system defaults.
+/// Find all TLS libraries across running processes and system defaults.
 pub fn find_all_targets() -> Result<HashSet<String>> {
-    discovery::find_libraries_all(&SSL_PATTERN, Some("libssl.so"))
+    discovery::find_libraries_all(&TLS_PATTERN, Some("libssl.so"))
 }
 
-/// Find SSL libraries for specific PIDs only (for re-discovery).
+/// Find TLS libraries for specific PIDs only (for re-discovery).
 pub fn find_targets_for_pids(pids: &[u32]) -> Result<HashSet<String>> {
-    discovery::find_libraries_for_pids(pids, &SSL_PATTERN)
+    discovery::find_libraries_for_pids(pids, &TLS_PATTERN)
 }
@@ -4,9 +4,13 @@
 //! LLM request/response data.
 
 pub mod protocol;
+pub mod streaming;
 pub mod utils;
 pub mod providers;
 
 // Re-export main types
-pub use protocol::{ProtocolParser, Http11Parser, Http2Parser};
+pub use protocol::{
+    ProtocolParser, Http11Parser, Http2Parser, GrpcParser, set_custom_provider_config_path,
+    start_provider_watcher,
+};
 pub use providers::{ProviderRegistry, ConfigurableProvider};
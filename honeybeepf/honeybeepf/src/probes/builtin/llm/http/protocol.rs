@@ -1,23 +1,53 @@
+use super::streaming::StreamDecoder;
 use super::utils as byte_utils;
 use super::providers::{ProviderRegistry, ConfigurableProvider};
 use crate::probes::builtin::llm::types::{UsageInfo, SseChunkDelta};
+use arc_swap::ArcSwap;
 use flate2::read::GzDecoder;
 use once_cell::sync::Lazy;
 use serde_json::Value;
 use std::io::Read;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
+use tiktoken_rs::CoreBPE;
+
+/// Cached providers, rebuilt from `PROVIDER_REGISTRY` on every reload. An
+/// `ArcSwap` rather than a plain `Lazy` so `provider_watcher` can publish a
+/// freshly-parsed config without blocking readers already mid-`.load()`.
+static CACHED_PROVIDERS: Lazy<ArcSwap<Vec<ConfigurableProvider>>> =
+    Lazy::new(|| ArcSwap::from_pointee(build_cached_providers(&load_provider_registry())));
+
+/// Global provider registry, for host/path matching only. See
+/// `CACHED_PROVIDERS` for why this is an `ArcSwap`.
+static PROVIDER_REGISTRY: Lazy<ArcSwap<ProviderRegistry>> =
+    Lazy::new(|| ArcSwap::from_pointee(load_provider_registry()));
+
+/// `Settings::custom_probe_config` path, recorded via
+/// `set_custom_provider_config_path` before `PROVIDER_REGISTRY`/
+/// `CACHED_PROVIDERS` are first forced, since a `Lazy` has no way to take
+/// an argument at first access.
+static CUSTOM_PROVIDER_CONFIG_PATH: OnceLock<Option<String>> = OnceLock::new();
+
+/// Records the `custom_probe_config` path from `Settings` so the provider
+/// registry merges it in the first time it's needed. Must be called before
+/// anything touches `PROVIDER_REGISTRY`/`CACHED_PROVIDERS` (i.e. before the
+/// LLM probe attaches); a no-op on any call after the first, since the path
+/// is fixed for the process's lifetime once the registry materializes.
+pub fn set_custom_provider_config_path(path: Option<String>) {
+    let _ = CUSTOM_PROVIDER_CONFIG_PATH.set(path);
+}
 
-/// Cached providers - built once at initialization
-static CACHED_PROVIDERS: Lazy<Vec<ConfigurableProvider>> = Lazy::new(|| {
-    let registry = load_provider_registry();
-    registry.providers.into_iter()
-        .map(ConfigurableProvider::new)
-        .collect()
-});
+fn load_provider_registry() -> ProviderRegistry {
+    let mut registry = load_base_provider_registry();
 
-/// Global provider registry - for host/path matching only
-static PROVIDER_REGISTRY: Lazy<ProviderRegistry> = Lazy::new(load_provider_registry);
+    if let Some(path) = CUSTOM_PROVIDER_CONFIG_PATH.get().cloned().flatten() {
+        registry.merge_custom_file(&path);
+    }
 
-fn load_provider_registry() -> ProviderRegistry {
+    registry
+}
+
+fn load_base_provider_registry() -> ProviderRegistry {
     // 1. Try loading from config file
     if let Ok(path) = std::env::var("LLM_PROVIDERS_CONFIG_FILE") {
         if let Ok(content) = std::fs::read_to_string(&path) {
@@ -42,11 +72,93 @@ fn load_provider_registry() -> ProviderRegistry {
     ProviderRegistry::with_defaults()
 }
 
-/// Get cached providers (no allocation per call)
-fn get_providers() -> &'static [ConfigurableProvider] {
-    &CACHED_PROVIDERS
+fn build_cached_providers(registry: &ProviderRegistry) -> Vec<ConfigurableProvider> {
+    registry
+        .providers
+        .iter()
+        .cloned()
+        .map(ConfigurableProvider::new)
+        .collect()
+}
+
+/// Re-parses the provider config source (file/env plus the custom-probe
+/// merge) and atomically swaps both `PROVIDER_REGISTRY` and
+/// `CACHED_PROVIDERS` in. Readers that already called `.load()`/`.load_full()`
+/// keep working against their snapshot, so an in-flight `parse_usage` call
+/// never observes a registry half-rebuilt.
+fn reload_providers() {
+    let registry = load_provider_registry();
+    let cached = build_cached_providers(&registry);
+    PROVIDER_REGISTRY.store(Arc::new(registry));
+    CACHED_PROVIDERS.store(Arc::new(cached));
+    log::info!("LLM provider registry reloaded");
+}
+
+/// Get the current cached-providers snapshot (cheap `Arc` clone, no parsing).
+fn get_providers() -> Arc<Vec<ConfigurableProvider>> {
+    CACHED_PROVIDERS.load_full()
 }
 
+/// Background watcher that polls the provider config source's mtime and
+/// calls [`reload_providers`] when it changes, so editing
+/// `LLM_PROVIDERS_CONFIG_FILE` (or the `custom_probe_config` file, typically
+/// a mounted Kubernetes ConfigMap) takes effect without restarting the
+/// agent. A plain mtime poll rather than a `notify` watch, mirroring
+/// `ConfigReloader`'s own poll loop for `Settings`.
+mod provider_watcher {
+    use super::*;
+
+    const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Paths whose mtime determines whether the provider config changed:
+    /// the base `LLM_PROVIDERS_CONFIG_FILE`, if set, and the custom-probe
+    /// config file merged on top of it, if any.
+    fn watched_paths() -> Vec<String> {
+        let mut paths = Vec::new();
+        if let Ok(path) = std::env::var("LLM_PROVIDERS_CONFIG_FILE") {
+            paths.push(path);
+        }
+        if let Some(path) = CUSTOM_PROVIDER_CONFIG_PATH.get().cloned().flatten() {
+            paths.push(path);
+        }
+        paths
+    }
+
+    fn mtimes(paths: &[String]) -> Vec<Option<SystemTime>> {
+        paths
+            .iter()
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    /// Spawns the poll loop. A no-op (no thread, no watching) if neither
+    /// config source is set, since there's nothing on disk that could
+    /// change out from under a defaults-only or inline-JSON registry.
+    pub fn start() {
+        let paths = watched_paths();
+        if paths.is_empty() {
+            return;
+        }
+
+        std::thread::spawn(move || {
+            let mut last_mtimes = mtimes(&paths);
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+
+                let current_mtimes = mtimes(&paths);
+                if current_mtimes != last_mtimes {
+                    reload_providers();
+                    last_mtimes = current_mtimes;
+                }
+            }
+        });
+
+        log::info!("LLM provider config watcher started for: {}", paths.join(", "));
+    }
+}
+
+pub use provider_watcher::start as start_provider_watcher;
+
 /// Protocol-specific parser interface
 pub trait ProtocolParser: Send + Sync {
     /// Detect if this buffer is an LLM request. Returns detected path/info if yes.
@@ -58,11 +170,60 @@ pub trait ProtocolParser: Send + Sync {
 
     /// Parse response buffer. Returns UsageInfo if complete.
     fn parse_response(&self, buffer: &[u8]) -> Option<UsageInfo>;
+
+    /// Incrementally feed newly-arrived **response** bytes (the `Read`
+    /// direction) for streaming (SSE/chunked) responses, tokenizing
+    /// `choices[].delta.content` deltas as they arrive instead of waiting
+    /// for the whole response to buffer up. `parse_response` remains the
+    /// source of truth whenever it succeeds; this exists to give streamed
+    /// responses (which may never present a complete, parseable buffer,
+    /// or report `usage` only at the very end) a live token count in the
+    /// meantime. Parsers that don't understand a streaming wire format
+    /// can leave this — and the accessors below — as no-ops.
+    fn feed(&mut self, _new_bytes: &[u8], _bpe: &CoreBPE) {}
+
+    /// Whether `feed` has confirmed this response is a streaming
+    /// (SSE/chunked) one. Once true, callers can stop handing the
+    /// accumulated response buffer to `parse_response` on every event —
+    /// `feed` already tracks everything it needs incrementally — and can
+    /// release the buffer's consumed bytes instead of holding onto the
+    /// whole stream.
+    fn is_streaming(&self) -> bool {
+        false
+    }
+
+    /// Running completion-token count accumulated by `feed` so far.
+    fn streaming_completion_tokens(&self) -> u64 {
+        0
+    }
+
+    /// Whether `feed` has seen a terminal marker (`data: [DONE]`, final
+    /// `0\r\n\r\n` chunk) for the stream.
+    fn streaming_done(&self) -> bool {
+        false
+    }
+
+    /// Terminal `usage` object captured by `feed`, if the provider sent one.
+    fn streaming_usage(&self) -> Option<UsageInfo> {
+        None
+    }
+
+    /// `streaming_usage`, falling back to a synthetic `UsageInfo` (flagged
+    /// `estimated`) built from `streaming_completion_tokens` when the
+    /// provider never sent one - either because it doesn't report `usage`
+    /// mid-stream, or because the connection went idle before the stream's
+    /// own terminal marker arrived. `None` only when nothing was tokenized
+    /// at all.
+    fn streaming_usage_or_estimate(&self) -> Option<UsageInfo> {
+        self.streaming_usage()
+    }
 }
 
 // --- HTTP/1.1 ---
-#[derive(Clone)]
-pub struct Http11Parser;
+#[derive(Default)]
+pub struct Http11Parser {
+    stream: StreamDecoder,
+}
 
 impl ProtocolParser for Http11Parser {
     fn detect_request(&self, buffer: &[u8]) -> Option<String> {
@@ -78,7 +239,7 @@ impl ProtocolParser for Http11Parser {
                         .unwrap_or_default();
 
                     // Check if host+path matches any configured provider
-                    if PROVIDER_REGISTRY.find_provider(&host_header, path).is_some() {
+                    if PROVIDER_REGISTRY.load().find_provider(&host_header, path).is_some() {
                         return Some(path.to_string());
                     }
                 }
@@ -152,11 +313,37 @@ impl ProtocolParser for Http11Parser {
 
         parse_response_json(json_body)
     }
+
+    fn feed(&mut self, new_bytes: &[u8], bpe: &CoreBPE) {
+        self.stream.feed(new_bytes, bpe);
+    }
+
+    fn is_streaming(&self) -> bool {
+        self.stream.is_stream()
+    }
+
+    fn streaming_completion_tokens(&self) -> u64 {
+        self.stream.completion_tokens
+    }
+
+    fn streaming_done(&self) -> bool {
+        self.stream.is_done()
+    }
+
+    fn streaming_usage(&self) -> Option<UsageInfo> {
+        self.stream.usage.clone()
+    }
+
+    fn streaming_usage_or_estimate(&self) -> Option<UsageInfo> {
+        self.stream.usage_or_estimate()
+    }
 }
 
 // --- HTTP/2 ---
-#[derive(Clone)]
-pub struct Http2Parser;
+#[derive(Default)]
+pub struct Http2Parser {
+    stream: StreamDecoder,
+}
 
 impl ProtocolParser for Http2Parser {
     fn detect_request(&self, buffer: &[u8]) -> Option<String> {
@@ -221,6 +408,113 @@ impl ProtocolParser for Http2Parser {
 
         None
     }
+
+    fn feed(&mut self, new_bytes: &[u8], bpe: &CoreBPE) {
+        self.stream.feed_h2_body(new_bytes, bpe);
+    }
+
+    fn is_streaming(&self) -> bool {
+        self.stream.is_stream()
+    }
+
+    fn streaming_completion_tokens(&self) -> u64 {
+        self.stream.completion_tokens
+    }
+
+    fn streaming_done(&self) -> bool {
+        self.stream.is_done()
+    }
+
+    fn streaming_usage(&self) -> Option<UsageInfo> {
+        self.stream.usage.clone()
+    }
+
+    fn streaming_usage_or_estimate(&self) -> Option<UsageInfo> {
+        self.stream.usage_or_estimate()
+    }
+}
+
+// --- gRPC ---
+
+/// Parses gRPC responses (HTTP/2 with `content-type: application/grpc`) for
+/// providers like Vertex AI whose prediction endpoints are protobuf rather
+/// than JSON. Detection stays at the same "scan the raw decrypted bytes
+/// directly" level as `Http2Parser`'s cleartext fallback, since this agent
+/// never reconstructs real HPACK-decoded HTTP/2 headers; only
+/// `parse_response`'s gRPC message framing is real wire format.
+#[derive(Default)]
+pub struct GrpcParser;
+
+impl ProtocolParser for GrpcParser {
+    fn detect_request(&self, buffer: &[u8]) -> Option<String> {
+        if !byte_utils::contains_pattern(buffer, b"application/grpc") {
+            return None;
+        }
+
+        let text = String::from_utf8_lossy(buffer);
+        PROVIDER_REGISTRY
+            .load()
+            .providers
+            .iter()
+            .find(|p| p.hosts.iter().any(|h| text.contains(h.as_str())))
+            .map(|p| format!("grpc:{}", p.name))
+    }
+
+    fn extract_request_text(&self, _buffer: &[u8]) -> String {
+        // Request bodies are protobuf prediction payloads with no
+        // configured field mapping for free-form text - only the
+        // response-side usage counters are (`GrpcUsageFields`) - so there's
+        // nothing meaningful to tokenize here.
+        String::new()
+    }
+
+    fn parse_response(&self, buffer: &[u8]) -> Option<UsageInfo> {
+        for provider in get_providers().iter() {
+            if let Some(usage) = parse_grpc_frames(buffer, provider) {
+                return Some(usage);
+            }
+        }
+        None
+    }
+}
+
+/// Walks gRPC length-prefixed message framing - a 1-byte compression flag,
+/// a 4-byte big-endian message length, then that many bytes of protobuf -
+/// and scans each frame for `provider`'s configured usage field numbers.
+fn parse_grpc_frames(buffer: &[u8], provider: &ConfigurableProvider) -> Option<UsageInfo> {
+    let grpc_fields = provider.grpc_usage_fields()?;
+
+    let mut pos = 0;
+    while pos + 5 <= buffer.len() {
+        let compressed = buffer[pos] == 1;
+        let len = u32::from_be_bytes(buffer[pos + 1..pos + 5].try_into().ok()?) as usize;
+        pos += 5;
+
+        if pos + len > buffer.len() {
+            break;
+        }
+        let frame = &buffer[pos..pos + len];
+        pos += len;
+
+        let decompressed;
+        let payload: &[u8] = if compressed {
+            match decompress_gzip(frame) {
+                Ok(data) => {
+                    decompressed = data;
+                    &decompressed
+                }
+                Err(_) => continue,
+            }
+        } else {
+            frame
+        };
+
+        if let Some(usage) = grpc_fields.extract_usage(payload) {
+            return Some(usage);
+        }
+    }
+
+    None
 }
 
 // --- SSE Parsing ---
@@ -263,19 +557,25 @@ fn parse_response_json(json_str: &str) -> Option<UsageInfo> {
     parse_response_json_value(&val)
 }
 
-fn parse_response_json_value(val: &Value) -> Option<UsageInfo> {
+pub(super) fn parse_response_json_value(val: &Value) -> Option<UsageInfo> {
     // Check for error response
     if val.get("error").is_some() {
         return Some(UsageInfo {
             prompt_tokens: 0,
             completion_tokens: 0,
             thoughts_tokens: None,
+            tool_tokens: None,
             model: None,
+            estimated: false,
         });
     }
 
-    for provider in get_providers() {
-        if let Some(info) = provider.parse_usage(val) {
+    // No path-template params available here: response parsing runs out of
+    // band from the request that produced it, so `$path.*` model references
+    // only resolve when a caller threads in params from `find_provider`.
+    let no_path_params = std::collections::HashMap::new();
+    for provider in get_providers().iter() {
+        if let Some(info) = provider.parse_usage(val, &no_path_params) {
             return Some(info);
         }
     }
@@ -293,8 +593,8 @@ fn decompress_gzip(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
 }
 
 fn is_llm_path(path: &str) -> bool {
-    PROVIDER_REGISTRY.providers.iter().any(|config| {
-        config.paths.iter().any(|p| path.contains(p))
+    PROVIDER_REGISTRY.load().providers.iter().any(|config| {
+        !config.paths.is_empty() && config.match_path(path).is_some()
     })
 }
 
@@ -302,7 +602,8 @@ fn is_llm_path(path: &str) -> bool {
 fn extract_text_from_json(json: &str) -> String {
     // Try parsing as complete JSON first
     if let Ok(val) = serde_json::from_str::<Value>(json) {
-        for provider in get_providers() {
+        let providers = get_providers();
+        for provider in providers.iter() {
             if provider.detect_request(&val) {
                 let text = provider.extract_request_text(&val);
                 if !text.is_empty() {
@@ -312,7 +613,7 @@ fn extract_text_from_json(json: &str) -> String {
         }
 
         // Fallback: try all providers' extract even without detection
-        for provider in get_providers() {
+        for provider in providers.iter() {
             let text = provider.extract_request_text(&val);
             if !text.is_empty() {
                 return text;
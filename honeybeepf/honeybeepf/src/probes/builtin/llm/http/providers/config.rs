@@ -1,5 +1,11 @@
+use std::collections::HashMap;
+
 use serde::Deserialize;
 
+use super::path_template::PathPattern;
+use super::protobuf::scan_varint_fields;
+use crate::probes::builtin::llm::types::UsageInfo;
+
 /// Type of request text extractor to use
 #[derive(Debug, Clone, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -7,14 +13,40 @@ pub enum RequestExtractorType {
     /// OpenAI/Anthropic style: messages[].content
     #[default]
     Messages,
+    /// OpenAI/Anthropic style, plus `tools`/`functions` schema definitions
+    /// and any assistant `tool_calls`/`tool_use` arguments, so
+    /// function-calling workloads aren't undercounted as text-only.
+    MessagesWithTools,
     /// Gemini style: contents[].parts[].text
     Contents,
+    /// Gemini style, plus `tools` schema definitions and any
+    /// `functionCall` arguments.
+    ContentsWithTools,
     /// Simple prompt field: prompt
     Prompt,
     /// No extraction (skip request text estimation)
     None,
 }
 
+/// Type of response text extractor to use - the completion-side counterpart
+/// to `RequestExtractorType`, selecting how a captured LLM response gets
+/// flattened to text for logging/estimation instead of just its usage block.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseExtractorType {
+    /// OpenAI style: choices[].message.content, or choices[].delta.content
+    /// for streaming responses.
+    #[serde(rename = "openai_choices")]
+    OpenAiChoices,
+    /// Anthropic style: content[].text
+    AnthropicContent,
+    /// Gemini style: candidates[].content.parts[].text
+    GeminiCandidates,
+    /// No extraction (skip response text capture)
+    #[default]
+    None,
+}
+
 /// Configuration for parsing usage from response JSON
 #[derive(Debug, Clone, Deserialize)]
 pub struct ResponseConfig {
@@ -33,9 +65,22 @@ pub struct ResponseConfig {
     /// Optional: field name for thinking/reasoning tokens
     pub thoughts_tokens: Option<String>,
 
-    /// JSON path to model name (from root, e.g., "model" or "modelVersion")
+    /// Optional: field name for tool/function-calling tokens, for providers
+    /// that report them as their own breakdown within the usage object.
+    pub tool_tokens: Option<String>,
+
+    /// JSON path to model name (from root, e.g., "model" or "modelVersion"),
+    /// or `$path.<param>` to pull it from a `{param}` captured by one of
+    /// this provider's path templates instead - see
+    /// `ConfigurableProvider::parse_usage`.
     #[serde(default = "default_model_path")]
     pub model_path: String,
+
+    /// Usage-field mapping for providers whose responses are gRPC/protobuf
+    /// rather than JSON (e.g. Vertex AI) - absent for every JSON provider.
+    /// See `GrpcParser`.
+    #[serde(default)]
+    pub grpc: Option<GrpcUsageFields>,
 }
 
 fn default_usage_path() -> String {
@@ -58,11 +103,54 @@ impl Default for ResponseConfig {
             prompt_tokens: default_prompt_tokens(),
             completion_tokens: default_completion_tokens(),
             thoughts_tokens: None,
+            tool_tokens: None,
             model_path: default_model_path(),
+            grpc: None,
         }
     }
 }
 
+/// Maps gRPC protobuf usage-field numbers to the same token roles
+/// `ResponseConfig` otherwise reads out of JSON via `usage_path`/
+/// `prompt_tokens`/etc.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrpcUsageFields {
+    pub prompt_tokens_field: u32,
+    pub completion_tokens_field: u32,
+    pub thoughts_tokens_field: Option<u32>,
+    pub tool_tokens_field: Option<u32>,
+}
+
+impl GrpcUsageFields {
+    /// Scans `payload` (one decoded gRPC message) for this config's field
+    /// numbers and builds a `UsageInfo`. `None` if the required
+    /// prompt/completion fields weren't both present.
+    pub fn extract_usage(&self, payload: &[u8]) -> Option<UsageInfo> {
+        let mut wanted = vec![self.prompt_tokens_field, self.completion_tokens_field];
+        if let Some(field) = self.thoughts_tokens_field {
+            wanted.push(field);
+        }
+        if let Some(field) = self.tool_tokens_field {
+            wanted.push(field);
+        }
+
+        let found = scan_varint_fields(payload, &wanted);
+
+        Some(UsageInfo {
+            prompt_tokens: *found.get(&self.prompt_tokens_field)?,
+            completion_tokens: *found.get(&self.completion_tokens_field)?,
+            thoughts_tokens: self
+                .thoughts_tokens_field
+                .and_then(|f| found.get(&f).copied()),
+            tool_tokens: self
+                .tool_tokens_field
+                .and_then(|f| found.get(&f).copied()),
+            model: None,
+            estimated: false,
+        })
+    }
+}
+
 /// Configuration for a single LLM provider
 #[derive(Debug, Clone, Deserialize)]
 pub struct ProviderConfig {
@@ -84,6 +172,10 @@ pub struct ProviderConfig {
     /// Request text extraction type
     #[serde(default)]
     pub request_extractor: RequestExtractorType,
+
+    /// Response text extraction type
+    #[serde(default)]
+    pub response_extractor: ResponseExtractorType,
 }
 
 /// Registry of all configured providers
@@ -92,12 +184,100 @@ pub struct ProviderRegistry {
     pub providers: Vec<ProviderConfig>,
 }
 
+/// Raw on-disk shape of a custom provider definition file: the same single
+/// `providers` list `ProviderRegistry` itself derives from, but decoded as
+/// untyped values first so one malformed entry doesn't take the whole file
+/// down with it - see `ProviderRegistry::merge_custom_file`.
+#[derive(Deserialize)]
+struct CustomProviderFile {
+    #[serde(default)]
+    providers: Vec<serde_yml::Value>,
+}
+
+impl ProviderConfig {
+    /// A provider with no hosts and no paths would match every request in
+    /// `ProviderRegistry::find_provider`, and one with no name has nothing
+    /// to report, so reject those up front. `request_extractor` needs no check of its
+    /// own - an unresolvable extractor string is already rejected by serde
+    /// before a `ProviderConfig` value exists at all.
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("provider name is empty".to_string());
+        }
+        if self.hosts.is_empty() && self.paths.is_empty() {
+            return Err(format!(
+                "provider '{}' has no hosts or paths to match on",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Matches `path` against this provider's `paths` patterns, compiling
+    /// each as a [`PathPattern`] (cheap: patterns are short and there are
+    /// only a handful per provider). Returns the first match's captured
+    /// `{param}` values - empty if every matching pattern was a plain
+    /// literal, or if `paths` itself is empty. `None` means no pattern
+    /// matched.
+    pub fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
+        if self.paths.is_empty() {
+            return Some(HashMap::new());
+        }
+        self.paths
+            .iter()
+            .find_map(|pat| PathPattern::compile(pat).match_path(path))
+    }
+}
+
 impl ProviderRegistry {
     /// Load from JSON string
     pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(json)
     }
 
+    /// Loads custom provider definitions from `path` (YAML or JSON -
+    /// `serde_yml` parses both) and merges the valid ones in front of
+    /// `self.providers`, so a custom provider matches before a built-in
+    /// default with an overlapping host/path. Each entry is decoded and
+    /// validated independently; an entry that fails to parse or fails
+    /// `validate` is logged by index and skipped rather than discarding
+    /// the whole file.
+    pub fn merge_custom_file(&mut self, path: &str) {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                log::warn!("Failed to read custom LLM provider config '{}': {}", path, e);
+                return;
+            }
+        };
+
+        let file: CustomProviderFile = match serde_yml::from_str(&content) {
+            Ok(file) => file,
+            Err(e) => {
+                log::warn!("Failed to parse custom LLM provider config '{}': {}", path, e);
+                return;
+            }
+        };
+
+        let mut inserted = 0;
+        for (i, raw) in file.providers.into_iter().enumerate() {
+            let provider: ProviderConfig = match serde_yml::from_value(raw) {
+                Ok(provider) => provider,
+                Err(e) => {
+                    log::warn!("Skipping custom LLM provider #{} in '{}': {}", i, path, e);
+                    continue;
+                }
+            };
+            if let Err(e) = provider.validate() {
+                log::warn!("Skipping custom LLM provider #{} in '{}': {}", i, path, e);
+                continue;
+            }
+            log::info!("Loaded custom LLM provider '{}' from '{}'", provider.name, path);
+            self.providers.insert(inserted, provider);
+            inserted += 1;
+        }
+    }
+
     /// Create with default providers (OpenAI, Anthropic, Gemini)
     pub fn with_defaults() -> Self {
         Self {
@@ -116,9 +296,12 @@ impl ProviderRegistry {
                         thoughts_tokens: Some(
                             "completion_tokens_details.reasoning_tokens".to_string(),
                         ),
+                        tool_tokens: None,
                         model_path: "model".to_string(),
+                        grpc: None,
                     },
                     request_extractor: RequestExtractorType::Messages,
+                    response_extractor: ResponseExtractorType::OpenAiChoices,
                 },
                 ProviderConfig {
                     name: "anthropic".to_string(),
@@ -129,9 +312,12 @@ impl ProviderRegistry {
                         prompt_tokens: "input_tokens".to_string(),
                         completion_tokens: "output_tokens".to_string(),
                         thoughts_tokens: None,
+                        tool_tokens: None,
                         model_path: "model".to_string(),
+                        grpc: None,
                     },
                     request_extractor: RequestExtractorType::Messages,
+                    response_extractor: ResponseExtractorType::AnthropicContent,
                 },
                 ProviderConfig {
                     name: "gemini".to_string(),
@@ -142,20 +328,27 @@ impl ProviderRegistry {
                         prompt_tokens: "promptTokenCount".to_string(),
                         completion_tokens: "candidatesTokenCount".to_string(),
                         thoughts_tokens: Some("thoughtsTokenCount".to_string()),
+                        tool_tokens: None,
                         model_path: "modelVersion".to_string(),
+                        grpc: None,
                     },
                     request_extractor: RequestExtractorType::Contents,
+                    response_extractor: ResponseExtractorType::GeminiCandidates,
                 },
             ],
         }
     }
 
-    /// Find matching provider for given host and path
-    pub fn find_provider(&self, host: &str, path: &str) -> Option<&ProviderConfig> {
-        self.providers.iter().find(|p| {
+    /// Find the matching provider for a given host and path, along with any
+    /// `{param}` values its path pattern captured (empty if the match was a
+    /// plain literal).
+    pub fn find_provider(&self, host: &str, path: &str) -> Option<(&ProviderConfig, HashMap<String, String>)> {
+        self.providers.iter().find_map(|p| {
             let host_match = p.hosts.is_empty() || p.hosts.iter().any(|h| host.contains(h));
-            let path_match = p.paths.is_empty() || p.paths.iter().any(|pat| path.contains(pat));
-            host_match && path_match
+            if !host_match {
+                return None;
+            }
+            p.match_path(path).map(|params| (p, params))
         })
     }
 }
@@ -170,20 +363,103 @@ mod tests {
         assert_eq!(registry.providers.len(), 3);
     }
 
+    #[test]
+    fn test_grpc_usage_fields_extracts_from_protobuf() {
+        let fields = GrpcUsageFields {
+            prompt_tokens_field: 1,
+            completion_tokens_field: 2,
+            thoughts_tokens_field: Some(3),
+            tool_tokens_field: None,
+        };
+
+        // Field 1 (varint) = 10, field 2 (varint) = 20, field 3 (varint) = 5
+        let payload = vec![0x08, 0x0a, 0x10, 0x14, 0x18, 0x05];
+
+        let usage = fields.extract_usage(&payload).expect("should extract usage");
+        assert_eq!(usage.prompt_tokens, 10);
+        assert_eq!(usage.completion_tokens, 20);
+        assert_eq!(usage.thoughts_tokens, Some(5));
+    }
+
+    #[test]
+    fn test_grpc_usage_fields_missing_required_field_returns_none() {
+        let fields = GrpcUsageFields {
+            prompt_tokens_field: 1,
+            completion_tokens_field: 2,
+            thoughts_tokens_field: None,
+            tool_tokens_field: None,
+        };
+
+        // Only field 1 present
+        let payload = vec![0x08, 0x0a];
+        assert!(fields.extract_usage(&payload).is_none());
+    }
+
     #[test]
     fn test_find_provider() {
         let registry = ProviderRegistry::with_defaults();
 
-        let openai = registry.find_provider("api.openai.com", "/v1/chat/completions");
-        assert!(openai.is_some());
-        assert_eq!(openai.unwrap().name, "openai");
+        let (openai, params) = registry
+            .find_provider("api.openai.com", "/v1/chat/completions")
+            .expect("openai should match");
+        assert_eq!(openai.name, "openai");
+        assert!(params.is_empty());
+
+        let (gemini, params) = registry
+            .find_provider(
+                "generativelanguage.googleapis.com",
+                "/v1/models/gemini:generateContent",
+            )
+            .expect("gemini should match");
+        assert_eq!(gemini.name, "gemini");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn test_find_provider_captures_path_template_param() {
+        let mut registry = ProviderRegistry::with_defaults();
+        registry.providers[2].paths = vec!["/v1/models/{model}:generateContent".to_string()];
 
-        let gemini = registry.find_provider(
-            "generativelanguage.googleapis.com",
-            "/v1/models/gemini:generateContent",
+        let (gemini, params) = registry
+            .find_provider(
+                "generativelanguage.googleapis.com",
+                "/v1/models/gemini-pro:generateContent",
+            )
+            .expect("gemini should match");
+        assert_eq!(gemini.name, "gemini");
+        assert_eq!(params.get("model"), Some(&"gemini-pro".to_string()));
+
+        assert!(
+            registry
+                .find_provider(
+                    "generativelanguage.googleapis.com",
+                    "/v1/models/gemini-pro:embedContent",
+                )
+                .is_none()
         );
-        assert!(gemini.is_some());
-        assert_eq!(gemini.unwrap().name, "gemini");
+    }
+
+    #[test]
+    fn test_merge_custom_file_skips_invalid_entries_and_keeps_valid_ones() {
+        let yaml = r#"
+providers:
+  - name: my-llm
+    hosts: ["llm.internal.com"]
+    paths: ["/api/generate"]
+    request_extractor: prompt
+  - name: ""
+    hosts: ["no-name.internal.com"]
+  - name: no-match
+"#;
+        let path = std::env::temp_dir().join("honeybeepf_test_custom_providers.yaml");
+        std::fs::write(&path, yaml).unwrap();
+
+        let mut registry = ProviderRegistry::with_defaults();
+        registry.merge_custom_file(path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(registry.providers.len(), 4); // 1 valid custom + 3 defaults
+        assert_eq!(registry.providers[0].name, "my-llm");
     }
 
     #[test]
@@ -16,12 +16,21 @@
 //!       completion_tokens: "completion_tokens"
 //!       model_path: "model"
 //!     request_extractor: "messages"
+//!     response_extractor: "openai_choices"
 //! ```
 
 mod config;
 mod usage;
 mod request;
+mod response;
+mod path_template;
+mod protobuf;
 
-pub use config::{ProviderConfig, ProviderRegistry, ResponseConfig, RequestExtractorType};
+pub use config::{
+    GrpcUsageFields, ProviderConfig, ProviderRegistry, ResponseConfig, RequestExtractorType,
+    ResponseExtractorType,
+};
 pub use usage::ConfigurableProvider;
 pub use request::RequestExtractor;
+pub use response::ResponseExtractor;
+pub use path_template::PathPattern;
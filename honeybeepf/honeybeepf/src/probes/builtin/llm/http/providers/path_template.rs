@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+/// A compiled `ProviderConfig` path pattern. Plain patterns (no `{}`) keep
+/// matching exactly like before - a substring check - so existing configs
+/// need no changes. A pattern containing `{name}` segments is compiled into
+/// an unanchored regex with a named capture group per parameter, letting
+/// `/v1/models/{model}:generateContent` tell apart
+/// `/v1/models/gpt-4:generateContent` from
+/// `/v1/models/gemini-pro:embedContent` and recover `model` from the URL.
+#[derive(Debug, Clone)]
+pub enum PathPattern {
+    Literal(String),
+    Template { source: String, regex: Regex },
+}
+
+impl PathPattern {
+    pub fn compile(pattern: &str) -> Self {
+        if !pattern.contains('{') {
+            return PathPattern::Literal(pattern.to_string());
+        }
+
+        let mut regex_str = String::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '{' {
+                let name: String = chars.by_ref().take_while(|&c2| c2 != '}').collect();
+                regex_str.push_str(&format!("(?P<{}>[^/]+)", name));
+            } else {
+                regex_str.push_str(&regex::escape(&c.to_string()));
+            }
+        }
+
+        match Regex::new(&regex_str) {
+            Ok(regex) => PathPattern::Template {
+                source: pattern.to_string(),
+                regex,
+            },
+            Err(e) => {
+                log::warn!(
+                    "Invalid path template '{}': {} - falling back to literal match",
+                    pattern,
+                    e
+                );
+                PathPattern::Literal(pattern.to_string())
+            }
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        match self {
+            PathPattern::Literal(s) => s,
+            PathPattern::Template { source, .. } => source,
+        }
+    }
+
+    /// Matches `path`, returning the captured `{param}` values (empty for a
+    /// plain literal match) or `None` if `path` doesn't match at all.
+    pub fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
+        match self {
+            PathPattern::Literal(lit) => path.contains(lit.as_str()).then(HashMap::new),
+            PathPattern::Template { regex, .. } => {
+                let caps = regex.captures(path)?;
+                Some(
+                    regex
+                        .capture_names()
+                        .flatten()
+                        .filter_map(|name| {
+                            caps.name(name).map(|m| (name.to_string(), m.as_str().to_string()))
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_pattern_matches_by_substring() {
+        let pattern = PathPattern::compile("/chat/completions");
+        assert_eq!(
+            pattern.match_path("/v1/chat/completions"),
+            Some(HashMap::new())
+        );
+        assert_eq!(pattern.match_path("/v1/messages"), None);
+    }
+
+    #[test]
+    fn test_template_pattern_captures_param() {
+        let pattern = PathPattern::compile("/v1/models/{model}:generateContent");
+
+        let captures = pattern
+            .match_path("/v1/models/gpt-4:generateContent")
+            .expect("should match");
+        assert_eq!(captures.get("model"), Some(&"gpt-4".to_string()));
+
+        assert_eq!(
+            pattern.match_path("/v1/models/gemini-pro:embedContent"),
+            None
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+/// Minimal protobuf wire-format reader: walks fields without a message
+/// descriptor, collecting varint-typed field values by field number. Enough
+/// to pull a handful of token-count fields out of a gRPC usage-metadata
+/// message - not a general protobuf decoder.
+pub fn scan_varint_fields(payload: &[u8], wanted: &[u32]) -> HashMap<u32, u64> {
+    let mut found = HashMap::new();
+    let mut pos = 0;
+
+    while pos < payload.len() {
+        let (tag, tag_len) = match read_varint(payload, pos) {
+            Some(v) => v,
+            None => break,
+        };
+        pos += tag_len;
+
+        let field_number = (tag >> 3) as u32;
+        let wire_type = tag & 0x7;
+
+        match wire_type {
+            0 => {
+                let (value, len) = match read_varint(payload, pos) {
+                    Some(v) => v,
+                    None => break,
+                };
+                pos += len;
+                if wanted.contains(&field_number) {
+                    found.insert(field_number, value);
+                }
+            }
+            1 => pos += 8, // 64-bit fixed (double, fixed64)
+            2 => {
+                // Length-delimited (string/bytes/embedded message)
+                let (len, len_bytes) = match read_varint(payload, pos) {
+                    Some(v) => v,
+                    None => break,
+                };
+                pos += len_bytes;
+                let len = len as usize;
+                if pos + len > payload.len() {
+                    break;
+                }
+                pos += len;
+            }
+            5 => pos += 4, // 32-bit fixed (float, fixed32)
+            _ => break,    // Unknown wire type - can't safely skip, stop here
+        }
+    }
+
+    found
+}
+
+/// Reads a base-128 varint starting at `pos`, returning `(value, bytes_consumed)`.
+fn read_varint(buf: &[u8], pos: usize) -> Option<(u64, usize)> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    let mut i = pos;
+
+    loop {
+        let byte = *buf.get(i)?;
+        value |= u64::from(byte & 0x7f) << shift;
+        i += 1;
+        if byte & 0x80 == 0 {
+            return Some((value, i - pos));
+        }
+        shift += 7;
+        if shift >= 64 {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_varint_fields_reads_tagged_values() {
+        // Field 1 (varint) = 150, field 2 (varint) = 7
+        let payload = vec![0x08, 0x96, 0x01, 0x10, 0x07];
+        let found = scan_varint_fields(&payload, &[1, 2]);
+        assert_eq!(found.get(&1), Some(&150));
+        assert_eq!(found.get(&2), Some(&7));
+    }
+
+    #[test]
+    fn test_scan_varint_fields_skips_length_delimited_and_fixed() {
+        // Field 3 (length-delimited, "abc"), field 4 (32-bit fixed), field 1 (varint) = 42
+        let payload = vec![
+            0x1a, 0x03, b'a', b'b', b'c', // field 3, len-delim "abc"
+            0x25, 0x00, 0x00, 0x00, 0x00, // field 4, fixed32
+            0x08, 0x2a, // field 1, varint 42
+        ];
+        let found = scan_varint_fields(&payload, &[1]);
+        assert_eq!(found.get(&1), Some(&42));
+    }
+
+    #[test]
+    fn test_scan_varint_fields_ignores_unwanted_fields() {
+        let payload = vec![0x08, 0x96, 0x01, 0x10, 0x07];
+        let found = scan_varint_fields(&payload, &[2]);
+        assert!(!found.contains_key(&1));
+        assert_eq!(found.get(&2), Some(&7));
+    }
+}
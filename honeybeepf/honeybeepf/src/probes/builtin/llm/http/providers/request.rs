@@ -10,7 +10,9 @@ pub trait RequestExtractor: Send + Sync {
 pub fn get_extractor(extractor_type: &RequestExtractorType) -> Box<dyn RequestExtractor> {
     match extractor_type {
         RequestExtractorType::Messages => Box::new(MessagesExtractor),
+        RequestExtractorType::MessagesWithTools => Box::new(MessagesWithToolsExtractor),
         RequestExtractorType::Contents => Box::new(ContentsExtractor),
+        RequestExtractorType::ContentsWithTools => Box::new(ContentsWithToolsExtractor),
         RequestExtractorType::Prompt => Box::new(PromptExtractor),
         RequestExtractorType::None => Box::new(NoOpExtractor),
     }
@@ -42,6 +44,49 @@ impl RequestExtractor for MessagesExtractor {
     }
 }
 
+/// OpenAI/Anthropic style, plus `tools`/`functions` schema definitions and
+/// any assistant `tool_calls`/`tool_use` arguments. Tool schemas and call
+/// arguments consume real prompt/completion tokens just like message text,
+/// so agentic workloads that lean on them need those bytes in the estimate
+/// too, not just `messages[].content`.
+struct MessagesWithToolsExtractor;
+
+impl RequestExtractor for MessagesWithToolsExtractor {
+    fn extract(&self, json: &Value) -> String {
+        let mut texts = vec![MessagesExtractor.extract(json)];
+
+        if let Some(tools) = json.get("tools").or_else(|| json.get("functions")) {
+            texts.push(tools.to_string());
+        }
+
+        if let Some(messages) = json.get("messages").and_then(|m| m.as_array()) {
+            for msg in messages {
+                // OpenAI: assistant messages carry `tool_calls[].function.arguments`
+                if let Some(tool_calls) = msg.get("tool_calls").and_then(|t| t.as_array()) {
+                    for call in tool_calls {
+                        if let Some(args) = call.get("function").and_then(|f| f.get("arguments")) {
+                            texts.push(args.as_str().map(str::to_string).unwrap_or_else(|| args.to_string()));
+                        }
+                    }
+                }
+                // Anthropic: `tool_use` content blocks carry `input`
+                if let Some(blocks) = msg.get("content").and_then(|c| c.as_array()) {
+                    for block in blocks {
+                        if block.get("type").and_then(|t| t.as_str()) == Some("tool_use") {
+                            if let Some(input) = block.get("input") {
+                                texts.push(input.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        texts.retain(|t| !t.is_empty());
+        texts.join(" ")
+    }
+}
+
 /// Gemini style: contents[].parts[].text
 struct ContentsExtractor;
 
@@ -63,6 +108,36 @@ impl RequestExtractor for ContentsExtractor {
     }
 }
 
+/// Gemini style, plus `tools` schema definitions and any `functionCall`
+/// arguments within `parts[]` - the Gemini analogue of
+/// `MessagesWithToolsExtractor`.
+struct ContentsWithToolsExtractor;
+
+impl RequestExtractor for ContentsWithToolsExtractor {
+    fn extract(&self, json: &Value) -> String {
+        let mut texts = vec![ContentsExtractor.extract(json)];
+
+        if let Some(tools) = json.get("tools") {
+            texts.push(tools.to_string());
+        }
+
+        if let Some(contents) = json.get("contents").and_then(|c| c.as_array()) {
+            for content in contents {
+                if let Some(parts) = content.get("parts").and_then(|p| p.as_array()) {
+                    for part in parts {
+                        if let Some(args) = part.get("functionCall").and_then(|f| f.get("args")) {
+                            texts.push(args.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        texts.retain(|t| !t.is_empty());
+        texts.join(" ")
+    }
+}
+
 /// Simple prompt field
 struct PromptExtractor;
 
@@ -121,6 +196,66 @@ mod tests {
         assert_eq!(result, "What's in this image?");
     }
 
+    #[test]
+    fn test_messages_with_tools_extractor_includes_tool_schema_and_calls() {
+        let extractor = MessagesWithToolsExtractor;
+        let json = json!({
+            "tools": [
+                {"type": "function", "function": {"name": "get_weather", "parameters": {"type": "object"}}}
+            ],
+            "messages": [
+                {"role": "user", "content": "What's the weather in Boston?"},
+                {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [
+                        {"function": {"name": "get_weather", "arguments": "{\"city\":\"Boston\"}"}}
+                    ]
+                }
+            ]
+        });
+        let result = extractor.extract(&json);
+        assert!(result.contains("What's the weather in Boston?"));
+        assert!(result.contains("get_weather"));
+        assert!(result.contains("Boston"));
+    }
+
+    #[test]
+    fn test_messages_with_tools_extractor_handles_anthropic_tool_use_blocks() {
+        let extractor = MessagesWithToolsExtractor;
+        let json = json!({
+            "tools": [{"name": "get_weather", "input_schema": {"type": "object"}}],
+            "messages": [
+                {
+                    "role": "assistant",
+                    "content": [
+                        {"type": "tool_use", "name": "get_weather", "input": {"city": "Boston"}}
+                    ]
+                }
+            ]
+        });
+        let result = extractor.extract(&json);
+        assert!(result.contains("Boston"));
+    }
+
+    #[test]
+    fn test_contents_with_tools_extractor_includes_tool_schema_and_function_calls() {
+        let extractor = ContentsWithToolsExtractor;
+        let json = json!({
+            "tools": [{"functionDeclarations": [{"name": "get_weather"}]}],
+            "contents": [
+                {
+                    "parts": [
+                        {"functionCall": {"name": "get_weather", "args": {"city": "Boston"}}}
+                    ]
+                }
+            ]
+        });
+        let result = extractor.extract(&json);
+        assert!(result.contains("get_weather"));
+        assert!(result.contains("Boston"));
+    }
+
     #[test]
     fn test_contents_extractor() {
         let extractor = ContentsExtractor;
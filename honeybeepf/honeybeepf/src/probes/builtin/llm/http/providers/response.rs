@@ -0,0 +1,152 @@
+use serde_json::Value;
+use super::config::ResponseExtractorType;
+
+/// Trait for extracting text from response JSON
+pub trait ResponseExtractor: Send + Sync {
+    fn extract(&self, json: &Value) -> String;
+}
+
+/// Get extractor for the given type
+pub fn get_response_extractor(extractor_type: &ResponseExtractorType) -> Box<dyn ResponseExtractor> {
+    match extractor_type {
+        ResponseExtractorType::OpenAiChoices => Box::new(OpenAiChoicesExtractor),
+        ResponseExtractorType::AnthropicContent => Box::new(AnthropicContentExtractor),
+        ResponseExtractorType::GeminiCandidates => Box::new(GeminiCandidatesExtractor),
+        ResponseExtractorType::None => Box::new(NoOpResponseExtractor),
+    }
+}
+
+/// OpenAI style: choices[].message.content, or choices[].delta.content for
+/// streaming responses - a response only ever carries one shape or the
+/// other, so both are checked per choice.
+struct OpenAiChoicesExtractor;
+
+impl ResponseExtractor for OpenAiChoicesExtractor {
+    fn extract(&self, json: &Value) -> String {
+        let mut texts = Vec::new();
+        if let Some(choices) = json.get("choices").and_then(|c| c.as_array()) {
+            for choice in choices {
+                let content = choice
+                    .get("message")
+                    .and_then(|m| m.get("content"))
+                    .or_else(|| choice.get("delta").and_then(|d| d.get("content")));
+                if let Some(text) = content.and_then(|c| c.as_str()) {
+                    texts.push(text.to_string());
+                }
+            }
+        }
+        texts.join(" ")
+    }
+}
+
+/// Anthropic style: content[].text
+struct AnthropicContentExtractor;
+
+impl ResponseExtractor for AnthropicContentExtractor {
+    fn extract(&self, json: &Value) -> String {
+        let mut texts = Vec::new();
+        if let Some(content) = json.get("content").and_then(|c| c.as_array()) {
+            for block in content {
+                if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                    texts.push(text.to_string());
+                }
+            }
+        }
+        texts.join(" ")
+    }
+}
+
+/// Gemini style: candidates[].content.parts[].text
+struct GeminiCandidatesExtractor;
+
+impl ResponseExtractor for GeminiCandidatesExtractor {
+    fn extract(&self, json: &Value) -> String {
+        let mut texts = Vec::new();
+        if let Some(candidates) = json.get("candidates").and_then(|c| c.as_array()) {
+            for candidate in candidates {
+                if let Some(parts) = candidate
+                    .get("content")
+                    .and_then(|c| c.get("parts"))
+                    .and_then(|p| p.as_array())
+                {
+                    for part in parts {
+                        if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                            texts.push(text.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        texts.join(" ")
+    }
+}
+
+/// No-op extractor (returns empty string)
+struct NoOpResponseExtractor;
+
+impl ResponseExtractor for NoOpResponseExtractor {
+    fn extract(&self, _json: &Value) -> String {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_openai_choices_extractor_message() {
+        let extractor = OpenAiChoicesExtractor;
+        let json = json!({
+            "choices": [
+                {"message": {"role": "assistant", "content": "Hello there"}}
+            ]
+        });
+        let result = extractor.extract(&json);
+        assert_eq!(result, "Hello there");
+    }
+
+    #[test]
+    fn test_openai_choices_extractor_streaming_delta() {
+        let extractor = OpenAiChoicesExtractor;
+        let json = json!({
+            "choices": [
+                {"delta": {"content": "Hel"}},
+            ]
+        });
+        let result = extractor.extract(&json);
+        assert_eq!(result, "Hel");
+    }
+
+    #[test]
+    fn test_anthropic_content_extractor() {
+        let extractor = AnthropicContentExtractor;
+        let json = json!({
+            "content": [
+                {"type": "text", "text": "Hello there"}
+            ]
+        });
+        let result = extractor.extract(&json);
+        assert_eq!(result, "Hello there");
+    }
+
+    #[test]
+    fn test_gemini_candidates_extractor() {
+        let extractor = GeminiCandidatesExtractor;
+        let json = json!({
+            "candidates": [
+                {"content": {"parts": [{"text": "Hello from Gemini"}]}}
+            ]
+        });
+        let result = extractor.extract(&json);
+        assert_eq!(result, "Hello from Gemini");
+    }
+
+    #[test]
+    fn test_noop_response_extractor() {
+        let extractor = NoOpResponseExtractor;
+        let json = json!({"choices": [{"message": {"content": "ignored"}}]});
+        assert_eq!(extractor.extract(&json), "");
+    }
+}
@@ -1,34 +1,48 @@
+use std::collections::HashMap;
+
 use serde_json::Value;
 
 use super::{
-    config::ProviderConfig,
+    config::{GrpcUsageFields, ProviderConfig},
     request::{RequestExtractor, get_extractor},
+    response::{ResponseExtractor, get_response_extractor},
 };
 use crate::probes::builtin::llm::types::UsageInfo;
 
+/// Prefix marking a `ResponseConfig.model_path` as a reference into the
+/// `{param}` map captured by `ProviderConfig::match_path`, e.g.
+/// `$path.model`, rather than a JSON path into the response body.
+const PATH_PARAM_PREFIX: &str = "$path.";
+
 /// A provider instance created from configuration
 pub struct ConfigurableProvider {
     config: ProviderConfig,
     extractor: Box<dyn RequestExtractor>,
+    response_extractor: Box<dyn ResponseExtractor>,
 }
 
 impl ConfigurableProvider {
     pub fn new(config: ProviderConfig) -> Self {
         let extractor = get_extractor(&config.request_extractor);
-        Self { config, extractor }
+        let response_extractor = get_response_extractor(&config.response_extractor);
+        Self { config, extractor, response_extractor }
     }
 
     pub fn name(&self) -> &str {
         &self.config.name
     }
 
+    /// This provider's gRPC usage-field mapping, if its responses are
+    /// protobuf rather than JSON - see `GrpcParser`.
+    pub fn grpc_usage_fields(&self) -> Option<&GrpcUsageFields> {
+        self.config.response.grpc.as_ref()
+    }
+
     /// Check if this provider matches the given host and path
     pub fn matches(&self, host: &str, path: &str) -> bool {
         let host_match =
             self.config.hosts.is_empty() || self.config.hosts.iter().any(|h| host.contains(h));
-        let path_match =
-            self.config.paths.is_empty() || self.config.paths.iter().any(|p| path.contains(p));
-        host_match && path_match
+        host_match && self.config.match_path(path).is_some()
     }
 
     /// Check if request JSON looks like this provider's format
@@ -43,8 +57,18 @@ impl ConfigurableProvider {
         self.extractor.extract(json)
     }
 
-    /// Parse usage from response JSON using configured paths
-    pub fn parse_usage(&self, json: &Value) -> Option<UsageInfo> {
+    /// Extract text from a response body, e.g. the model's completion text,
+    /// for full prompt+completion visibility alongside `extract_request_text`.
+    pub fn extract_response_text(&self, json: &Value) -> String {
+        self.response_extractor.extract(json)
+    }
+
+    /// Parse usage from response JSON using configured paths. `path_params`
+    /// is whatever `ProviderConfig::match_path` captured from the request's
+    /// URL (empty if the caller has none available, e.g. no path template
+    /// was configured, or the response is being parsed out of band from the
+    /// request that produced it) - see `ResponseConfig::model_path`.
+    pub fn parse_usage(&self, json: &Value, path_params: &HashMap<String, String>) -> Option<UsageInfo> {
         let response_config = &self.config.response;
 
         // Get usage object using configured path
@@ -63,20 +87,38 @@ impl ConfigurableProvider {
             .and_then(|path| get_nested_value(usage, path))
             .and_then(|v| v.as_u64());
 
-        // Model name from root
-        let model = get_nested_value(json, &response_config.model_path)
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        // Optional: tool/function-calling tokens
+        let tools = response_config
+            .tool_tokens
+            .as_ref()
+            .and_then(|path| get_nested_value(usage, path))
+            .and_then(|v| v.as_u64());
+
+        let model = resolve_model(&response_config.model_path, json, path_params);
 
         Some(UsageInfo {
             prompt_tokens: prompt,
             completion_tokens: completion,
             thoughts_tokens: thoughts,
+            tool_tokens: tools,
             model,
+            estimated: false,
         })
     }
 }
 
+/// Resolves `ResponseConfig.model_path`: either a `$path.<param>` reference
+/// into `path_params`, or (the common case) a JSON path into the response
+/// body itself.
+fn resolve_model(model_path: &str, json: &Value, path_params: &HashMap<String, String>) -> Option<String> {
+    if let Some(param) = model_path.strip_prefix(PATH_PARAM_PREFIX) {
+        return path_params.get(param).cloned();
+    }
+    get_nested_value(json, model_path)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
 /// Get a nested value using dot-notation path (e.g., "usage.prompt_tokens")
 fn get_nested_value<'a>(json: &'a Value, path: &str) -> Option<&'a Value> {
     let mut current = json;
@@ -91,7 +133,7 @@ mod tests {
     use serde_json::json;
 
     use super::{
-        super::config::{ProviderConfig, RequestExtractorType, ResponseConfig},
+        super::config::{ProviderConfig, RequestExtractorType, ResponseConfig, ResponseExtractorType},
         *,
     };
 
@@ -105,9 +147,12 @@ mod tests {
                 prompt_tokens: "prompt_tokens".to_string(),
                 completion_tokens: "completion_tokens".to_string(),
                 thoughts_tokens: None,
+                tool_tokens: None,
                 model_path: "model".to_string(),
+                grpc: None,
             },
             request_extractor: RequestExtractorType::Messages,
+            response_extractor: ResponseExtractorType::OpenAiChoices,
         }
     }
 
@@ -121,9 +166,12 @@ mod tests {
                 prompt_tokens: "promptTokenCount".to_string(),
                 completion_tokens: "candidatesTokenCount".to_string(),
                 thoughts_tokens: Some("thoughtsTokenCount".to_string()),
+                tool_tokens: None,
                 model_path: "modelVersion".to_string(),
+                grpc: None,
             },
             request_extractor: RequestExtractorType::Contents,
+            response_extractor: ResponseExtractorType::GeminiCandidates,
         }
     }
 
@@ -147,7 +195,7 @@ mod tests {
             }
         });
 
-        let usage = provider.parse_usage(&response).unwrap();
+        let usage = provider.parse_usage(&response, &HashMap::new()).unwrap();
         assert_eq!(usage.prompt_tokens, 10);
         assert_eq!(usage.completion_tokens, 20);
         assert_eq!(usage.model, Some("gpt-4".to_string()));
@@ -166,13 +214,32 @@ mod tests {
             "modelVersion": "gemini-1.5-pro"
         });
 
-        let usage = provider.parse_usage(&response).unwrap();
+        let usage = provider.parse_usage(&response, &HashMap::new()).unwrap();
         assert_eq!(usage.prompt_tokens, 15);
         assert_eq!(usage.completion_tokens, 25);
         assert_eq!(usage.thoughts_tokens, Some(100));
         assert_eq!(usage.model, Some("gemini-1.5-pro".to_string()));
     }
 
+    #[test]
+    fn test_parse_usage_resolves_model_from_path_param() {
+        let mut config = gemini_config();
+        config.response.model_path = "$path.model".to_string();
+        let provider = ConfigurableProvider::new(config);
+
+        let response = json!({
+            "usageMetadata": {
+                "promptTokenCount": 15,
+                "candidatesTokenCount": 25,
+            },
+        });
+        let mut path_params = HashMap::new();
+        path_params.insert("model".to_string(), "gemini-pro".to_string());
+
+        let usage = provider.parse_usage(&response, &path_params).unwrap();
+        assert_eq!(usage.model, Some("gemini-pro".to_string()));
+    }
+
     #[test]
     fn test_extract_request_text() {
         let provider = ConfigurableProvider::new(openai_config());
@@ -187,6 +254,19 @@ mod tests {
         assert_eq!(text, "Hello, world!");
     }
 
+    #[test]
+    fn test_extract_response_text() {
+        let provider = ConfigurableProvider::new(openai_config());
+        let response = json!({
+            "choices": [
+                {"message": {"role": "assistant", "content": "Hi, human!"}}
+            ]
+        });
+
+        let text = provider.extract_response_text(&response);
+        assert_eq!(text, "Hi, human!");
+    }
+
     #[test]
     fn test_nested_path() {
         let json = json!({
@@ -0,0 +1,410 @@
+//! Incremental chunked/SSE decoding for streaming LLM responses.
+//!
+//! `ProtocolParser::feed` hands newly-arrived response bytes to a
+//! `StreamDecoder` as they come in, instead of reparsing the whole
+//! accumulated buffer on every event. It peels off HTTP chunked framing,
+//! splits the dechunked body into SSE records on a blank line, and
+//! tokenizes each `choices[].delta.content` fragment the moment it
+//! completes, so streamed completions get a live token count even when
+//! the provider never sends (or only sends at the very end) a `usage`
+//! object.
+
+use tiktoken_rs::CoreBPE;
+
+use super::protocol::parse_response_json_value;
+use super::utils as byte_utils;
+use crate::probes::builtin::llm::types::{SseChunkDelta, SseDeltaChunk, UsageInfo};
+
+#[derive(Default)]
+pub struct StreamDecoder {
+    /// Bytes seen so far while still waiting for the end of the response
+    /// headers (`\r\n\r\n`).
+    header_buf: Vec<u8>,
+    headers_done: bool,
+    is_sse: bool,
+    is_chunked: bool,
+    /// Undecoded tail of the chunked framing (a chunk-size line, or chunk
+    /// data that hasn't fully arrived yet).
+    chunk_tail: Vec<u8>,
+    /// Dechunked bytes not yet split into a complete SSE record.
+    sse_tail: Vec<u8>,
+    /// Running count of completion tokens tokenized from streamed deltas.
+    pub completion_tokens: u64,
+    /// Terminal `usage` object, if the provider sent one (e.g. OpenAI's
+    /// `stream_options: {"include_usage": true}`).
+    pub usage: Option<UsageInfo>,
+    done: bool,
+}
+
+impl StreamDecoder {
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Best-effort `UsageInfo` for whatever this decoder has seen so far:
+    /// the provider's own terminal `usage` object if one arrived, otherwise
+    /// (when any completion text has been tokenized) a synthetic one built
+    /// from `completion_tokens` and flagged `estimated`. Many providers
+    /// never send `usage` in a stream unless the request opted in with
+    /// `stream_options: {"include_usage": true}`, and a connection can go
+    /// idle before the stream's own terminal marker ever arrives — in both
+    /// cases this is the only token count the agent will ever have for the
+    /// response, so callers use it instead of reporting nothing.
+    pub fn usage_or_estimate(&self) -> Option<UsageInfo> {
+        if self.usage.is_some() {
+            return self.usage.clone();
+        }
+        if self.completion_tokens == 0 {
+            return None;
+        }
+        Some(UsageInfo {
+            prompt_tokens: 0,
+            completion_tokens: self.completion_tokens,
+            thoughts_tokens: None,
+            tool_tokens: None,
+            model: None,
+            estimated: true,
+        })
+    }
+
+    /// Whether headers have confirmed this is an SSE response. `false`
+    /// both before headers arrive and once they've confirmed it *isn't*
+    /// SSE — callers should keep using `parse_response` in either case.
+    pub fn is_stream(&self) -> bool {
+        self.headers_done && self.is_sse
+    }
+
+    /// Feeds body bytes directly, with no HTTP/1.1 header/chunked framing
+    /// to strip first: used for HTTP/2, whose responses never present a
+    /// textual status line/`Content-Type` header the way `feed` gates on
+    /// for H1. SSE-ness is instead decided from the first fed bytes by
+    /// checking whether they contain a `data:` field, since unary (non-streaming) H2
+    /// responses are complete JSON bodies with no such marker.
+    pub fn feed_h2_body(&mut self, new_bytes: &[u8], bpe: &CoreBPE) {
+        if self.done {
+            return;
+        }
+        if !self.headers_done {
+            self.headers_done = true;
+            self.is_sse = byte_utils::contains_pattern(new_bytes, b"data:");
+            if !self.is_sse {
+                self.done = true;
+                return;
+            }
+        }
+        self.ingest_body(new_bytes, bpe);
+    }
+
+    /// Feeds newly-arrived raw response bytes (headers and/or body,
+    /// however TCP happened to split them).
+    pub fn feed(&mut self, new_bytes: &[u8], bpe: &CoreBPE) {
+        if self.done {
+            return;
+        }
+
+        if !self.headers_done {
+            self.header_buf.extend_from_slice(new_bytes);
+            let Some(body) = self.try_consume_headers() else {
+                return; // headers not fully arrived yet
+            };
+            if !self.is_sse {
+                // Not a streaming response; `parse_response` handles it.
+                self.done = true;
+                return;
+            }
+            self.ingest_body(&body, bpe);
+            return;
+        }
+
+        self.ingest_body(new_bytes, bpe);
+    }
+
+    /// Parses `header_buf` as an HTTP response once it looks complete,
+    /// recording whether the response is chunked/SSE and returning
+    /// whatever body bytes arrived along with the headers.
+    fn try_consume_headers(&mut self) -> Option<Vec<u8>> {
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut resp = httparse::Response::new(&mut headers);
+
+        let body_offset = match resp.parse(&self.header_buf) {
+            Ok(httparse::Status::Complete(n)) => n,
+            _ => return None,
+        };
+
+        self.is_sse = resp.headers.iter().any(|h| {
+            h.name.eq_ignore_ascii_case("Content-Type")
+                && String::from_utf8_lossy(h.value).contains("text/event-stream")
+        });
+        self.is_chunked = resp.headers.iter().any(|h| {
+            h.name.eq_ignore_ascii_case("Transfer-Encoding")
+                && String::from_utf8_lossy(h.value).contains("chunked")
+        });
+
+        self.headers_done = true;
+        let body = self.header_buf[body_offset..].to_vec();
+        self.header_buf.clear();
+        self.header_buf.shrink_to_fit();
+        Some(body)
+    }
+
+    fn ingest_body(&mut self, bytes: &[u8], bpe: &CoreBPE) {
+        if self.is_chunked {
+            self.chunk_tail.extend_from_slice(bytes);
+            let dechunked = self.drain_chunks();
+            self.sse_tail.extend_from_slice(&dechunked);
+        } else {
+            self.sse_tail.extend_from_slice(bytes);
+        }
+        self.drain_sse_records(bpe);
+    }
+
+    /// Pulls as many complete `size\r\ndata\r\n` chunks as possible out of
+    /// `chunk_tail`, leaving any trailing partial chunk for next time.
+    fn drain_chunks(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut pos = 0;
+
+        loop {
+            let Some(rel) = byte_utils::find_pattern(&self.chunk_tail[pos..], b"\r\n") else {
+                break;
+            };
+            let size_end = pos + rel;
+            let size_str = String::from_utf8_lossy(&self.chunk_tail[pos..size_end]);
+            let Ok(chunk_size) = usize::from_str_radix(size_str.trim(), 16) else {
+                break;
+            };
+
+            if chunk_size == 0 {
+                self.done = true;
+                break;
+            }
+
+            let data_start = size_end + 2;
+            let data_end = data_start + chunk_size;
+            if data_end + 2 > self.chunk_tail.len() {
+                break; // chunk hasn't fully arrived yet
+            }
+
+            out.extend_from_slice(&self.chunk_tail[data_start..data_end]);
+            pos = data_end + 2;
+        }
+
+        self.chunk_tail.drain(..pos);
+        out
+    }
+
+    /// Splits `sse_tail` into complete records (terminated by a blank
+    /// line), tokenizing each `data: ` payload as it completes.
+    fn drain_sse_records(&mut self, bpe: &CoreBPE) {
+        while !self.done {
+            let sep = byte_utils::find_pattern(&self.sse_tail, b"\n\n")
+                .or_else(|| byte_utils::find_pattern(&self.sse_tail, b"\r\n\r\n"));
+            let Some(sep) = sep else {
+                break;
+            };
+
+            let record: Vec<u8> = self.sse_tail.drain(..sep + 2).collect();
+            self.handle_sse_record(&record, bpe);
+        }
+    }
+
+    fn handle_sse_record(&mut self, record: &[u8], bpe: &CoreBPE) {
+        let text = String::from_utf8_lossy(record);
+        for line in text.lines() {
+            let line = line.trim();
+            let Some(data) = line
+                .strip_prefix("data: ")
+                .or_else(|| line.strip_prefix("data:"))
+            else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                self.done = true;
+                return;
+            }
+
+            if let Ok(usage_probe) = serde_json::from_str::<SseChunkDelta>(data) {
+                if usage_probe.usage.is_some() {
+                    if let Ok(val) = serde_json::from_str::<serde_json::Value>(data) {
+                        if let Some(info) = parse_response_json_value(&val) {
+                            self.usage = Some(info);
+                        }
+                    }
+                }
+            }
+
+            if let Ok(delta) = serde_json::from_str::<SseDeltaChunk>(data) {
+                for choice in &delta.choices {
+                    if let Some(content) = &choice.delta.content {
+                        if !content.is_empty() {
+                            self.completion_tokens +=
+                                bpe.encode_with_special_tokens(content).len() as u64;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiktoken_rs::cl100k_base;
+
+    fn sse_response(records: &[&str]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n",
+        );
+        for record in records {
+            out.extend_from_slice(record.as_bytes());
+            out.extend_from_slice(b"\n\n");
+        }
+        out
+    }
+
+    #[test]
+    fn tokenizes_deltas_as_they_arrive() {
+        let bpe = cl100k_base().unwrap();
+        let mut decoder = StreamDecoder::default();
+
+        let response = sse_response(&[
+            r#"data: {"choices":[{"delta":{"content":"Hello"}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":" world"}}]}"#,
+            "data: [DONE]",
+        ]);
+
+        decoder.feed(&response, &bpe);
+
+        assert!(decoder.completion_tokens > 0);
+        assert!(decoder.is_done());
+    }
+
+    #[test]
+    fn captures_terminal_usage_object() {
+        let bpe = cl100k_base().unwrap();
+        let mut decoder = StreamDecoder::default();
+
+        let response = sse_response(&[
+            r#"data: {"choices":[{"delta":{"content":"Hi"}}]}"#,
+            r#"data: {"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":1,"total_tokens":6}}"#,
+            "data: [DONE]",
+        ]);
+
+        decoder.feed(&response, &bpe);
+
+        let usage = decoder.usage.expect("usage should be captured");
+        assert_eq!(usage.prompt_tokens, 5);
+        assert_eq!(usage.completion_tokens, 1);
+    }
+
+    #[test]
+    fn feed_across_multiple_calls_handles_split_chunks() {
+        let bpe = cl100k_base().unwrap();
+        let mut decoder = StreamDecoder::default();
+
+        let response = sse_response(&[r#"data: {"choices":[{"delta":{"content":"Hello"}}]}"#, "data: [DONE]"]);
+        let (first, second) = response.split_at(response.len() / 2);
+
+        decoder.feed(first, &bpe);
+        decoder.feed(second, &bpe);
+
+        assert!(decoder.completion_tokens > 0);
+        assert!(decoder.is_done());
+    }
+
+    #[test]
+    fn h2_body_tokenizes_deltas_without_header_framing() {
+        let bpe = cl100k_base().unwrap();
+        let mut decoder = StreamDecoder::default();
+
+        let mut body = Vec::new();
+        body.extend_from_slice(br#"data: {"choices":[{"delta":{"content":"Hi"}}]}"#);
+        body.extend_from_slice(b"\n\n");
+        body.extend_from_slice(b"data: [DONE]\n\n");
+
+        decoder.feed_h2_body(&body, &bpe);
+
+        assert!(decoder.completion_tokens > 0);
+        assert!(decoder.is_done());
+    }
+
+    #[test]
+    fn h2_body_without_data_prefix_is_not_treated_as_sse() {
+        let bpe = cl100k_base().unwrap();
+        let mut decoder = StreamDecoder::default();
+
+        decoder.feed_h2_body(br#"{"choices":[{"message":{"content":"Hi"}}]}"#, &bpe);
+
+        assert!(!decoder.is_stream());
+        assert!(decoder.is_done());
+    }
+
+    #[test]
+    fn usage_or_estimate_flags_tokenized_deltas_as_estimated_when_no_usage_sent() {
+        let bpe = cl100k_base().unwrap();
+        let mut decoder = StreamDecoder::default();
+
+        // No `usage` object anywhere in the stream - common unless the
+        // request opted in with `stream_options.include_usage`.
+        let response = sse_response(&[
+            r#"data: {"choices":[{"delta":{"content":"Hello"}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":" world"}}]}"#,
+            "data: [DONE]",
+        ]);
+        decoder.feed(&response, &bpe);
+
+        let usage = decoder.usage_or_estimate().expect("should estimate from deltas");
+        assert!(usage.estimated);
+        assert_eq!(usage.prompt_tokens, 0);
+        assert!(usage.completion_tokens > 0);
+    }
+
+    #[test]
+    fn usage_or_estimate_prefers_real_terminal_usage_over_estimate() {
+        let bpe = cl100k_base().unwrap();
+        let mut decoder = StreamDecoder::default();
+
+        let response = sse_response(&[
+            r#"data: {"choices":[{"delta":{"content":"Hi"}}]}"#,
+            r#"data: {"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":1,"total_tokens":6}}"#,
+            "data: [DONE]",
+        ]);
+        decoder.feed(&response, &bpe);
+
+        let usage = decoder.usage_or_estimate().expect("usage should be present");
+        assert!(!usage.estimated);
+        assert_eq!(usage.prompt_tokens, 5);
+    }
+
+    #[test]
+    fn usage_or_estimate_returns_best_effort_from_a_stream_with_no_done_marker() {
+        let bpe = cl100k_base().unwrap();
+        let mut decoder = StreamDecoder::default();
+
+        // Mid-stream: connection went idle before `[DONE]` ever arrived.
+        let mut response = Vec::new();
+        response.extend_from_slice(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n");
+        response.extend_from_slice(br#"data: {"choices":[{"delta":{"content":"partial"}}]}"#);
+        response.extend_from_slice(b"\n\n");
+        decoder.feed(&response, &bpe);
+
+        assert!(!decoder.is_done());
+        let usage = decoder.usage_or_estimate().expect("should have a best-effort estimate");
+        assert!(usage.estimated);
+        assert!(usage.completion_tokens > 0);
+    }
+
+    #[test]
+    fn non_sse_response_is_a_no_op() {
+        let bpe = cl100k_base().unwrap();
+        let mut decoder = StreamDecoder::default();
+
+        decoder.feed(b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{}", &bpe);
+
+        assert_eq!(decoder.completion_tokens, 0);
+        assert!(decoder.is_done());
+    }
+}
@@ -1,6 +1,9 @@
+pub mod backend;
+pub mod buffer;
 pub mod discovery;
 pub mod http;
 pub mod processor;
+pub mod tls;
 pub mod types;
 
 use std::{
@@ -16,43 +19,62 @@ use aya::{
 };
 use honeybeepf_common::{ExecEvent, LlmEvent};
 use log::{info, warn};
+use once_cell::sync::Lazy;
 use processor::StreamProcessor;
-use tokio::sync::Notify;
+use tiktoken_rs::CoreBPE;
+use tokio::sync::{Notify, mpsc};
 use types::LlmDirection;
 
 use crate::probes::{IdentityResolver, Probe, spawn_ringbuf_handler};
+use backend::TlsBackend;
+use tls::TlsHelloInfo;
 
 // Queue and timing constants
 const MAX_EXEC_QUEUE_SIZE: usize = 1024; // Max pending exec PIDs
-const CLEANUP_INTERVAL_SECS: u64 = 30; // How often to run cleanup
 const CONNECTION_RETENTION_SECS: u64 = 300; // Keep idle connections for 5 minutes
+// Per-connection channel depth: enough to absorb a burst of events while the
+// task is busy tokenizing without the ringbuf handler ever blocking on send.
+const CONNECTION_CHANNEL_CAPACITY: usize = 256;
+
+static TOKENIZER: Lazy<CoreBPE> =
+    Lazy::new(|| tiktoken_rs::cl100k_base().expect("failed to load cl100k_base tokenizer"));
+
+/// Whether per-connection tasks should spend cycles tokenizing request/response
+/// bodies, or just time requests and log latency. Set
+/// `HONEYBEEPF_LLM_EXTRACT_TOKENS=0` to disable on CPU-constrained hosts.
+fn extract_tokens_enabled() -> bool {
+    std::env::var("HONEYBEEPF_LLM_EXTRACT_TOKENS")
+        .map(|v| v != "0" && !v.eq_ignore_ascii_case("false"))
+        .unwrap_or(true)
+}
+
+/// Attaches the shared rw/handshake uprobes to `path` using the entry-point
+/// symbol names for `backend`. The eBPF programs themselves (`probe_ssl_rw_enter`
+/// et al.) are backend-agnostic — they just read `(handle, buf, len)` off the
+/// arguments — so only the symbol names attached to change per backend.
+pub fn attach_backend_probes(bpf: &mut Ebpf, backend: TlsBackend, path: &str) -> Result<()> {
+    let symbols = backend.symbols();
+
+    // read/write need BOTH entry (to save buf ptr) and exit (to read data + emit event)
+    attach_uprobe(bpf, "probe_ssl_rw_enter", symbols.read, path)?;
+    attach_uprobe(bpf, "probe_ssl_read_exit", symbols.read, path)?;
+    attach_uprobe(bpf, "probe_ssl_rw_enter", symbols.write, path)?;
+    attach_uprobe(bpf, "probe_ssl_write_exit", symbols.write, path)?;
+
+    if let Some(handshake) = symbols.handshake {
+        attach_uprobe(bpf, "probe_ssl_do_handshake_enter", handshake, path)?;
+        attach_uprobe(bpf, "probe_ssl_do_handshake_exit", handshake, path)?;
+    }
 
-pub fn attach_probes_to_path(bpf: &mut Ebpf, libssl_path: &str) -> Result<()> {
-    // SSL_read/SSL_write need BOTH entry (to save buf ptr) and exit (to read data + emit event)
-    attach_uprobe(bpf, "probe_ssl_rw_enter", "SSL_read", libssl_path)?;
-    attach_uprobe(bpf, "probe_ssl_read_exit", "SSL_read", libssl_path)?;
-    attach_uprobe(bpf, "probe_ssl_rw_enter", "SSL_write", libssl_path)?;
-    attach_uprobe(bpf, "probe_ssl_write_exit", "SSL_write", libssl_path)?;
-
-    // Handshake
-    attach_uprobe(
-        bpf,
-        "probe_ssl_do_handshake_enter",
-        "SSL_do_handshake",
-        libssl_path,
-    )?;
-    attach_uprobe(
-        bpf,
-        "probe_ssl_do_handshake_exit",
-        "SSL_do_handshake",
-        libssl_path,
-    )?;
-
-    // Extended variants (optional — not all OpenSSL builds export these)
-    let _ = attach_uprobe(bpf, "probe_ssl_rw_ex_enter", "SSL_write_ex", libssl_path);
-    let _ = attach_uprobe(bpf, "probe_ssl_write_ex_exit", "SSL_write_ex", libssl_path);
-    let _ = attach_uprobe(bpf, "probe_ssl_rw_ex_enter", "SSL_read_ex", libssl_path);
-    let _ = attach_uprobe(bpf, "probe_ssl_read_ex_exit", "SSL_read_ex", libssl_path);
+    // Extended variants (optional — OpenSSL-only, not every build exports these)
+    if let Some(read_ex) = symbols.read_ex {
+        let _ = attach_uprobe(bpf, "probe_ssl_rw_ex_enter", read_ex, path);
+        let _ = attach_uprobe(bpf, "probe_ssl_read_ex_exit", read_ex, path);
+    }
+    if let Some(write_ex) = symbols.write_ex {
+        let _ = attach_uprobe(bpf, "probe_ssl_rw_ex_enter", write_ex, path);
+        let _ = attach_uprobe(bpf, "probe_ssl_write_ex_exit", write_ex, path);
+    }
 
     Ok(())
 }
@@ -66,15 +88,15 @@ pub fn attach_new_targets_for_pids(
     let targets = discovery::find_targets_for_pids(pids)?;
 
     for path in targets {
-        if path.contains("libcrypto") {
+        let Some(backend) = TlsBackend::classify(&path) else {
             continue;
-        }
+        };
         if known.contains(&path) {
             continue;
         }
 
-        info!("[Re-discovery] New SSL library found: {}", path);
-        match attach_probes_to_path(bpf, &path) {
+        info!("[Re-discovery] New {} library found: {}", backend.name(), path);
+        match attach_backend_probes(bpf, backend, &path) {
             Ok(()) => {
                 known.insert(path);
             }
@@ -87,14 +109,23 @@ pub fn attach_new_targets_for_pids(
     Ok(())
 }
 
-/// Shared queue of PIDs from exec events.
-pub type ExecPidQueue = Arc<Mutex<VecDeque<u32>>>;
+/// A newly exec'd process, as reported by `probe_exec`: the identity needed
+/// to decide whether it's worth scanning for TLS libraries, ahead of the
+/// `/proc/{pid}/maps` read that scan itself requires.
+pub struct ExecTarget {
+    pub pid: u32,
+    pub comm: String,
+    pub filename: String,
+}
+
+/// Shared queue of newly exec'd processes.
+pub type ExecPidQueue = Arc<Mutex<VecDeque<ExecTarget>>>;
 
 /// Notifier to wake up the main loop immediately when new exec events arrive.
 pub type ExecNotify = Arc<Notify>;
 
 /// Set up the `sched_process_exec` tracepoint and return a queue that collects
-/// PIDs of newly exec'd processes. The caller drains this queue to do targeted scans.
+/// newly exec'd processes. The caller drains this queue to do targeted scans.
 /// Also returns a Notify that gets triggered on each new exec event.
 pub fn setup_exec_watch(bpf: &mut Ebpf) -> Result<(ExecPidQueue, ExecNotify)> {
     let program: &mut TracePoint = bpf
@@ -113,7 +144,11 @@ pub fn setup_exec_watch(bpf: &mut Ebpf) -> Result<(ExecPidQueue, ExecNotify)> {
         let mut q = handler_queue.lock().unwrap_or_else(|e| e.into_inner());
         // Cap queue to avoid unbounded growth under extreme exec rates
         if q.len() < MAX_EXEC_QUEUE_SIZE {
-            q.push_back(event.pid);
+            q.push_back(ExecTarget {
+                pid: event.pid,
+                comm: bytes_to_string(&event.comm),
+                filename: bytes_to_string(&event.filename),
+            });
         }
         // Notify the main loop immediately
         handler_notify.notify_one();
@@ -123,13 +158,65 @@ pub fn setup_exec_watch(bpf: &mut Ebpf) -> Result<(ExecPidQueue, ExecNotify)> {
     Ok((queue, notify))
 }
 
+/// Converts a fixed-size, NUL-padded byte buffer (as filled by `bpf_get_current_comm`
+/// or a truncated tracepoint filename) into a `String`, stopping at the first NUL
+/// and discarding invalid UTF-8 rather than failing.
+fn bytes_to_string(buf: &[u8]) -> String {
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
+
+/// Comma-separated substrings to match a newly exec'd process's `comm`/`filename`
+/// against before scanning it for TLS libraries, read from
+/// `HONEYBEEPF_LLM_EXEC_ALLOWLIST`. Matching is case-insensitive and unset/empty
+/// means "scan everything", preserving today's behavior; operators on busy hosts
+/// with a known, fixed set of LLM-calling runtimes (e.g. `python,node,my-service`)
+/// can set this to skip attachment churn on the flood of short-lived utility
+/// processes a busy host execs.
+pub fn exec_allowlist() -> Vec<String> {
+    std::env::var("HONEYBEEPF_LLM_EXEC_ALLOWLIST")
+        .ok()
+        .map(|v| v.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+/// Whether `target` should be scanned for TLS libraries, per `allowlist`. An
+/// empty allowlist scans everything.
+pub fn exec_target_allowed(target: &ExecTarget, allowlist: &[String]) -> bool {
+    if allowlist.is_empty() {
+        return true;
+    }
+    let comm = target.comm.to_lowercase();
+    let filename = target.filename.to_lowercase();
+    allowlist.iter().any(|pat| comm.contains(pat.as_str()) || filename.contains(pat.as_str()))
+}
+
 pub struct LlmProbe;
 
-// Shared state
-type StreamMap = Arc<Mutex<HashMap<(u32, u32), StreamProcessor>>>;
+/// Events routed to a single connection's dedicated task. Both the TLS
+/// handshake (SNI/ALPN) and the subsequent read/write data land here so a
+/// connection's `StreamProcessor` is only ever touched from its own task,
+/// never shared behind a lock.
+enum ConnectionEvent {
+    Hello(TlsHelloInfo),
+    Data {
+        direction: LlmDirection,
+        bytes: Vec<u8>,
+        pid: u32,
+        cgroup_id: u64,
+    },
+}
+
+/// Per-connection senders, keyed by `(pid, ssl_handle)` - the `SSL *`
+/// pointer identifies the TLS connection itself, unlike tid, which a single
+/// thread can multiplex across many connections (e.g. a single-threaded
+/// async event loop). The ringbuf handler only ever holds this lock long
+/// enough to look up or insert a sender — the expensive state-machine/
+/// tokenization work happens in the owning task, off this hot path.
+type ConnectionSenders = Arc<Mutex<HashMap<(u32, u64), mpsc::Sender<ConnectionEvent>>>>;
 
 impl Probe for LlmProbe {
-    fn attach(&self, bpf: &mut Ebpf, _resolver: IdentityResolver) -> Result<()> {
+    fn attach(&self, bpf: &mut Ebpf, resolver: IdentityResolver) -> Result<()> {
         let targets = discovery::find_all_targets()?;
 
         if targets.is_empty() {
@@ -138,71 +225,153 @@ impl Probe for LlmProbe {
         }
 
         for path in &targets {
-            // Skip libcrypto for SSL_* probes as they usually don't contain them
-            if path.contains("libcrypto") {
-                info!("Skipping SSL probes for libcrypto: {}", path);
+            // Skip libcrypto and anything else that isn't a recognized
+            // TLS backend (it usually carries crypto primitives, not
+            // SSL_read/SSL_write or their GnuTLS/NSS equivalents).
+            let Some(backend) = TlsBackend::classify(path) else {
+                info!("Skipping unrecognized TLS library: {}", path);
                 continue;
-            }
+            };
 
-            info!("Attaching LLM (SSL) probes to detected library: {}", path);
-            if let Err(e) = attach_probes_to_path(bpf, path) {
+            info!("Attaching LLM ({}) probes to detected library: {}", backend.name(), path);
+            if let Err(e) = attach_backend_probes(bpf, backend, path) {
                 warn!("Failed to attach to {}: {}", path, e);
             }
         }
 
-        let state: StreamMap = Arc::new(Mutex::new(HashMap::new()));
-        let handler_state = state.clone();
+        let senders: ConnectionSenders = Arc::new(Mutex::new(HashMap::new()));
 
         spawn_ringbuf_handler(bpf, "SSL_EVENTS", move |event: LlmEvent| {
             let direction = LlmDirection::from(event.rw);
+            let key = (event.metadata.pid, event.ssl_handle);
+
             if event.is_handshake == 1 {
+                // ClientHello/ServerHello: no application data yet, but the
+                // SNI/ALPN extensions tell us the destination host and
+                // protocol before we'd otherwise have to guess from the
+                // first bytes of traffic.
+                if event.buf_filled != 0 && event.len != 0 {
+                    let data_len = std::cmp::min(event.len as usize, honeybeepf_common::MAX_SSL_BUF_SIZE);
+                    if let Some(hello) = tls::parse_hello(&event.buf[..data_len]) {
+                        route(&senders, &resolver, key, ConnectionEvent::Hello(hello));
+                    }
+                }
                 return;
             }
             if event.buf_filled == 0 || event.len == 0 {
                 return;
             }
 
-            // Resolve pod identity (result is used for future telemetry enrichment)
-            #[cfg(feature = "k8s")]
-            let pod_info = _resolver.resolve_pod(event.metadata.pid, event.metadata.cgroup_id);
-
-            let key = (event.metadata.pid, event.metadata._pad);
-            let mut map = handler_state.lock().unwrap_or_else(|e| e.into_inner());
-            let processor = map.entry(key).or_default();
-
             let data_len = std::cmp::min(event.len as usize, honeybeepf_common::MAX_SSL_BUF_SIZE);
-
-            #[cfg(feature = "k8s")]
-            processor.handle_event(
-                direction,
-                &event.buf[..data_len],
-                event.metadata.pid,
-                pod_info,
+            route(
+                &senders,
+                &resolver,
+                key,
+                ConnectionEvent::Data {
+                    direction,
+                    bytes: event.buf[..data_len].to_vec(),
+                    pid: event.metadata.pid,
+                    cgroup_id: event.metadata.cgroup_id,
+                },
             );
-
-            #[cfg(not(feature = "k8s"))]
-            processor.handle_event(direction, &event.buf[..data_len], event.metadata.pid);
         })?;
 
-        start_cleanup_task(state);
-
         Ok(())
     }
 }
 
-fn start_cleanup_task(state: StreamMap) {
-    let shutdown = crate::probes::shutdown_flag();
-    std::thread::spawn(move || {
-        while !shutdown.load(std::sync::atomic::Ordering::Relaxed) {
-            std::thread::sleep(Duration::from_secs(CLEANUP_INTERVAL_SECS));
-            let mut map = state.lock().unwrap_or_else(|e| e.into_inner());
-            let now = std::time::Instant::now();
+/// Sends `msg` to the task owning `key`'s connection, spawning a fresh task
+/// if none exists yet (or the existing one has already exited). This is the
+/// only part of the data path that touches the shared map, and the lock is
+/// held only long enough to look up/insert a sender — never across a send or
+/// any state-machine/tokenization work.
+fn route(senders: &ConnectionSenders, resolver: &IdentityResolver, key: (u32, u64), msg: ConnectionEvent) {
+    let mut map = senders.lock().unwrap_or_else(|e| e.into_inner());
+    let tx = match map.get(&key) {
+        Some(tx) if !tx.is_closed() => tx.clone(),
+        _ => {
+            let tx = spawn_connection_task(key, resolver.clone(), senders.clone());
+            map.insert(key, tx.clone());
+            tx
+        }
+    };
+    drop(map);
 
-            map.retain(|_, v| {
-                now.duration_since(v.last_activity()).as_secs() < CONNECTION_RETENTION_SECS
-            });
+    if let Err(e) = tx.try_send(msg) {
+        match e {
+            mpsc::error::TrySendError::Full(_) => {
+                crate::telemetry::record_ringbuf_drop("SSL_EVENTS");
+            }
+            mpsc::error::TrySendError::Closed(_) => {
+                // The task exited between the lookup above and this send
+                // (e.g. it hit its idle timeout). Drop the stale entry so
+                // the next event for this connection respawns a fresh task.
+                let mut map = senders.lock().unwrap_or_else(|e| e.into_inner());
+                if map.get(&key).map(|t| t.is_closed()).unwrap_or(false) {
+                    map.remove(&key);
+                }
+            }
         }
+    }
+}
+
+/// Spawns the dedicated task that owns this connection's `StreamProcessor`
+/// for its whole lifetime. The task drives the state machine and does all
+/// tokenization itself, entirely off the ringbuf handler's hot path, and
+/// exits (removing its own map entry) once `CONNECTION_RETENTION_SECS`
+/// passes with no new events — replacing the old periodic sweep with each
+/// connection managing its own lifetime.
+fn spawn_connection_task(
+    key: (u32, u64),
+    resolver: IdentityResolver,
+    senders: ConnectionSenders,
+) -> mpsc::Sender<ConnectionEvent> {
+    let (tx, mut rx) = mpsc::channel(CONNECTION_CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        let mut processor = StreamProcessor::new();
+        let extract_tokens = extract_tokens_enabled();
+        // Last PID seen on this connection, so a teardown with a response
+        // still in flight can still attribute its `finalize` log line.
+        let mut last_pid = 0u32;
+
+        loop {
+            let event = match tokio::time::timeout(
+                Duration::from_secs(CONNECTION_RETENTION_SECS),
+                rx.recv(),
+            )
+            .await
+            {
+                Ok(Some(event)) => event,
+                Ok(None) => break, // all senders dropped
+                Err(_) => break,   // idle timeout
+            };
+
+            match event {
+                ConnectionEvent::Hello(info) => processor.record_hello(info),
+                ConnectionEvent::Data { direction, bytes, pid, cgroup_id } => {
+                    // Resolved here, off the hot path; currently used only
+                    // for future telemetry enrichment.
+                    #[cfg(feature = "k8s")]
+                    let _pod_info = resolver.resolve_pod(pid, cgroup_id);
+                    #[cfg(not(feature = "k8s"))]
+                    let _ = (&resolver, cgroup_id);
+
+                    last_pid = pid;
+                    processor.handle_event(direction, &bytes, &TOKENIZER, pid, extract_tokens);
+                }
+            }
+        }
+
+        // A streaming response that was still in flight when the
+        // connection went idle or closed would otherwise be silently
+        // dropped; report a best-effort estimate of whatever arrived.
+        processor.finalize(last_pid, extract_tokens);
+
+        senders.lock().unwrap_or_else(|e| e.into_inner()).remove(&key);
     });
+
+    tx
 }
 
 fn attach_uprobe(bpf: &mut Ebpf, prog_name: &str, func_name: &str, path: &str) -> Result<()> {
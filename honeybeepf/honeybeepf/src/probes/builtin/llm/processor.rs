@@ -1,11 +1,12 @@
 use std::time::Instant;
 use tiktoken_rs::CoreBPE;
 use log::{info, warn};
-use crate::probes::builtin::llm::types::LlmDirection;
+use crate::probes::builtin::llm::buffer::SegmentedBuffer;
+use crate::probes::builtin::llm::types::{LlmDirection, UsageInfo};
 use crate::probes::builtin::llm::http::{self, ProtocolParser};
+use crate::probes::builtin::llm::tls::TlsHelloInfo;
 
 // Buffer size constants
-const INITIAL_BUFFER_CAPACITY: usize = 8 * 1024;         // 8KB initial allocation
 const MAX_REQUEST_BUFFER_SIZE: usize = 8 * 1024 * 1024;  // 8MB max for request (images, large context)
 const MAX_RESPONSE_BUFFER_SIZE: usize = 16 * 1024 * 1024; // 16MB max for response (streaming)
 const DETECTION_BUFFER_THRESHOLD: usize = 4096;           // Give up detection after 4KB
@@ -24,6 +25,10 @@ enum ProcessorState {
         start_time: Instant,
         parser: Box<dyn ProtocolParser>,
         est_input_tokens: u64,
+        /// How far into `read_buf` (in its stable `total_pushed` offset
+        /// space) bytes have already been handed to `parser.feed` and can
+        /// be released once a streaming response is confirmed.
+        parsed_offset: usize,
     },
     /// Finished or Invalid
     Finished, 
@@ -31,23 +36,44 @@ enum ProcessorState {
 
 pub struct StreamProcessor {
     state: ProcessorState,
-    write_buf: Vec<u8>,
-    read_buf: Vec<u8>,
-    last_activity: Instant,
+    write_buf: SegmentedBuffer,
+    read_buf: SegmentedBuffer,
+    /// SNI hostname from the TLS ClientHello, if the handshake was captured
+    /// and parsed before any application data arrived.
+    server_name: Option<String>,
+    /// Negotiated/offered ALPN protocol (`h2`, `http/1.1`, ...), used to
+    /// pick the right `ProtocolParser` up front instead of guessing from
+    /// the first request bytes.
+    alpn: Option<String>,
 }
 
 impl StreamProcessor {
     pub fn new() -> Self {
         Self {
             state: ProcessorState::Detecting,
-            write_buf: Vec::with_capacity(INITIAL_BUFFER_CAPACITY),
-            read_buf: Vec::with_capacity(INITIAL_BUFFER_CAPACITY),
-            last_activity: Instant::now(),
+            write_buf: SegmentedBuffer::new(),
+            read_buf: SegmentedBuffer::new(),
+            server_name: None,
+            alpn: None,
         }
     }
 
-    pub fn last_activity(&self) -> Instant {
-        self.last_activity
+    /// Records SNI/ALPN info extracted from a TLS handshake for this
+    /// connection. Safe to call multiple times (e.g. once for ClientHello,
+    /// once for ServerHello); later non-empty fields overwrite earlier ones.
+    pub fn record_hello(&mut self, info: TlsHelloInfo) {
+        if let Some(server_name) = info.server_name {
+            info!("[LLM] TLS SNI: {}", server_name);
+            self.server_name = Some(server_name);
+        }
+        if let Some(alpn) = info.alpn {
+            info!("[LLM] TLS ALPN: {}", alpn);
+            self.alpn = Some(alpn);
+        }
+    }
+
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
     }
 
     pub fn is_llm(&self) -> bool {
@@ -62,8 +88,6 @@ impl StreamProcessor {
     }
 
     pub fn handle_event(&mut self, direction: LlmDirection, data: &[u8], bpe: &CoreBPE, pid: u32, extract_tokens: bool) {
-        self.last_activity = Instant::now();
-
         // Early return if finished (except for new Write which triggers reset)
         if matches!(self.state, ProcessorState::Finished) {
             if direction == LlmDirection::Write {
@@ -82,7 +106,7 @@ impl StreamProcessor {
                      self.reset();
                      return;
                 }
-                self.write_buf.extend_from_slice(data);
+                self.write_buf.push(data);
             }
             LlmDirection::Read => {
                 if self.read_buf.len() + data.len() > MAX_RESPONSE_BUFFER_SIZE {
@@ -91,7 +115,7 @@ impl StreamProcessor {
                     self.reset();
                     return;
                 }
-                self.read_buf.extend_from_slice(data);
+                self.read_buf.push(data);
             }
             _ => return,
         }
@@ -104,23 +128,46 @@ impl StreamProcessor {
             
             // [State 1] Detecting
             ProcessorState::Detecting => {
-                // Try H1
-                if let Some(path) = http::Http11Parser.detect_request(&self.write_buf) {
-                    info!("[LLM] Detected HTTP/1.1: {} (PID: {})", path, pid);
-                    ProcessorState::ProcessingRequest {
-                        start_time: Instant::now(),
-                        parser: Box::new(http::Http11Parser),
-                    }
-                }
-                // Try H2
-                else if let Some(path) = http::Http2Parser.detect_request(&self.write_buf) {
-                    info!("[LLM] Detected HTTP/2: {} (PID: {})", path, pid);
+                // If the TLS handshake told us ALPN was "h2", try the H2
+                // parser first instead of always sniffing H1 first.
+                let try_h2_first = self.alpn.as_deref() == Some("h2");
+                // Detection gives up once the buffer passes
+                // DETECTION_BUFFER_THRESHOLD, so it never sees more than that
+                // plus one event's bytes; a contiguous copy here is cheap.
+                let write_buf = self.write_buf.to_contiguous();
+
+                let h1 = || http::Http11Parser::default().detect_request(&write_buf).map(|path| {
+                    (path, "HTTP/1.1", Box::new(http::Http11Parser::default()) as Box<dyn ProtocolParser>)
+                });
+                let h2 = || http::Http2Parser::default().detect_request(&write_buf).map(|path| {
+                    (path, "HTTP/2", Box::new(http::Http2Parser::default()) as Box<dyn ProtocolParser>)
+                });
+                // gRPC is HTTP/2 too, so it shares h2's ALPN priority, but
+                // its "application/grpc" content-type check is more
+                // specific than h2's JSON-key sniffing - try it first so a
+                // gRPC request doesn't get misdetected as a plain H2 one.
+                let grpc = || http::GrpcParser::default().detect_request(&write_buf).map(|path| {
+                    (path, "gRPC", Box::new(http::GrpcParser::default()) as Box<dyn ProtocolParser>)
+                });
+
+                let detected = if try_h2_first {
+                    grpc().or_else(h2).or_else(h1)
+                } else {
+                    h1().or_else(grpc).or_else(h2)
+                };
+
+                if let Some((path, proto_name, parser)) = detected {
+                    let host_suffix = self
+                        .server_name
+                        .as_deref()
+                        .map(|h| format!(" (SNI: {})", h))
+                        .unwrap_or_default();
+                    info!("[LLM] Detected {}: {} (PID: {}){}", proto_name, path, pid, host_suffix);
                     ProcessorState::ProcessingRequest {
                         start_time: Instant::now(),
-                        parser: Box::new(http::Http2Parser),
+                        parser,
                     }
-                }
-                else {
+                } else {
                     // Not detected yet
                     if self.write_buf.len() > DETECTION_BUFFER_THRESHOLD {
                         // Buffer too large and still not detected -> likely not LLM
@@ -135,14 +182,20 @@ impl StreamProcessor {
             ProcessorState::ProcessingRequest { start_time, parser } => {
                 if direction == LlmDirection::Read {
                     // Write finished (implied by Read starting), calculate input tokens
-                    let text = parser.extract_request_text(&self.write_buf);
+                    let text = parser.extract_request_text(&self.write_buf.to_contiguous());
                     let est_tokens = bpe.encode_with_special_tokens(&text).len() as u64;
 
+                    // The request body is no longer needed once its token
+                    // count is computed; free it instead of carrying it
+                    // for the rest of the connection's lifetime.
+                    self.write_buf.clear();
+
                     // Transition to Response phase
                     ProcessorState::ProcessingResponse {
                         start_time,
                         parser,
                         est_input_tokens: est_tokens,
+                        parsed_offset: 0,
                     }
                 } else {
                     // Still writing -> keep state
@@ -151,34 +204,41 @@ impl StreamProcessor {
             },
 
             // [State 3] Processing Response
-            ProcessorState::ProcessingResponse { start_time, parser, est_input_tokens } => {
-                // Try parsing response
-                if let Some(usage) = parser.parse_response(&self.read_buf) {
-                    let latency = start_time.elapsed();
-                    let model_str = usage.model.as_deref().unwrap_or("unknown");
-
-                    if !extract_tokens {
-                        // Latency-only mode: skip token details
-                        info!("LLM | PID: {} | Model: {} | Latency: {:.2}s",
-                              pid, model_str, latency.as_secs_f64());
-                    } else if usage.prompt_tokens == 0 && usage.completion_tokens == 0 {
-                         info!("LLM FAILED/ERROR | PID: {} | Model: {} | Latency: {:.2}s | Est. Input: {}",
-                               pid, model_str, latency.as_secs_f64(), est_input_tokens);
+            ProcessorState::ProcessingResponse { start_time, mut parser, est_input_tokens, mut parsed_offset } => {
+                // Streamed (SSE/chunked) responses may never present a
+                // complete, re-parseable buffer, and providers often only
+                // report `usage` (if at all) in the terminal chunk. Feed
+                // each new event's bytes incrementally so completion
+                // tokens are counted live instead of discovered only once
+                // `parse_response` below happens to succeed.
+                if direction == LlmDirection::Read {
+                    parser.feed(data, bpe);
+                }
+
+                // Once `feed` has confirmed this is a streaming response,
+                // it's already consuming bytes incrementally and doesn't
+                // need `read_buf` re-scanned from byte 0 on every event —
+                // skip that reparse and release what's been fed so far
+                // instead of holding the whole response in memory.
+                let usage = if parser.is_streaming() {
+                    parsed_offset = self.read_buf.total_pushed();
+                    self.read_buf.release_through(parsed_offset);
+
+                    if parser.streaming_done() {
+                        parser.streaming_usage_or_estimate()
                     } else {
-                         let thoughts_str = usage.thoughts_tokens
-                             .map(|t| format!(", Thoughts: {}", t))
-                             .unwrap_or_default();
-                         info!("LLM SUCCESS | PID: {} | Model: {} | Latency: {:.2}s | Tokens: {} (Prompt: {}, Compl: {}{}) | Est. Input: {}",
-                               pid, model_str, latency.as_secs_f64(),
-                               usage.prompt_tokens + usage.completion_tokens,
-                               usage.prompt_tokens, usage.completion_tokens,
-                               thoughts_str, est_input_tokens);
+                        None
                     }
+                } else {
+                    parser.parse_response(&self.read_buf.to_contiguous())
+                };
 
+                if let Some(usage) = usage {
+                    log_usage(&usage, pid, start_time.elapsed(), est_input_tokens, extract_tokens);
                     ProcessorState::Finished
                 } else {
                     // Incomplete -> keep state
-                    ProcessorState::ProcessingResponse { start_time, parser, est_input_tokens }
+                    ProcessorState::ProcessingResponse { start_time, parser, est_input_tokens, parsed_offset }
                 }
             },
 
@@ -188,9 +248,54 @@ impl StreamProcessor {
 
     }
 
+    /// Called when the owning connection task is giving up on this
+    /// connection (idle timeout, all senders dropped) with a response still
+    /// in flight. A streaming response that never reached its terminal
+    /// marker would otherwise vanish with nothing reported; log whatever
+    /// `streaming_usage_or_estimate` has accumulated instead, same as a
+    /// normal completion would, and mark it `Finished` so a later reset
+    /// doesn't double-report it.
+    pub fn finalize(&mut self, pid: u32, extract_tokens: bool) {
+        let ProcessorState::ProcessingResponse { start_time, parser, est_input_tokens, .. } =
+            std::mem::replace(&mut self.state, ProcessorState::Finished)
+        else {
+            return;
+        };
+
+        if let Some(usage) = parser.streaming_usage_or_estimate() {
+            log_usage(&usage, pid, start_time.elapsed(), est_input_tokens, extract_tokens);
+        }
+    }
+
     fn reset(&mut self) {
         self.state = ProcessorState::Detecting;
         self.write_buf.clear();
         self.read_buf.clear();
     }
 }
+
+/// Logs a completed (or best-effort estimated) response's usage, shared by
+/// the normal in-stream completion path and `StreamProcessor::finalize`'s
+/// teardown path.
+fn log_usage(usage: &UsageInfo, pid: u32, latency: std::time::Duration, est_input_tokens: u64, extract_tokens: bool) {
+    let model_str = usage.model.as_deref().unwrap_or("unknown");
+    let estimated_str = if usage.estimated { " (estimated)" } else { "" };
+
+    if !extract_tokens {
+        // Latency-only mode: skip token details
+        info!("LLM | PID: {} | Model: {} | Latency: {:.2}s",
+              pid, model_str, latency.as_secs_f64());
+    } else if usage.prompt_tokens == 0 && usage.completion_tokens == 0 {
+        info!("LLM FAILED/ERROR | PID: {} | Model: {} | Latency: {:.2}s | Est. Input: {}",
+              pid, model_str, latency.as_secs_f64(), est_input_tokens);
+    } else {
+        let thoughts_str = usage.thoughts_tokens
+            .map(|t| format!(", Thoughts: {}", t))
+            .unwrap_or_default();
+        info!("LLM SUCCESS{} | PID: {} | Model: {} | Latency: {:.2}s | Tokens: {} (Prompt: {}, Compl: {}{}) | Est. Input: {}",
+              estimated_str, pid, model_str, latency.as_secs_f64(),
+              usage.prompt_tokens + usage.completion_tokens,
+              usage.prompt_tokens, usage.completion_tokens,
+              thoughts_str, est_input_tokens);
+    }
+}
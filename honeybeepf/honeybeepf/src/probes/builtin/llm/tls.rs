@@ -0,0 +1,290 @@
+//! Minimal TLS ClientHello/ServerHello parser: extracts the SNI hostname and
+//! ALPN protocol from a captured handshake buffer so an LLM connection can
+//! be tagged with its destination host and negotiated protocol before any
+//! application data arrives. Captured bytes may either be a raw handshake
+//! message or a full TLS record (content type `0x16`); see RFC 8446
+//! §4.1.2/§4.1.3, RFC 6066 §3 (SNI), and RFC 7301 (ALPN) for the wire
+//! formats this mirrors. Bails out cleanly (returns `None`) on any length
+//! that would run past the buffer, since captured handshakes are routinely
+//! truncated by the eBPF-side buffer cap.
+
+const CONTENT_TYPE_HANDSHAKE: u8 = 0x16;
+const HANDSHAKE_TYPE_CLIENT_HELLO: u8 = 0x01;
+const HANDSHAKE_TYPE_SERVER_HELLO: u8 = 0x02;
+const EXT_SERVER_NAME: u16 = 0x0000;
+const EXT_ALPN: u16 = 0x0010;
+
+/// Server name and negotiated/offered ALPN protocol extracted from a
+/// handshake. Either field may be absent: a ClientHello may omit SNI, and
+/// not every peer offers ALPN.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TlsHelloInfo {
+    pub server_name: Option<String>,
+    pub alpn: Option<String>,
+}
+
+/// Parses a captured handshake buffer (ClientHello or ServerHello, with or
+/// without the enclosing TLS record) into whatever SNI/ALPN info it
+/// carries. Returns `None` if the buffer isn't a recognizable, complete
+/// enough handshake to extract anything from.
+pub fn parse_hello(buf: &[u8]) -> Option<TlsHelloInfo> {
+    let handshake = unwrap_record(buf);
+    parse_handshake(handshake)
+}
+
+/// Strips the TLS record header (`content_type(1) + version(2) + length(2)`)
+/// if present; otherwise assumes `buf` is already a bare handshake message.
+fn unwrap_record(buf: &[u8]) -> &[u8] {
+    if buf.len() >= 5 && buf[0] == CONTENT_TYPE_HANDSHAKE {
+        let record_len = u16::from_be_bytes([buf[3], buf[4]]) as usize;
+        buf.get(5..5 + record_len).unwrap_or(&buf[5..])
+    } else {
+        buf
+    }
+}
+
+fn parse_handshake(buf: &[u8]) -> Option<TlsHelloInfo> {
+    if buf.len() < 4 {
+        return None;
+    }
+    let handshake_type = buf[0];
+    let len = u32::from_be_bytes([0, buf[1], buf[2], buf[3]]) as usize;
+    let body = buf.get(4..4 + len)?;
+
+    match handshake_type {
+        HANDSHAKE_TYPE_CLIENT_HELLO => parse_client_hello_body(body),
+        HANDSHAKE_TYPE_SERVER_HELLO => parse_server_hello_body(body),
+        _ => None,
+    }
+}
+
+fn parse_client_hello_body(body: &[u8]) -> Option<TlsHelloInfo> {
+    // version(2) + random(32)
+    let mut pos = 34usize;
+    if pos > body.len() {
+        return None;
+    }
+
+    // session_id: 1-byte length prefix
+    let session_id_len = *body.get(pos)? as usize;
+    pos = pos.checked_add(1 + session_id_len)?;
+
+    // cipher_suites: 2-byte length prefix (a list, unlike ServerHello)
+    let cipher_len = read_u16(body, pos)? as usize;
+    pos = pos.checked_add(2 + cipher_len)?;
+
+    // compression_methods: 1-byte length prefix (a list)
+    let comp_len = *body.get(pos)? as usize;
+    pos = pos.checked_add(1 + comp_len)?;
+
+    parse_extensions(body, pos).map(|(server_name, alpn)| TlsHelloInfo { server_name, alpn })
+}
+
+fn parse_server_hello_body(body: &[u8]) -> Option<TlsHelloInfo> {
+    // version(2) + random(32)
+    let mut pos = 34usize;
+    if pos > body.len() {
+        return None;
+    }
+
+    let session_id_len = *body.get(pos)? as usize;
+    pos = pos.checked_add(1 + session_id_len)?;
+
+    // cipher_suite: single 2-byte value, not a length-prefixed list
+    pos = pos.checked_add(2)?;
+    // compression_method: single byte, not a length-prefixed list
+    pos = pos.checked_add(1)?;
+
+    parse_extensions(body, pos).map(|(_server_name, alpn)| TlsHelloInfo {
+        server_name: None,
+        alpn,
+    })
+}
+
+/// Walks the `extensions` block starting at `pos` (2-byte total-length
+/// prefix, then a sequence of `type(2) + length(2) + data` entries),
+/// returning whatever SNI/ALPN extensions it finds.
+fn parse_extensions(body: &[u8], pos: usize) -> Option<(Option<String>, Option<String>)> {
+    if pos.checked_add(2)? > body.len() {
+        return None;
+    }
+    let ext_total_len = read_u16(body, pos)? as usize;
+    let mut cursor = pos + 2;
+    let ext_end = cursor.checked_add(ext_total_len)?;
+    if ext_end > body.len() {
+        return None;
+    }
+
+    let mut server_name = None;
+    let mut alpn = None;
+    while cursor + 4 <= ext_end {
+        let ext_type = read_u16(body, cursor)?;
+        let ext_len = read_u16(body, cursor + 2)? as usize;
+        cursor += 4;
+        if cursor + ext_len > ext_end {
+            break;
+        }
+        let ext_data = &body[cursor..cursor + ext_len];
+
+        match ext_type {
+            EXT_SERVER_NAME => server_name = parse_sni(ext_data),
+            EXT_ALPN => alpn = parse_alpn(ext_data).and_then(|protocols| protocols.into_iter().next()),
+            _ => {}
+        }
+        cursor += ext_len;
+    }
+
+    Some((server_name, alpn))
+}
+
+/// `server_name` extension body: a 2-byte list length, then entries of
+/// `name_type(1) + name_len(2) + name`. Only `name_type == 0` (host_name)
+/// is defined; returns the first one found.
+fn parse_sni(ext_data: &[u8]) -> Option<String> {
+    let list_len = read_u16(ext_data, 0)? as usize;
+    let list = ext_data.get(2..2 + list_len)?;
+
+    let mut pos = 0;
+    while pos + 3 <= list.len() {
+        let name_type = list[pos];
+        let name_len = read_u16(list, pos + 1)? as usize;
+        pos += 3;
+        if pos + name_len > list.len() {
+            break;
+        }
+        if name_type == 0 {
+            return std::str::from_utf8(&list[pos..pos + name_len])
+                .ok()
+                .map(String::from);
+        }
+        pos += name_len;
+    }
+    None
+}
+
+/// ALPN `protocol_name_list` extension body: a 2-byte list length, then a
+/// run of 1-byte-length-prefixed protocol name strings.
+fn parse_alpn(ext_data: &[u8]) -> Option<Vec<String>> {
+    let list_len = read_u16(ext_data, 0)? as usize;
+    let list = ext_data.get(2..2 + list_len)?;
+
+    let mut protocols = Vec::new();
+    let mut pos = 0;
+    while pos < list.len() {
+        let proto_len = *list.get(pos)? as usize;
+        pos += 1;
+        if pos + proto_len > list.len() {
+            break;
+        }
+        if let Ok(proto) = std::str::from_utf8(&list[pos..pos + proto_len]) {
+            protocols.push(proto.to_string());
+        }
+        pos += proto_len;
+    }
+    if protocols.is_empty() { None } else { Some(protocols) }
+}
+
+fn read_u16(buf: &[u8], pos: usize) -> Option<u16> {
+    buf.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal ClientHello body (post handshake-type+length header)
+    /// with an SNI extension for `host` and an ALPN extension offering
+    /// `protocols`, for feeding through `parse_client_hello_body`.
+    fn client_hello_body(host: &str, protocols: &[&str]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&[0u8; 34]); // version + random
+        body.push(0); // session_id length 0
+        body.extend_from_slice(&2u16.to_be_bytes()); // cipher_suites length
+        body.extend_from_slice(&[0x13, 0x01]); // one cipher suite
+        body.push(0); // compression_methods length 0
+
+        let mut extensions = Vec::new();
+
+        // server_name extension
+        let mut sni_list = Vec::new();
+        sni_list.push(0u8); // name_type: host_name
+        sni_list.extend_from_slice(&(host.len() as u16).to_be_bytes());
+        sni_list.extend_from_slice(host.as_bytes());
+        let mut sni_ext_data = Vec::new();
+        sni_ext_data.extend_from_slice(&(sni_list.len() as u16).to_be_bytes());
+        sni_ext_data.extend_from_slice(&sni_list);
+        extensions.extend_from_slice(&EXT_SERVER_NAME.to_be_bytes());
+        extensions.extend_from_slice(&(sni_ext_data.len() as u16).to_be_bytes());
+        extensions.extend_from_slice(&sni_ext_data);
+
+        // alpn extension
+        let mut alpn_list = Vec::new();
+        for proto in protocols {
+            alpn_list.push(proto.len() as u8);
+            alpn_list.extend_from_slice(proto.as_bytes());
+        }
+        let mut alpn_ext_data = Vec::new();
+        alpn_ext_data.extend_from_slice(&(alpn_list.len() as u16).to_be_bytes());
+        alpn_ext_data.extend_from_slice(&alpn_list);
+        extensions.extend_from_slice(&EXT_ALPN.to_be_bytes());
+        extensions.extend_from_slice(&(alpn_ext_data.len() as u16).to_be_bytes());
+        extensions.extend_from_slice(&alpn_ext_data);
+
+        body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        body.extend_from_slice(&extensions);
+        body
+    }
+
+    fn wrap_handshake(handshake_type: u8, body: &[u8]) -> Vec<u8> {
+        let mut msg = vec![handshake_type];
+        let len = body.len() as u32;
+        msg.extend_from_slice(&len.to_be_bytes()[1..]); // 3-byte length
+        msg.extend_from_slice(body);
+        msg
+    }
+
+    #[test]
+    fn extracts_sni_and_alpn_from_client_hello() {
+        let body = client_hello_body("api.openai.com", &["h2", "http/1.1"]);
+        let msg = wrap_handshake(HANDSHAKE_TYPE_CLIENT_HELLO, &body);
+
+        let info = parse_hello(&msg).expect("should parse");
+        assert_eq!(info.server_name.as_deref(), Some("api.openai.com"));
+        assert_eq!(info.alpn.as_deref(), Some("h2"));
+    }
+
+    #[test]
+    fn unwraps_tls_record_before_parsing() {
+        let body = client_hello_body("generativelanguage.googleapis.com", &["http/1.1"]);
+        let handshake = wrap_handshake(HANDSHAKE_TYPE_CLIENT_HELLO, &body);
+
+        let mut record = vec![CONTENT_TYPE_HANDSHAKE, 0x03, 0x03];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(&handshake);
+
+        let info = parse_hello(&record).expect("should parse");
+        assert_eq!(
+            info.server_name.as_deref(),
+            Some("generativelanguage.googleapis.com")
+        );
+        assert_eq!(info.alpn.as_deref(), Some("http/1.1"));
+    }
+
+    #[test]
+    fn returns_none_on_truncated_handshake() {
+        let body = client_hello_body("api.openai.com", &["h2"]);
+        let msg = wrap_handshake(HANDSHAKE_TYPE_CLIENT_HELLO, &body);
+        // Chop off the last 10 bytes so the extensions block runs past the buffer.
+        let truncated = &msg[..msg.len() - 10];
+
+        assert_eq!(parse_hello(truncated), None);
+    }
+
+    #[test]
+    fn returns_none_for_unrecognized_handshake_type() {
+        let body = client_hello_body("api.openai.com", &["h2"]);
+        // HelloRetryRequest / other type, not Client/ServerHello.
+        let msg = wrap_handshake(0x06, &body);
+        assert_eq!(parse_hello(&msg), None);
+    }
+}
@@ -3,11 +3,23 @@ use serde_json::Value;
 pub use honeybeepf_common::LlmDirection;
 
 /// Parsed usage info from an LLM response
+#[derive(Clone, Default)]
 pub struct UsageInfo {
     pub prompt_tokens: u64,
     pub completion_tokens: u64,
     pub thoughts_tokens: Option<u64>,
+    /// Tokens spent on `tools`/`functions` schema definitions and any
+    /// `tool_calls`/`functionCall` arguments, for providers that break this
+    /// out of the usual prompt/completion split.
+    pub tool_tokens: Option<u64>,
     pub model: Option<String>,
+    /// `true` when `completion_tokens` came from tokenizing accumulated
+    /// streaming deltas rather than a provider-reported `usage` object -
+    /// e.g. a provider that never sends `usage` unless a streaming request
+    /// opts in with `stream_options.include_usage`, or a stream that never
+    /// reached its terminal `[DONE]` (or zero-size final chunk) before the
+    /// connection went idle. See `StreamDecoder::usage_or_estimate`.
+    pub estimated: bool,
 }
 
 /// Lightweight struct for SSE chunk detection (only checks if usage field exists)
@@ -15,3 +27,23 @@ pub struct UsageInfo {
 pub struct SseChunkDelta {
     pub usage: Option<Value>,
 }
+
+/// OpenAI-style streaming delta chunk (`choices[].delta.content`), used to
+/// tokenize completion text as it streams in rather than waiting for the
+/// response to finish.
+#[derive(Deserialize, Default)]
+pub struct SseDeltaChunk {
+    #[serde(default)]
+    pub choices: Vec<SseChoiceDelta>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct SseChoiceDelta {
+    #[serde(default)]
+    pub delta: SseDelta,
+}
+
+#[derive(Deserialize, Default)]
+pub struct SseDelta {
+    pub content: Option<String>,
+}
@@ -6,17 +6,23 @@
 //! ├── network/      # TCP, DNS, retransmission tracking
 //! ├── filesystem/   # VFS latency, file access auditing
 //! ├── scheduler/    # Runqueue latency, off-CPU analysis
-//! └── gpu/          # (deprecated) Use NVIDIA tools instead
+//! ├── gpu/          # NCCL communicator tracking
+//! └── gpu_*.rs      # GPU device open/close, memory, utilization
 //! ```
 
 pub mod filesystem;
+pub mod gpu;
+pub mod gpu_mem;
+pub mod gpu_usage;
+pub mod gpu_util;
 pub mod network;
 pub mod scheduler;
 
-// GPU module disabled - use NVIDIA tools (DCGM, nsys) instead
-// pub mod gpu;
-
 // Re-export all probes for convenience
 pub use filesystem::{FileAccessProbe, VfsLatencyProbe};
+pub use gpu::NcclCommProbe;
+pub use gpu_mem::GpuMemProbe;
+pub use gpu_usage::GpuUsageProbe;
+pub use gpu_util::GpuUtilProbe;
 pub use network::{DnsProbe, TcpConnectProbe, TcpRetransProbe};
 pub use scheduler::{OffCpuProbe, RunqueueLatencyProbe};
@@ -1,4 +1,4 @@
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use anyhow::Result;
 use aya::Ebpf;
@@ -7,6 +7,24 @@ use log::info;
 
 use crate::probes::{IdentityResolver, Probe, TracepointConfig, attach_tracepoint, spawn_ringbuf_handler};
 
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+
+/// Reconstructs the destination IP from `ConnectionEvent::dest_addr` given
+/// its `address_family` - see that field's doc comment for the `AF_INET` vs
+/// `AF_INET6` byte layout. Returns `None` for any other family (e.g. Unix
+/// sockets, which don't carry an IP destination at all).
+fn dest_ip(event: &ConnectionEvent) -> Option<IpAddr> {
+    match event.address_family {
+        AF_INET => {
+            let octets: [u8; 4] = event.dest_addr[..4].try_into().ok()?;
+            Some(IpAddr::V4(Ipv4Addr::from(octets)))
+        }
+        AF_INET6 => Some(IpAddr::V6(Ipv6Addr::from(event.dest_addr))),
+        _ => None,
+    }
+}
+
 pub struct NetworkLatencyProbe;
 
 impl Probe for NetworkLatencyProbe {
@@ -15,31 +33,44 @@ impl Probe for NetworkLatencyProbe {
         attach_tracepoint(
             bpf,
             TracepointConfig {
-                program_name: "honeybeepf",
+                program_name: "connect_enter",
                 category: "syscalls",
                 name: "sys_enter_connect",
             },
         )?;
+        attach_tracepoint(
+            bpf,
+            TracepointConfig {
+                program_name: "connect_exit",
+                category: "syscalls",
+                name: "sys_exit_connect",
+            },
+        )?;
 
         spawn_ringbuf_handler(bpf, "NETWORK_EVENTS", move |event: ConnectionEvent| {
-            let dest_ip = Ipv4Addr::from(u32::from_be(event.dest_addr));
             let dest_port = u16::from_be(event.dest_port);
 
-            let _pod_info = resolver.resolve_pod(event.metadata.pid, event.metadata.cgroup_id);
+            let _pod_info = resolver.resolve_pod(event.pid, event.cgroup_id);
 
             // Read process name from /proc since ConnectionEvent doesn't have comm field
-            let comm = std::fs::read_to_string(format!("/proc/{}/comm", event.metadata.pid))
+            let comm = std::fs::read_to_string(format!("/proc/{}/comm", event.pid))
                 .unwrap_or_default();
             let comm = comm.trim();
 
+            let dest = dest_ip(&event)
+                .map(|ip| ip.to_string())
+                .unwrap_or_else(|| format!("<unknown family {}>", event.address_family));
+
             info!(
-                "PID {} ({}) connecting to {}:{} (cgroup_id={}, ts={})",
-                event.metadata.pid,
+                "PID {} ({}) connecting to {}:{} (cgroup_id={}, latency={}us, ret={}, in_progress={})",
+                event.pid,
                 comm,
-                dest_ip,
+                dest,
                 dest_port,
-                event.metadata.cgroup_id,
-                event.metadata.timestamp
+                event.cgroup_id,
+                event.latency_ns / 1000,
+                event.ret_code,
+                event.in_progress != 0,
             );
         })?;
 
@@ -1,12 +1,13 @@
 use anyhow::{Context, Result};
 use aya::Ebpf;
-use aya::maps::RingBuf;
+use aya::maps::{HashMap, RingBuf};
 use aya::programs::KProbe;
+use honeybeepf_common::CgroupAggBucket;
 use log::info;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use crate::probes::Probe;
+use crate::probes::{Probe, spawn_agg_flush_task, spawn_histogram_handler};
 use crate::telemetry;
 
 /// TCP connection event data
@@ -27,12 +28,23 @@ pub struct TcpConnectEvent {
 
 pub struct TcpConnectProbe {
     running: Arc<AtomicBool>,
+    /// When true, aggregate per-cgroup connect stats in-kernel instead of
+    /// streaming one event per call - see `VfsLatencyProbe::aggregate` for
+    /// the same idea applied to VFS.
+    pub aggregate: bool,
+    /// When true, skip both per-event streaming and `TCP_CONNECT_AGG` and
+    /// only bump the in-kernel `TCP_CONNECT_HIST` log2 latency histogram -
+    /// see `VfsLatencyProbe::histogram`. Takes priority over `aggregate`
+    /// when both are set.
+    pub histogram: bool,
 }
 
 impl Default for TcpConnectProbe {
     fn default() -> Self {
         Self {
             running: Arc::new(AtomicBool::new(true)),
+            aggregate: false,
+            histogram: false,
         }
     }
 }
@@ -67,17 +79,86 @@ impl Probe for TcpConnectProbe {
 
         info!("Attached kretprobe: tcp_v4_connect (exit)");
 
-        // Spawn event handler
-        self.spawn_event_handler(bpf)?;
+        self.set_aggregate_mode(bpf)?;
+        self.set_histogram_mode(bpf)?;
+
+        if self.histogram {
+            self.spawn_histogram_flush(bpf)?;
+        } else if self.aggregate {
+            self.spawn_agg_flush(bpf)?;
+        } else {
+            self.spawn_event_handler(bpf)?;
+        }
 
         telemetry::record_active_probe("tcp_connect", 1);
-        info!("TcpConnectProbe attached successfully");
+        info!(
+            "TcpConnectProbe attached successfully (aggregate={}, histogram={})",
+            self.aggregate, self.histogram
+        );
 
         Ok(())
     }
 }
 
 impl TcpConnectProbe {
+    fn set_aggregate_mode(&self, bpf: &mut Ebpf) -> Result<()> {
+        let mut mode_map: HashMap<_, u32, u8> = bpf
+            .map_mut("TCP_CONNECT_AGGREGATE_MODE")
+            .context("Failed to find TCP_CONNECT_AGGREGATE_MODE map")?
+            .try_into()
+            .context("TCP_CONNECT_AGGREGATE_MODE is not a HashMap")?;
+
+        mode_map.insert(0, self.aggregate as u8, 0)?;
+
+        Ok(())
+    }
+
+    fn set_histogram_mode(&self, bpf: &mut Ebpf) -> Result<()> {
+        let mut mode_map: HashMap<_, u32, u8> = bpf
+            .map_mut("TCP_CONNECT_HISTOGRAM_MODE")
+            .context("Failed to find TCP_CONNECT_HISTOGRAM_MODE map")?
+            .try_into()
+            .context("TCP_CONNECT_HISTOGRAM_MODE is not a HashMap")?;
+
+        mode_map.insert(0, self.histogram as u8, 0)?;
+
+        Ok(())
+    }
+
+    /// Histogram-mode equivalent of `spawn_event_handler`: periodically
+    /// snapshots `TCP_CONNECT_HIST` and exports each bucket's delta as a
+    /// cumulative `le`-bucketed counter. TCP connect has only one
+    /// "operation", so `op_type` is always 0.
+    fn spawn_histogram_flush(&self, bpf: &mut Ebpf) -> Result<()> {
+        spawn_histogram_handler(bpf, "TCP_CONNECT_HIST", 1, |_op_type, bucket, delta| {
+            telemetry::record_histogram_bucket("tcp_connect", "connect", bucket, delta);
+        })
+    }
+
+    /// Aggregated-mode equivalent of `spawn_event_handler`: drains and
+    /// cgroup-rolls-up `TCP_CONNECT_AGG` on a timer instead of streaming one
+    /// event per connect call.
+    fn spawn_agg_flush(&self, bpf: &mut Ebpf) -> Result<()> {
+        spawn_agg_flush_task(
+            bpf,
+            "TCP_CONNECT_AGG",
+            |cgroup_id: u64, bucket: CgroupAggBucket| {
+                let avg_latency_ns = bucket
+                    .sum_latency_ns
+                    .checked_div(bucket.count)
+                    .unwrap_or(0);
+
+                info!(
+                    "TCP_CONNECT_AGG cgroup={} count={} avg_latency={}µs max_latency={}µs",
+                    cgroup_id,
+                    bucket.count,
+                    avg_latency_ns / 1000,
+                    bucket.max_latency_ns / 1000,
+                );
+            },
+        )
+    }
+
     fn spawn_event_handler(&self, bpf: &mut Ebpf) -> Result<()> {
         let ring_buf = RingBuf::try_from(
             bpf.take_map("TCP_CONNECT_EVENTS")
@@ -119,6 +200,7 @@ impl TcpConnectProbe {
 
                         // Send metrics
                         telemetry::record_tcp_connect_event(
+                            event.pid,
                             &daddr,
                             event.dport,
                             event.latency_ns,
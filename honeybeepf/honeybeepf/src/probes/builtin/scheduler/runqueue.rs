@@ -13,43 +13,31 @@
 
 use anyhow::{Context, Result};
 use aya::Ebpf;
-use aya::maps::RingBuf;
+use aya::maps::HashMap;
 use aya::programs::TracePoint;
+use honeybeepf_common::RunqueueEvent;
 use log::info;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
 
-use crate::probes::Probe;
+use crate::probes::{Probe, spawn_ringbuf_handler};
 use crate::telemetry;
 
-/// Run queue latency event
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
-pub struct RunqueueEvent {
-    pub pid: u32,
-    pub tid: u32,
-    pub cpu: u32,
-    pub latency_ns: u64,
-    pub cgroup_id: u64,
-    pub comm: [u8; 16],
-}
-
 pub struct RunqueueLatencyProbe {
     pub threshold_ns: u64,
-    running: Arc<AtomicBool>,
 }
 
 impl Default for RunqueueLatencyProbe {
     fn default() -> Self {
         Self {
             threshold_ns: 10_000_000, // 10ms
-            running: Arc::new(AtomicBool::new(true)),
         }
     }
 }
 
 impl Probe for RunqueueLatencyProbe {
     fn attach(&self, bpf: &mut Ebpf) -> Result<()> {
+        // Set threshold in eBPF map
+        self.set_threshold(bpf)?;
+
         // Attach to sched_wakeup
         let wakeup: &mut TracePoint = bpf
             .program_mut("sched_wakeup")
@@ -59,6 +47,15 @@ impl Probe for RunqueueLatencyProbe {
         wakeup.attach("sched", "sched_wakeup")?;
         info!("Attached tracepoint: sched/sched_wakeup");
 
+        // Attach to sched_wakeup_new (first wakeup of a freshly forked task)
+        let wakeup_new: &mut TracePoint = bpf
+            .program_mut("sched_wakeup_new")
+            .context("Failed to find sched_wakeup_new program")?
+            .try_into()?;
+        wakeup_new.load()?;
+        wakeup_new.attach("sched", "sched_wakeup_new")?;
+        info!("Attached tracepoint: sched/sched_wakeup_new");
+
         // Attach to sched_switch
         let switch: &mut TracePoint = bpf
             .program_mut("sched_switch")
@@ -81,49 +78,41 @@ impl Probe for RunqueueLatencyProbe {
 }
 
 impl RunqueueLatencyProbe {
-    fn spawn_event_handler(&self, bpf: &mut Ebpf) -> Result<()> {
-        let ring_buf = RingBuf::try_from(
-            bpf.map_mut("RUNQUEUE_EVENTS")
-                .context("Failed to find RUNQUEUE_EVENTS map")?,
-        )?;
-
-        let running = self.running.clone();
-
-        std::thread::spawn(move || {
-            let mut ring_buf = ring_buf;
-
-            while running.load(Ordering::Relaxed) {
-                if let Some(item) = ring_buf.next() {
-                    if item.len() >= std::mem::size_of::<RunqueueEvent>() {
-                        let event: RunqueueEvent = unsafe {
-                            std::ptr::read_unaligned(item.as_ptr() as *const RunqueueEvent)
-                        };
-
-                        let comm = std::str::from_utf8(&event.comm)
-                            .unwrap_or("<invalid>")
-                            .trim_matches(char::from(0));
-
-                        info!(
-                            "RUNQUEUE_LATENCY pid={} comm={} cpu={} latency={}ms cgroup={}",
-                            event.pid,
-                            comm,
-                            event.cpu,
-                            event.latency_ns / 1_000_000,
-                            event.cgroup_id,
-                        );
-
-                        telemetry::record_runqueue_latency(
-                            event.latency_ns,
-                            event.cpu,
-                            comm,
-                            event.cgroup_id,
-                        );
-                    }
-                }
-                std::thread::sleep(std::time::Duration::from_millis(10));
-            }
-        });
+    fn set_threshold(&self, bpf: &mut Ebpf) -> Result<()> {
+        let mut threshold_map: HashMap<_, u32, u64> = bpf
+            .map_mut("RUNQUEUE_THRESHOLD_NS")
+            .context("Failed to find RUNQUEUE_THRESHOLD_NS map")?
+            .try_into()
+            .context("RUNQUEUE_THRESHOLD_NS is not a HashMap")?;
+
+        threshold_map.insert(0, self.threshold_ns, 0)?;
+        info!("Set runqueue latency threshold to {}ns", self.threshold_ns);
 
         Ok(())
     }
+
+    fn spawn_event_handler(&self, bpf: &mut Ebpf) -> Result<()> {
+        spawn_ringbuf_handler(bpf, "RUNQUEUE_EVENTS", move |event: RunqueueEvent| {
+            let comm = std::str::from_utf8(&event.comm)
+                .unwrap_or("<invalid>")
+                .trim_matches(char::from(0));
+
+            info!(
+                "RUNQUEUE_LATENCY pid={} comm={} cpu={} prev_pid={} latency={}ms cgroup={}",
+                event.pid,
+                comm,
+                event.cpu,
+                event.prev_pid,
+                event.latency_ns / 1_000_000,
+                event.cgroup_id,
+            );
+
+            telemetry::record_runqueue_latency(
+                event.latency_ns,
+                event.cpu,
+                comm,
+                event.cgroup_id,
+            );
+        })
+    }
 }
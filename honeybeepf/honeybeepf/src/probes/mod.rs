@@ -1,6 +1,7 @@
 use std::{
     collections::VecDeque,
-    path::Path,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    path::{Path, PathBuf},
     sync::{
         Arc, Mutex,
         atomic::{AtomicBool, Ordering},
@@ -8,30 +9,59 @@ use std::{
     time::Duration,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use aya::{
     Ebpf,
-    maps::RingBuf,
+    maps::{Array as AyaArray, HashMap as AyaHashMap, RingBuf},
     programs::{TracePoint, UProbe},
 };
-use honeybeepf_common::ExecEvent;
+use honeybeepf_common::{CgroupAggBucket, ExecEvent, HIST_NUM_BUCKETS};
 use log::{info, warn};
 use tokio::sync::Notify;
 
 static SHUTDOWN: once_cell::sync::Lazy<Arc<AtomicBool>> =
     once_cell::sync::Lazy::new(|| Arc::new(AtomicBool::new(false)));
 
+/// Signaled alongside [`SHUTDOWN`] so threads blocked in `epoll_wait` (see
+/// [`spawn_ringbuf_handler`]) wake immediately instead of waiting out their
+/// poll timeout.
+static SHUTDOWN_EVENTFD: once_cell::sync::Lazy<Arc<OwnedFd>> = once_cell::sync::Lazy::new(|| {
+    let raw = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+    assert!(
+        raw >= 0,
+        "eventfd creation failed: {}",
+        std::io::Error::last_os_error()
+    );
+    Arc::new(unsafe { OwnedFd::from_raw_fd(raw) })
+});
+
 pub fn shutdown_flag() -> Arc<AtomicBool> {
     SHUTDOWN.clone()
 }
 
+/// An eventfd that becomes readable as soon as [`request_shutdown`] is
+/// called, for registering alongside a ring-buffer fd in an `epoll` instance.
+pub fn shutdown_eventfd() -> Arc<OwnedFd> {
+    SHUTDOWN_EVENTFD.clone()
+}
+
 pub fn request_shutdown() {
     SHUTDOWN.store(true, Ordering::Relaxed);
+
+    let one: u64 = 1;
+    unsafe {
+        libc::write(
+            SHUTDOWN_EVENTFD.as_raw_fd(),
+            &one as *const u64 as *const libc::c_void,
+            std::mem::size_of::<u64>(),
+        );
+    }
 }
 
 pub mod builtin;
 pub mod custom;
 pub mod discovery;
+pub mod process_listener;
 
 pub trait Probe {
     fn attach(&self, bpf: &mut Ebpf) -> Result<()>;
@@ -97,6 +127,71 @@ pub fn attach_tracepoint(bpf: &mut Ebpf, config: TracepointConfig) -> Result<boo
     Ok(true)
 }
 
+/// Tag stored in `epoll_event.u64` identifying which fd an `epoll_wait` wakeup
+/// came from; only used to distinguish the ring buffer from the shutdown
+/// eventfd, so their exact values don't matter beyond being distinct.
+const EPOLL_DATA_RINGBUF: u64 = 1;
+const EPOLL_DATA_SHUTDOWN: u64 = 2;
+
+/// Safety-net timeout for `epoll_wait`: normally every wakeup comes from the
+/// ring buffer becoming readable or the shutdown eventfd firing, but a small
+/// timeout keeps the loop responsive to `SHUTDOWN` even if a signal is
+/// somehow missed.
+const EPOLL_WAIT_TIMEOUT_MS: i32 = POLL_INTERVAL_MS as i32 * 100;
+
+/// Minimal `epoll` wrapper: register one or more fds for readability and
+/// block until any of them is ready.
+struct Epoll {
+    fd: OwnedFd,
+}
+
+impl Epoll {
+    fn new() -> std::io::Result<Self> {
+        let raw = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if raw < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(raw) },
+        })
+    }
+
+    fn add_readable(&self, fd: RawFd, data: u64) -> std::io::Result<()> {
+        let mut event = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: data,
+        };
+        let ret =
+            unsafe { libc::epoll_ctl(self.fd.as_raw_fd(), libc::EPOLL_CTL_ADD, fd, &mut event) };
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Waits for readability on any registered fd, or `timeout_ms`, whichever
+    /// comes first. Returns the number of ready events.
+    fn wait(&self, timeout_ms: i32) -> std::io::Result<usize> {
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; 8];
+        let n = unsafe {
+            libc::epoll_wait(
+                self.fd.as_raw_fd(),
+                events.as_mut_ptr(),
+                events.len() as i32,
+                timeout_ms,
+            )
+        };
+        if n < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(0);
+            }
+            return Err(err);
+        }
+        Ok(n as usize)
+    }
+}
+
 pub fn spawn_ringbuf_handler<T, F>(bpf: &mut Ebpf, map_name: &str, handler: F) -> Result<()>
 where
     T: Copy + Send + 'static,
@@ -104,28 +199,51 @@ where
 {
     let mut ring_buf = RingBuf::try_from(bpf.take_map(map_name).context("Failed to get map")?)?;
     let shutdown = shutdown_flag();
+    let shutdown_fd = shutdown_eventfd();
+    let map_name = map_name.to_string();
+
+    let epoll = Epoll::new().context("Failed to create epoll instance")?;
+    epoll
+        .add_readable(ring_buf.as_raw_fd(), EPOLL_DATA_RINGBUF)
+        .with_context(|| format!("Failed to register {} ring buffer with epoll", map_name))?;
+    epoll
+        .add_readable(shutdown_fd.as_raw_fd(), EPOLL_DATA_SHUTDOWN)
+        .context("Failed to register shutdown eventfd with epoll")?;
 
     tokio::task::spawn_blocking(move || {
         while !shutdown.load(Ordering::Relaxed) {
-            let mut has_work = false;
+            if let Err(e) = epoll.wait(EPOLL_WAIT_TIMEOUT_MS) {
+                warn!("epoll_wait failed for {} ring buffer: {}", map_name, e);
+                continue;
+            }
+
             while let Some(item) = ring_buf.next() {
-                has_work = true;
                 if item.len() >= std::mem::size_of::<T>() {
                     let event = unsafe { (item.as_ptr() as *const T).read_unaligned() };
                     handler(event);
+                } else {
+                    warn!(
+                        "Dropping truncated {} ring buffer item: {} bytes, expected {}",
+                        map_name,
+                        item.len(),
+                        std::mem::size_of::<T>()
+                    );
+                    crate::telemetry::record_ringbuf_drop(&map_name);
                 }
             }
-            if !has_work {
-                std::thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
-            }
         }
     });
     Ok(())
 }
 
-/// Attach a uprobe to a function in a shared library.
-/// Loads the program if not already loaded.
-pub fn attach_uprobe(bpf: &mut Ebpf, prog_name: &str, func_name: &str, path: &str) -> Result<()> {
+/// Attach a uprobe to a function in a shared library, trying each of
+/// `func_names` in order and accepting the first that attaches
+/// successfully - library builds frequently rename or version-suffix their
+/// exported symbols (e.g. an OpenSSL fork exporting `SSL_read` under a
+/// patched name), so a single hardcoded name can silently fail to attach.
+/// Loads the program if not already loaded. Returns an error listing every
+/// name tried if none of them attach.
+pub fn attach_uprobe(bpf: &mut Ebpf, prog_name: &str, func_names: &[&str], path: &str) -> Result<()> {
     let program: &mut UProbe = bpf
         .program_mut(prog_name)
         .with_context(|| format!("Failed to find program {}", prog_name))?
@@ -136,11 +254,58 @@ pub fn attach_uprobe(bpf: &mut Ebpf, prog_name: &str, func_name: &str, path: &st
         program.load()?;
     }
 
-    program
-        .attach(Some(func_name), 0, path, None)
-        .with_context(|| format!("Failed to attach {} to {}", prog_name, func_name))?;
+    for func_name in func_names {
+        if program.attach(Some(*func_name), 0, path, None).is_ok() {
+            return Ok(());
+        }
+    }
 
-    Ok(())
+    bail!(
+        "Failed to attach {} to any of [{}] in {}",
+        prog_name,
+        func_names.join(", "),
+        path
+    );
+}
+
+/// Resolves one of several candidate kernel symbol names against
+/// `/proc/kallsyms`, for kprobe targets whose on-disk name varies across
+/// kernel builds (compiler inlining/cloning commonly turns `vfs_read` into
+/// `vfs_read.constprop.0` or `vfs_read.isra.0`). Tries each candidate's
+/// exact name first (in order), then falls back to scanning for a
+/// `.constprop.`/`.isra.`-suffixed variant of each candidate, since the
+/// numeric suffix itself isn't predictable ahead of time. Returns the first
+/// match found, or an error listing every candidate tried.
+pub fn resolve_kernel_symbol(candidates: &[&str]) -> Result<String> {
+    let kallsyms =
+        std::fs::read_to_string("/proc/kallsyms").context("Failed to read /proc/kallsyms")?;
+
+    let names: std::collections::HashSet<&str> = kallsyms
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(2))
+        .collect();
+
+    for candidate in candidates {
+        if names.contains(candidate) {
+            return Ok((*candidate).to_string());
+        }
+    }
+
+    for candidate in candidates {
+        let constprop_prefix = format!("{}.constprop.", candidate);
+        let isra_prefix = format!("{}.isra.", candidate);
+        if let Some(found) = names
+            .iter()
+            .find(|name| name.starts_with(&constprop_prefix) || name.starts_with(&isra_prefix))
+        {
+            return Ok((*found).to_string());
+        }
+    }
+
+    bail!(
+        "No kernel symbol found for any candidate: [{}]",
+        candidates.join(", ")
+    );
 }
 
 /// Set up the `sched_process_exec` tracepoint and return a queue that collects
@@ -174,3 +339,159 @@ pub fn setup_exec_watch(bpf: &mut Ebpf) -> Result<(ExecPidQueue, ExecNotify)> {
     info!("Exec watch active: will trigger targeted re-discovery on new processes");
     Ok((queue, notify))
 }
+
+/// Drain interval for per-cgroup aggregation maps (see [`spawn_agg_flush_task`]).
+/// A plain periodic sweep rather than an epoll wakeup, since an eBPF hash map
+/// has no fd to block on.
+const AGG_FLUSH_INTERVAL_MS: u64 = 1000;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Finds the `/sys/fs/cgroup` subdirectory whose inode matches `cgroup_id`
+/// (the same identity `bpf_get_current_cgroup_id()` returns in-kernel), by
+/// walking the hierarchy.
+fn find_cgroup_dir(cgroup_id: u64) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    fn walk(dir: &Path, cgroup_id: u64) -> Option<PathBuf> {
+        if std::fs::metadata(dir).is_ok_and(|m| m.ino() == cgroup_id) {
+            return Some(dir.to_path_buf());
+        }
+        for entry in std::fs::read_dir(dir).ok()?.flatten() {
+            let path = entry.path();
+            if path.is_dir()
+                && let Some(found) = walk(&path, cgroup_id)
+            {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    walk(Path::new(CGROUP_ROOT), cgroup_id)
+}
+
+/// Returns `cgroup_id`'s ancestor cgroup ids, nearest parent first, up to
+/// (but not including) the cgroup2 root - so a parent cgroup's rolled-up
+/// bucket can be built as the sum of all descendants' buckets, mirroring the
+/// kernel's own rstat accounting.
+fn cgroup_ancestors(cgroup_id: u64) -> Vec<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    let Some(mut dir) = find_cgroup_dir(cgroup_id) else {
+        return Vec::new();
+    };
+    let root = Path::new(CGROUP_ROOT);
+
+    let mut ancestors = Vec::new();
+    while dir != root {
+        let Some(parent) = dir.parent() else { break };
+        dir = parent.to_path_buf();
+        if dir == root {
+            break;
+        }
+        match std::fs::metadata(&dir) {
+            Ok(meta) => ancestors.push(meta.ino()),
+            Err(_) => break,
+        }
+    }
+    ancestors
+}
+
+/// Spawns a background thread that periodically drains a per-cgroup
+/// aggregation map (see [`CgroupAggBucket`]), rolls each cgroup's totals up
+/// through its ancestors via [`cgroup_ancestors`], and hands every
+/// `(cgroup_id, bucket)` pair - leaves and rolled-up ancestors alike - to
+/// `sink`.
+pub fn spawn_agg_flush_task<F>(bpf: &mut Ebpf, map_name: &str, sink: F) -> Result<()>
+where
+    F: Fn(u64, CgroupAggBucket) + Send + 'static,
+{
+    let mut agg_map: AyaHashMap<_, u64, CgroupAggBucket> =
+        AyaHashMap::try_from(bpf.take_map(map_name).context("Failed to get map")?)?;
+    let shutdown = shutdown_flag();
+    let map_name = map_name.to_string();
+
+    std::thread::spawn(move || {
+        while !shutdown.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(AGG_FLUSH_INTERVAL_MS));
+
+            let keys: Vec<u64> = agg_map.keys().filter_map(Result::ok).collect();
+            let mut leaves = Vec::with_capacity(keys.len());
+            for cgroup_id in keys {
+                if let Ok(bucket) = agg_map.get(&cgroup_id, 0) {
+                    let _ = agg_map.remove(&cgroup_id);
+                    leaves.push((cgroup_id, bucket));
+                }
+            }
+            if leaves.is_empty() {
+                continue;
+            }
+
+            let mut rolled_up: std::collections::HashMap<u64, CgroupAggBucket> =
+                std::collections::HashMap::new();
+            for (cgroup_id, bucket) in &leaves {
+                for ancestor in cgroup_ancestors(*cgroup_id) {
+                    let entry = rolled_up.entry(ancestor).or_default();
+                    entry.count += bucket.count;
+                    entry.sum_latency_ns += bucket.sum_latency_ns;
+                    entry.max_latency_ns = entry.max_latency_ns.max(bucket.max_latency_ns);
+                    entry.sum_bytes += bucket.sum_bytes;
+                }
+            }
+
+            for (cgroup_id, bucket) in leaves.into_iter().chain(rolled_up) {
+                sink(cgroup_id, bucket);
+            }
+        }
+    });
+
+    info!("Aggregation flush task started for {}", map_name);
+    Ok(())
+}
+
+/// Spawns a background thread that periodically snapshots a log2 latency
+/// histogram `Array` map (flattened `[op_type][bucket]`, `num_op_types *
+/// HIST_NUM_BUCKETS` entries long - see `honeybeepf_common::latency_bucket`)
+/// and hands each bucket's *increase* since the last snapshot to `sink` as
+/// `(op_type, bucket, delta)`. Buckets are cumulative counters in the eBPF
+/// map itself (it's never reset), so the delta is what a monotonic OTel
+/// counter expects to `.add()` each tick rather than the raw running total.
+pub fn spawn_histogram_handler<F>(
+    bpf: &mut Ebpf,
+    map_name: &str,
+    num_op_types: usize,
+    sink: F,
+) -> Result<()>
+where
+    F: Fn(u8, usize, u64) + Send + 'static,
+{
+    let hist_map: AyaArray<_, u64> =
+        AyaArray::try_from(bpf.take_map(map_name).context("Failed to get map")?)?;
+    let shutdown = shutdown_flag();
+    let map_name = map_name.to_string();
+    let total_buckets = num_op_types * HIST_NUM_BUCKETS;
+
+    std::thread::spawn(move || {
+        let mut last = vec![0u64; total_buckets];
+
+        while !shutdown.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(AGG_FLUSH_INTERVAL_MS));
+
+            for (index, last_count) in last.iter_mut().enumerate() {
+                let count = hist_map.get(&(index as u32), 0).unwrap_or(0);
+                let delta = count.saturating_sub(*last_count);
+                *last_count = count;
+
+                if delta > 0 {
+                    let op_type = (index / HIST_NUM_BUCKETS) as u8;
+                    let bucket = index % HIST_NUM_BUCKETS;
+                    sink(op_type, bucket, delta);
+                }
+            }
+        }
+    });
+
+    info!("Histogram flush task started for {}", map_name);
+    Ok(())
+}
@@ -0,0 +1,264 @@
+//! Continuous process listener for incremental uprobe attachment.
+//!
+//! [`discovery::find_libraries_all`] and [`discovery::find_libraries_for_pids`]
+//! are one-shot scans: a uprobe target that launches after the initial scan
+//! is never instrumented unless something keeps watching for new processes.
+//! This module watches process lifecycle events and incrementally resolves
+//! libraries for newly-started PIDs, emitting the results over a channel so
+//! the probe-attachment layer can attach uprobes to binaries that start
+//! after startup.
+//!
+//! Prefers the netlink `PROC_CONNECTOR` (cn_proc) socket for near-instant
+//! EXEC/FORK/EXIT notifications; falls back to a periodic procfs diff (the
+//! PID set from `procfs::process::all_processes()` compared between ticks)
+//! when the connector is unavailable, e.g. missing `CAP_NET_ADMIN` or a
+//! kernel built without `CONFIG_PROC_EVENTS`.
+
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use regex::Regex;
+
+use super::discovery::find_libraries_for_pids;
+use super::shutdown_flag;
+
+const PROCFS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A process lifecycle notification emitted to the probe-attachment layer.
+pub enum ProcessEvent {
+    /// Newly resolved library paths for a PID that just forked or exec'd.
+    /// Paths have already been passed through [`discovery::resolve_host_path`]
+    /// (via `find_libraries_for_pids`), so containerized children resolve to
+    /// their host-visible path.
+    Discovered { pid: u32, paths: HashSet<String> },
+    /// The PID exited; the caller should prune any paths it had attributed
+    /// to this PID from its tracked set.
+    Exited { pid: u32 },
+}
+
+/// Watches for process lifecycle events and resolves libraries matching
+/// `pattern` for every newly-seen PID, emitting the results over `tx`.
+/// Runs until [`shutdown_flag`] is set, on a dedicated background thread.
+pub fn spawn_process_listener(pattern: Regex, tx: Sender<ProcessEvent>) {
+    std::thread::spawn(move || match cn_proc::Socket::connect() {
+        Ok(socket) => {
+            info!("Process listener: using netlink PROC_CONNECTOR (cn_proc)");
+            run_cn_proc_loop(socket, &pattern, &tx);
+        }
+        Err(e) => {
+            warn!(
+                "Process listener: PROC_CONNECTOR unavailable ({}), falling back to procfs polling",
+                e
+            );
+            run_procfs_poll_loop(&pattern, &tx);
+        }
+    });
+}
+
+fn resolve_and_send(pid: u32, pattern: &Regex, tx: &Sender<ProcessEvent>) {
+    match find_libraries_for_pids(&[pid], pattern) {
+        Ok(paths) if !paths.is_empty() => {
+            let _ = tx.send(ProcessEvent::Discovered { pid, paths });
+        }
+        Ok(_) => {}
+        Err(e) => debug!(
+            "Process listener: failed to resolve libraries for pid {}: {}",
+            pid, e
+        ),
+    }
+}
+
+fn run_cn_proc_loop(socket: cn_proc::Socket, pattern: &Regex, tx: &Sender<ProcessEvent>) {
+    let shutdown = shutdown_flag();
+    while !shutdown.load(Ordering::Relaxed) {
+        match socket.recv_event() {
+            Ok(cn_proc::ProcEvent::Fork { child_pid }) => resolve_and_send(child_pid, pattern, tx),
+            Ok(cn_proc::ProcEvent::Exec { pid }) => resolve_and_send(pid, pattern, tx),
+            Ok(cn_proc::ProcEvent::Exit { pid }) => {
+                let _ = tx.send(ProcessEvent::Exited { pid });
+            }
+            Ok(cn_proc::ProcEvent::Other) => {}
+            Err(e) => {
+                warn!(
+                    "Process listener: cn_proc socket error ({}), switching to procfs polling",
+                    e
+                );
+                return run_procfs_poll_loop(pattern, tx);
+            }
+        }
+    }
+}
+
+fn run_procfs_poll_loop(pattern: &Regex, tx: &Sender<ProcessEvent>) {
+    let shutdown = shutdown_flag();
+    let mut known = procfs_pids();
+
+    while !shutdown.load(Ordering::Relaxed) {
+        std::thread::sleep(PROCFS_POLL_INTERVAL);
+
+        let current = procfs_pids();
+
+        for &pid in current.difference(&known) {
+            resolve_and_send(pid, pattern, tx);
+        }
+        for &pid in known.difference(&current) {
+            let _ = tx.send(ProcessEvent::Exited { pid });
+        }
+
+        known = current;
+    }
+}
+
+fn procfs_pids() -> HashSet<u32> {
+    procfs::process::all_processes()
+        .map(|procs| procs.filter_map(|p| p.ok()).map(|p| p.pid as u32).collect())
+        .unwrap_or_default()
+}
+
+/// Minimal netlink `PROC_CONNECTOR` (cn_proc) client: enough to subscribe to
+/// the kernel's process-event multicast group and decode FORK/EXEC/EXIT
+/// notifications. See `linux/cn_proc.h` and `linux/connector.h` for the wire
+/// format this mirrors.
+mod cn_proc {
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    const NETLINK_CONNECTOR: i32 = 11;
+    const CN_IDX_PROC: u32 = 0x1;
+    const CN_VAL_PROC: u32 = 0x1;
+    const PROC_CN_MCAST_LISTEN: u32 = 1;
+
+    const PROC_EVENT_FORK: u32 = 0x0000_0001;
+    const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+    const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+
+    const NLMSGHDR_SIZE: usize = std::mem::size_of::<libc::nlmsghdr>();
+    /// `struct cn_msg` sans trailing data: cb_id{idx,val} + seq + ack + len + flags.
+    const CN_MSG_HDR_SIZE: usize = 20;
+    /// `struct proc_event` header sans its union: what + cpu + timestamp_ns.
+    const PROC_EVENT_HDR_SIZE: usize = 16;
+    const UNION_OFFSET: usize = NLMSGHDR_SIZE + CN_MSG_HDR_SIZE + PROC_EVENT_HDR_SIZE;
+
+    pub(super) enum ProcEvent {
+        Fork { child_pid: u32 },
+        Exec { pid: u32 },
+        Exit { pid: u32 },
+        Other,
+    }
+
+    pub(super) struct Socket {
+        fd: OwnedFd,
+    }
+
+    impl Socket {
+        pub(super) fn connect() -> io::Result<Self> {
+            let raw_fd = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM, NETLINK_CONNECTOR) };
+            if raw_fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let fd = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+
+            let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+            addr.nl_family = libc::AF_NETLINK as u16;
+            addr.nl_pid = 0;
+            addr.nl_groups = CN_IDX_PROC;
+
+            let ret = unsafe {
+                libc::bind(
+                    fd.as_raw_fd(),
+                    &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                    std::mem::size_of::<libc::sockaddr_nl>() as u32,
+                )
+            };
+            if ret < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let socket = Socket { fd };
+            socket.subscribe()?;
+            Ok(socket)
+        }
+
+        /// Sends the `PROC_CN_MCAST_LISTEN` control message that asks the
+        /// kernel connector driver to start delivering process events to us.
+        fn subscribe(&self) -> io::Result<()> {
+            let payload_len = CN_MSG_HDR_SIZE + std::mem::size_of::<u32>();
+            let total_len = NLMSGHDR_SIZE + payload_len;
+            let mut buf = vec![0u8; total_len];
+
+            let nlh = buf.as_mut_ptr() as *mut libc::nlmsghdr;
+            unsafe {
+                (*nlh).nlmsg_len = total_len as u32;
+                (*nlh).nlmsg_type = libc::NLMSG_DONE as u16;
+                (*nlh).nlmsg_flags = 0;
+                (*nlh).nlmsg_seq = 0;
+                (*nlh).nlmsg_pid = libc::getpid() as u32;
+            }
+
+            let cn = &mut buf[NLMSGHDR_SIZE..];
+            cn[0..4].copy_from_slice(&CN_IDX_PROC.to_ne_bytes());
+            cn[4..8].copy_from_slice(&CN_VAL_PROC.to_ne_bytes());
+            // seq (8..12), ack (12..16) left at zero
+            cn[16..18].copy_from_slice(&(std::mem::size_of::<u32>() as u16).to_ne_bytes());
+            // flags (18..20) left at zero
+            cn[20..24].copy_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+
+            let ret = unsafe {
+                libc::send(self.fd.as_raw_fd(), buf.as_ptr() as *const libc::c_void, buf.len(), 0)
+            };
+            if ret < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        pub(super) fn recv_event(&self) -> io::Result<ProcEvent> {
+            let mut buf = [0u8; 1024];
+            let n = unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if n < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(parse_proc_event(&buf[..n as usize]))
+        }
+    }
+
+    fn parse_proc_event(buf: &[u8]) -> ProcEvent {
+        let what_offset = NLMSGHDR_SIZE + CN_MSG_HDR_SIZE;
+        if buf.len() < what_offset + 4 {
+            return ProcEvent::Other;
+        }
+        let what = u32::from_ne_bytes(buf[what_offset..what_offset + 4].try_into().unwrap());
+
+        match what {
+            // fork_proc_event: parent_pid, parent_tgid, child_pid, child_tgid
+            PROC_EVENT_FORK if buf.len() >= UNION_OFFSET + 16 => {
+                let child_pid =
+                    i32::from_ne_bytes(buf[UNION_OFFSET + 8..UNION_OFFSET + 12].try_into().unwrap());
+                ProcEvent::Fork {
+                    child_pid: child_pid as u32,
+                }
+            }
+            // exec_proc_event: process_pid, process_tgid
+            PROC_EVENT_EXEC if buf.len() >= UNION_OFFSET + 8 => {
+                let pid = i32::from_ne_bytes(buf[UNION_OFFSET..UNION_OFFSET + 4].try_into().unwrap());
+                ProcEvent::Exec { pid: pid as u32 }
+            }
+            // exit_proc_event: process_pid, process_tgid, exit_code, exit_signal
+            PROC_EVENT_EXIT if buf.len() >= UNION_OFFSET + 8 => {
+                let pid = i32::from_ne_bytes(buf[UNION_OFFSET..UNION_OFFSET + 4].try_into().unwrap());
+                ProcEvent::Exit { pid: pid as u32 }
+            }
+            _ => ProcEvent::Other,
+        }
+    }
+}
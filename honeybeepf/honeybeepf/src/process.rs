@@ -0,0 +1,185 @@
+//! Per-PID process metadata enrichment, built on the `procfs` crate.
+//!
+//! Consolidates the scattered one-off `/proc` reads done elsewhere in this
+//! crate (e.g. `k8s::extract_container_id`'s hand-rolled `/proc/{pid}/cgroup`
+//! parsing, `NetworkLatencyProbe`'s manual `/proc/{pid}/comm` read) into a
+//! single per-PID lookup that resolves `comm`, `cmdline`, `ppid`, `uid`, and
+//! the container cgroup ID in one pass, with the result cached so repeated
+//! events from the same process don't re-read `/proc` each time.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use procfs::process::Process;
+
+/// Process metadata resolved from `/proc/{pid}`, cached per PID.
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub comm: String,
+    pub cmdline: Vec<String>,
+    pub ppid: i32,
+    pub uid: u32,
+    /// Short (12-char) container ID extracted from `/proc/{pid}/cgroup`, if
+    /// any - see `k8s::extract_container_id` for the recognized cgroup
+    /// formats.
+    pub container_id: Option<String>,
+}
+
+/// Thread-safe pid → `ProcessInfo` cache.
+///
+/// Designed to be wrapped in `Arc` and shared across probe handler closures
+/// (which run in `spawn_blocking` threads), mirroring `k8s::PodResolver`.
+pub struct ProcessResolver {
+    cache: RwLock<HashMap<u32, ProcessInfo>>,
+}
+
+impl Default for ProcessResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessResolver {
+    pub fn new() -> Self {
+        Self {
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Resolve a PID to process metadata, in one `/proc` pass.
+    ///
+    /// Returns the cached result in O(1) for already-seen PIDs. PIDs are
+    /// reused by the kernel, but a cache hit only serves the same process
+    /// that was observed when it was first resolved (see `evict`, called
+    /// once the process-lifecycle listener sees that PID exit), so a reused
+    /// PID gets a fresh `/proc` read the next time it's resolved.
+    pub fn resolve(&self, pid: u32) -> Option<ProcessInfo> {
+        if let Ok(cache) = self.cache.read()
+            && let Some(info) = cache.get(&pid)
+        {
+            return Some(info.clone());
+        }
+
+        let info = Self::read_process_info(pid)?;
+
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(pid, info.clone());
+        }
+
+        Some(info)
+    }
+
+    /// Evict a cached PID, e.g. once its process has exited.
+    pub fn evict(&self, pid: u32) {
+        if let Ok(mut cache) = self.cache.write() {
+            cache.remove(&pid);
+        }
+    }
+
+    fn read_process_info(pid: u32) -> Option<ProcessInfo> {
+        let process = Process::new(pid as i32).ok()?;
+        let stat = process.stat().ok()?;
+        let cmdline = process.cmdline().unwrap_or_default();
+        let uid = process.uid().ok()?;
+        let container_id = extract_container_id(&process);
+
+        Some(ProcessInfo {
+            comm: stat.comm,
+            cmdline,
+            ppid: stat.ppid,
+            uid,
+            container_id,
+        })
+    }
+}
+
+/// Extract the container ID from a process's cgroups, using `procfs`'s typed
+/// cgroup parser instead of hand-rolled line splitting.
+fn extract_container_id(process: &Process) -> Option<String> {
+    let cgroups = process.cgroups().ok()?;
+    cgroups
+        .0
+        .iter()
+        .find_map(|cgroup| parse_container_id_from_path(&cgroup.pathname))
+}
+
+/// Extract a container ID from a cgroup pathname.
+///
+/// Supports both cgroup v1/v2 path layout and the systemd scope naming
+/// (`cri-containerd-<64hex>.scope` / `docker-<64hex>.scope`) - see
+/// `k8s::parse_container_id_from_cgroup_line` for the line-oriented
+/// equivalent this mirrors.
+fn parse_container_id_from_path(path: &str) -> Option<String> {
+    if !path.contains("kubepods") && !path.contains("docker") && !path.contains("containerd") {
+        return None;
+    }
+
+    let last_segment = path.rsplit('/').next()?;
+
+    if last_segment.ends_with(".scope") {
+        let inner = last_segment.trim_end_matches(".scope");
+        if let Some(hex_id) = inner.rsplit('-').next()
+            && is_container_id(hex_id)
+        {
+            return Some(hex_id[..12].to_string());
+        }
+    }
+
+    if is_container_id(last_segment) {
+        return Some(last_segment[..12].to_string());
+    }
+
+    None
+}
+
+/// Check if a string looks like a 64-char hex container ID.
+fn is_container_id(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_container_id_from_v2_path() {
+        let path = "/kubepods/besteffort/podabc123/a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2";
+        assert_eq!(parse_container_id_from_path(path), Some("a1b2c3d4e5f6".to_string()));
+    }
+
+    #[test]
+    fn test_parse_container_id_from_systemd_scope() {
+        let path = "/kubepods.slice/kubepods-besteffort.slice/cri-containerd-a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2.scope";
+        assert_eq!(parse_container_id_from_path(path), Some("a1b2c3d4e5f6".to_string()));
+    }
+
+    #[test]
+    fn test_parse_container_id_from_non_container_path() {
+        let path = "/user.slice/user-1000.slice/session-1.scope";
+        assert_eq!(parse_container_id_from_path(path), None);
+    }
+
+    #[test]
+    fn test_resolver_returns_none_for_nonexistent_pid() {
+        let resolver = ProcessResolver::new();
+        assert!(resolver.resolve(u32::MAX).is_none());
+    }
+
+    #[test]
+    fn test_resolver_caches_result() {
+        let resolver = ProcessResolver::new();
+        let pid = std::process::id();
+        let first = resolver.resolve(pid);
+        assert!(first.is_some());
+        assert!(resolver.cache.read().unwrap().contains_key(&pid));
+    }
+
+    #[test]
+    fn test_evict_removes_cached_entry() {
+        let resolver = ProcessResolver::new();
+        let pid = std::process::id();
+        resolver.resolve(pid);
+        resolver.evict(pid);
+        assert!(!resolver.cache.read().unwrap().contains_key(&pid));
+    }
+}
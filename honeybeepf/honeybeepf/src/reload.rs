@@ -0,0 +1,235 @@
+//! Hot-reloadable probe configuration.
+//!
+//! Polls the config source (environment + `.env` file) on a timer, rebuilds
+//! [`Settings`] and diffs the resulting [`CommonConfig`] against the last
+//! value actually pushed to the eBPF side, and atomically overwrites the
+//! single-entry `CONFIG` array map the probes consult once the new value has
+//! held steady for [`DEBOUNCE_WINDOW`]. This lets an operator flip a probe
+//! flag or raise a latency threshold without restarting the agent or
+//! re-attaching any programs.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use aya::Ebpf;
+use aya::maps::{Array, MapData};
+use honeybeepf_common::CommonConfig;
+use log::{info, warn};
+
+use crate::settings::Settings;
+
+/// How often to re-read the config source for changes.
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A candidate config must read back identically for this long before it's
+/// applied, so a burst of rapid successive writes to the env/`.env` file
+/// coalesces into a single apply instead of thrashing the live map.
+const DEBOUNCE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Watches for config changes and keeps the live `CONFIG` eBPF map in sync
+/// with the latest validated [`Settings`].
+pub struct ConfigReloader {
+    map: Array<MapData, CommonConfig>,
+    applied: CommonConfig,
+    pending: Option<(CommonConfig, Instant)>,
+}
+
+impl ConfigReloader {
+    /// Takes ownership of the `CONFIG` map out of `bpf` and seeds it with
+    /// `initial` (the config computed from the settings the engine attached
+    /// probes with).
+    pub fn new(bpf: &mut Ebpf, initial: CommonConfig) -> Result<Self> {
+        let mut map: Array<MapData, CommonConfig> = Array::try_from(
+            bpf.take_map("CONFIG").context("Failed to find CONFIG map")?,
+        )
+        .context("CONFIG is not an Array map")?;
+        map.set(0, initial, 0).context("Failed to seed CONFIG map")?;
+
+        Ok(Self {
+            map,
+            applied: initial,
+            pending: None,
+        })
+    }
+
+    /// Runs the poll loop forever. Intended to be spawned as its own tokio
+    /// task; returns only if re-reading settings panics (it doesn't - errors
+    /// are logged and the previous good config stays live).
+    pub async fn run(mut self) {
+        loop {
+            tokio::time::sleep(RELOAD_POLL_INTERVAL).await;
+            self.tick(Instant::now());
+        }
+    }
+
+    /// One poll iteration: reload settings, decide whether to apply, and
+    /// push to the map if so. Split out from `run` so the debounce decision
+    /// can be unit tested without sleeping or touching a real eBPF map.
+    fn tick(&mut self, now: Instant) {
+        let candidate = match reload_common_config() {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Config reload: failed to load settings, keeping previous config live: {}", e);
+                return;
+            }
+        };
+
+        let (next_pending, to_apply) = decide_reload(self.pending, self.applied, candidate, now, DEBOUNCE_WINDOW);
+        self.pending = next_pending;
+
+        if let Some(config) = to_apply {
+            log_transitions(&self.applied, &config);
+            if let Err(e) = self.map.set(0, config, 0) {
+                warn!("Config reload: failed to write CONFIG map: {}", e);
+                return;
+            }
+            self.applied = config;
+        }
+    }
+}
+
+/// Rebuilds [`Settings`] from the environment/`.env` file and converts it to
+/// a [`CommonConfig`]. Re-reads the `.env` file's values directly (rather
+/// than relying on `dotenvy::dotenv()`, which never overrides a variable
+/// already present in the process environment) so edits to the file are
+/// actually picked up on a later poll, not just the first one.
+fn reload_common_config() -> Result<CommonConfig> {
+    if let Ok(entries) = dotenvy::dotenv_iter() {
+        for entry in entries {
+            let (key, value) = entry.context("Failed to parse .env entry")?;
+            unsafe { std::env::set_var(key, value) };
+        }
+    }
+
+    let settings = Settings::new().context("Failed to load settings")?;
+    Ok(settings.to_common_config())
+}
+
+/// Pure debounce decision: given the currently pending candidate (if any),
+/// the last-applied config, the freshly reloaded candidate, and "now", works
+/// out the next pending state and, if the debounce window has elapsed on an
+/// unchanged candidate, the config to apply.
+fn decide_reload(
+    pending: Option<(CommonConfig, Instant)>,
+    applied: CommonConfig,
+    candidate: CommonConfig,
+    now: Instant,
+    debounce: Duration,
+) -> (Option<(CommonConfig, Instant)>, Option<CommonConfig>) {
+    if candidate == applied {
+        // Back to (or still at) the live config - nothing pending anymore.
+        return (None, None);
+    }
+
+    match pending {
+        Some((pending_config, first_seen)) if pending_config == candidate => {
+            if now.duration_since(first_seen) >= debounce {
+                (None, Some(candidate))
+            } else {
+                (Some((pending_config, first_seen)), None)
+            }
+        }
+        _ => (Some((candidate, now)), None),
+    }
+}
+
+fn log_transitions(old: &CommonConfig, new: &CommonConfig) {
+    macro_rules! log_flag {
+        ($field:ident, $name:expr) => {
+            if old.$field != new.$field {
+                info!("Config reload: {} {} -> {}", $name, old.$field, new.$field);
+            }
+        };
+    }
+
+    log_flag!(probe_tcp_connect, "probe_tcp_connect");
+    log_flag!(probe_tcp_retrans, "probe_tcp_retrans");
+    log_flag!(probe_dns, "probe_dns");
+    log_flag!(probe_vfs_latency, "probe_vfs_latency");
+    log_flag!(probe_file_access, "probe_file_access");
+    log_flag!(probe_runqueue, "probe_runqueue");
+    log_flag!(probe_offcpu, "probe_offcpu");
+    log_flag!(probe_llm, "probe_llm");
+    log_flag!(probe_interval, "probe_interval");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(offcpu: u8) -> CommonConfig {
+        CommonConfig {
+            probe_tcp_connect: 1,
+            probe_tcp_retrans: 0,
+            probe_dns: 0,
+            probe_vfs_latency: 0,
+            probe_file_access: 0,
+            probe_runqueue: 0,
+            probe_offcpu: offcpu,
+            probe_llm: 0,
+            probe_interval: 60,
+            _pad: [],
+        }
+    }
+
+    #[test]
+    fn unchanged_candidate_clears_pending_without_applying() {
+        let now = Instant::now();
+        let (pending, to_apply) = decide_reload(None, config(0), config(0), now, Duration::from_secs(10));
+        assert_eq!(pending, None);
+        assert_eq!(to_apply, None);
+    }
+
+    #[test]
+    fn new_candidate_starts_pending_without_applying() {
+        let now = Instant::now();
+        let (pending, to_apply) = decide_reload(None, config(0), config(1), now, Duration::from_secs(10));
+        assert_eq!(pending, Some((config(1), now)));
+        assert_eq!(to_apply, None);
+    }
+
+    #[test]
+    fn stable_candidate_applies_once_debounce_elapses() {
+        let first_seen = Instant::now();
+        let later = first_seen + Duration::from_secs(11);
+        let (pending, to_apply) = decide_reload(
+            Some((config(1), first_seen)),
+            config(0),
+            config(1),
+            later,
+            Duration::from_secs(10),
+        );
+        assert_eq!(pending, None);
+        assert_eq!(to_apply, Some(config(1)));
+    }
+
+    #[test]
+    fn still_within_debounce_window_keeps_waiting() {
+        let first_seen = Instant::now();
+        let soon = first_seen + Duration::from_secs(2);
+        let (pending, to_apply) = decide_reload(
+            Some((config(1), first_seen)),
+            config(0),
+            config(1),
+            soon,
+            Duration::from_secs(10),
+        );
+        assert_eq!(pending, Some((config(1), first_seen)));
+        assert_eq!(to_apply, None);
+    }
+
+    #[test]
+    fn flapping_candidate_restarts_the_debounce_timer() {
+        let first_seen = Instant::now();
+        let flapped_at = first_seen + Duration::from_secs(5);
+        let (pending, to_apply) = decide_reload(
+            Some((config(1), first_seen)),
+            config(0),
+            config(2),
+            flapped_at,
+            Duration::from_secs(10),
+        );
+        assert_eq!(pending, Some((config(2), flapped_at)));
+        assert_eq!(to_apply, None);
+    }
+}
@@ -1,5 +1,6 @@
 use config::{Config, ConfigError, Environment};
 use serde::Deserialize;
+use std::path::PathBuf;
 
 const DEFAULT_PROBE_INTERVAL_SECONDS: u32 = 60;
 
@@ -8,6 +9,12 @@ const DEFAULT_PROBE_INTERVAL_SECONDS: u32 = 60;
 #[allow(unused)]
 pub struct NetworkProbes {
     pub tcp_connect: Option<bool>,
+    /// Aggregate TCP connect stats per cgroup in-kernel instead of streaming
+    /// one event per call - see `TcpConnectProbe::aggregate`.
+    pub tcp_connect_aggregate: Option<bool>,
+    /// Track TCP connect latency as an in-kernel log2 histogram instead of
+    /// streaming events or aggregating - see `TcpConnectProbe::histogram`.
+    pub tcp_connect_histogram: Option<bool>,
     pub tcp_retrans: Option<bool>,
     pub dns: Option<bool>,
 }
@@ -17,9 +24,38 @@ pub struct NetworkProbes {
 #[allow(unused)]
 pub struct FilesystemProbes {
     pub vfs_latency: Option<bool>,
+    /// Latency threshold applied to both read and write, unless overridden
+    /// for write by `vfs_latency_write_threshold_ms` - see
+    /// `VfsLatencyProbe::read`/`write`.
     pub vfs_latency_threshold_ms: Option<u32>,
+    /// Write-only latency threshold override (defaults to
+    /// `vfs_latency_threshold_ms` when unset).
+    pub vfs_latency_write_threshold_ms: Option<u32>,
+    /// Minimum bytes transferred for the read fast-path bypass, in bytes -
+    /// defaults to the probe's long-standing 1MB floor.
+    pub vfs_read_min_bytes: Option<u64>,
+    /// Minimum bytes transferred for the write fast-path bypass, in bytes -
+    /// 0 (the default) disables byte-based filtering for writes, same as
+    /// before this was configurable.
+    pub vfs_write_min_bytes: Option<u64>,
+    /// Whether the write path should also restrict itself to regular files
+    /// only, like the read path always has. Off by default.
+    pub vfs_write_regular_files_only: Option<bool>,
+    /// Aggregate VFS latency stats per cgroup in-kernel instead of streaming
+    /// one event per call - see `VfsLatencyProbe::aggregate`.
+    pub vfs_latency_aggregate: Option<bool>,
+    /// Track VFS latency as an in-kernel log2 histogram instead of streaming
+    /// events or aggregating - see `VfsLatencyProbe::histogram`.
+    pub vfs_latency_histogram: Option<bool>,
     pub file_access: Option<bool>,
     pub watched_paths: Option<Vec<String>>,
+    pub watched_prefixes: Option<Vec<String>>,
+    /// Also hook the legacy `open()` syscall, not just `openat()`.
+    pub file_access_open: Option<bool>,
+    /// Also hook `openat2()`.
+    pub file_access_openat2: Option<bool>,
+    /// Also hook `creat()`.
+    pub file_access_creat: Option<bool>,
 }
 
 /// Scheduler probe configuration
@@ -32,6 +68,21 @@ pub struct SchedulerProbes {
     pub offcpu_threshold_ms: Option<u32>,
 }
 
+/// GPU probe configuration
+#[derive(Debug, Deserialize, Clone, Default)]
+#[allow(unused)]
+pub struct GpuProbes {
+    /// `GpuMemProbe` - per-process VRAM allocation accounting via ioctl.
+    pub mem: Option<bool>,
+    /// `GpuUsageProbe` - GPU device open/close tracking.
+    pub usage: Option<bool>,
+    /// `GpuUtilProbe` - per-process GPU busy-time tracking via dma-fence
+    /// tracepoints.
+    pub util: Option<bool>,
+    /// `NcclCommProbe` - NCCL collective-communication monitoring.
+    pub nccl: Option<bool>,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[allow(unused)]
 pub struct BuiltinProbes {
@@ -41,6 +92,8 @@ pub struct BuiltinProbes {
     pub filesystem: FilesystemProbes,
     #[serde(default)]
     pub scheduler: SchedulerProbes,
+    #[serde(default)]
+    pub gpu: GpuProbes,
     pub llm: Option<bool>,
     pub interval: Option<u32>,
 }
@@ -52,17 +105,55 @@ pub struct Settings {
     pub otel_exporter_otlp_protocol: Option<String>,
     pub builtin_probes: BuiltinProbes,
     pub custom_probe_config: Option<String>,
+    /// Address (`host:port`) to serve Prometheus-format connection metrics
+    /// on, e.g. `"0.0.0.0:9464"` - the OTLP collector's conventional
+    /// Prometheus-exporter port. Unset disables the endpoint.
+    pub metrics_http_addr: Option<String>,
+}
+
+/// Config file locations checked, in order, for each of the supported
+/// extensions - the first one that exists is loaded. Conventional system
+/// location first, then the working directory as a fallback for local runs.
+const CONFIG_SEARCH_DIRS: &[&str] = &["/etc/honeybeepf", "."];
+const CONFIG_BASENAME: &str = "config";
+const CONFIG_EXTENSIONS: &[&str] = &["toml", "yaml", "yml"];
+
+/// The config file path the `init` wizard writes to and points users at -
+/// the first `CONFIG_SEARCH_DIRS` entry with the first (TOML) extension.
+pub fn default_config_path() -> PathBuf {
+    PathBuf::from(CONFIG_SEARCH_DIRS[0]).join(format!("{}.{}", CONFIG_BASENAME, CONFIG_EXTENSIONS[0]))
+}
+
+/// Returns the first existing `CONFIG_SEARCH_DIRS`/`CONFIG_BASENAME.{CONFIG_EXTENSIONS}`
+/// path, if any.
+fn find_config_file() -> Option<PathBuf> {
+    CONFIG_SEARCH_DIRS.iter().find_map(|dir| {
+        CONFIG_EXTENSIONS.iter().find_map(|ext| {
+            let path = PathBuf::from(dir).join(format!("{}.{}", CONFIG_BASENAME, ext));
+            path.exists().then_some(path)
+        })
+    })
 }
 
 impl Settings {
     pub fn new() -> Result<Self, ConfigError> {
         dotenvy::dotenv().ok();
 
-        let s = Config::builder()
+        let mut builder = Config::builder();
+
+        // File source goes in first so the environment source added below
+        // layers on top and always wins on a conflicting key.
+        if let Some(path) = find_config_file() {
+            builder = builder.add_source(config::File::from(path).required(false));
+        }
+
+        let s = builder
             .add_source(Environment::default().separator("__"))
             .build()?;
 
-        s.try_deserialize()
+        s.try_deserialize().map_err(|e| {
+            ConfigError::Message(format!("Failed to parse honeybeepf configuration: {}", e))
+        })
     }
 
     pub fn to_common_config(&self) -> honeybeepf_common::CommonConfig {
@@ -138,14 +229,26 @@ mod tests {
             builtin_probes: BuiltinProbes {
                 network: NetworkProbes {
                     tcp_connect: Some(true),
+                    tcp_connect_aggregate: None,
+                    tcp_connect_histogram: None,
                     tcp_retrans: None,
                     dns: Some(true),
                 },
                 filesystem: FilesystemProbes {
                     vfs_latency: Some(true),
                     vfs_latency_threshold_ms: Some(10),
+                    vfs_latency_write_threshold_ms: None,
+                    vfs_read_min_bytes: None,
+                    vfs_write_min_bytes: None,
+                    vfs_write_regular_files_only: None,
+                    vfs_latency_aggregate: None,
+                    vfs_latency_histogram: None,
                     file_access: None,
                     watched_paths: None,
+                    watched_prefixes: None,
+                    file_access_open: None,
+                    file_access_openat2: None,
+                    file_access_creat: None,
                 },
                 scheduler: SchedulerProbes {
                     runqueue: None,
@@ -153,10 +256,12 @@ mod tests {
                     offcpu: Some(true),
                     offcpu_threshold_ms: Some(5),
                 },
+                gpu: GpuProbes::default(),
                 llm: None,
                 interval: None,
             },
             custom_probe_config: None,
+            metrics_http_addr: None,
         };
 
         let common = settings.to_common_config();
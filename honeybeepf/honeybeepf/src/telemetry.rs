@@ -8,6 +8,7 @@
 //! 3. Code default value (FQDN)
 
 use anyhow::{Context, Result};
+use honeybeepf_common::{HIST_NUM_BUCKETS, bucket_upper_bound_ns};
 use log::info;
 use opentelemetry::metrics::{Counter, Histogram, Meter};
 use opentelemetry::{KeyValue, global};
@@ -15,18 +16,749 @@ use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::Resource;
 use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
 use std::collections::HashMap;
-use std::sync::{OnceLock, RwLock};
+use std::sync::{Arc, OnceLock, RwLock};
 use std::time::Duration;
 
+use crate::k8s::PodResolver;
+use crate::metrics as prom_metrics;
+
 /// Metric export interval in seconds
 const METRIC_EXPORT_INTERVAL_SECS: u64 = 30;
 
+/// Adaptive cardinality governor: tracks, per metric, how many distinct
+/// attribute-value tuples are recorded within an export window and
+/// progressively drops high-cardinality labels (`filename`, `dest_addr`,
+/// `query_name`, `process`, `cgroup_id`) before the OTLP collector's
+/// time-series count explodes.
+mod cardinality {
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::{Mutex, OnceLock};
+
+    use log::{info, warn};
+
+    const DEFAULT_HIGH_WATERMARK: usize = 5_000;
+    const DEFAULT_LOW_WATERMARK: usize = 2_000;
+
+    /// Tiered label-stripping level for a single metric. Escalates as the
+    /// distinct-tuple count for that metric crosses the high watermark, and
+    /// de-escalates once it falls back under the low watermark.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    #[repr(u8)]
+    pub enum DropLevel {
+        /// Record every label at full resolution.
+        None = 0,
+        /// Bucket `filename`-shaped labels down to their parent directory.
+        DropFilename = 1,
+        /// Bucket `dest_addr`-shaped labels down to their /24 subnet.
+        DropAddr = 2,
+        /// Drop every droppable label; only the bare metric survives.
+        AggregateOnly = 3,
+    }
+
+    impl DropLevel {
+        fn from_u8(v: u8) -> Self {
+            match v {
+                1 => DropLevel::DropFilename,
+                2 => DropLevel::DropAddr,
+                3 => DropLevel::AggregateOnly,
+                _ => DropLevel::None,
+            }
+        }
+
+        fn escalate(self) -> Option<Self> {
+            match self {
+                DropLevel::None => Some(DropLevel::DropFilename),
+                DropLevel::DropFilename => Some(DropLevel::DropAddr),
+                DropLevel::DropAddr => Some(DropLevel::AggregateOnly),
+                DropLevel::AggregateOnly => None,
+            }
+        }
+
+        fn de_escalate(self) -> Option<Self> {
+            match self {
+                DropLevel::None => None,
+                DropLevel::DropFilename => Some(DropLevel::None),
+                DropLevel::DropAddr => Some(DropLevel::DropFilename),
+                DropLevel::AggregateOnly => Some(DropLevel::DropAddr),
+            }
+        }
+    }
+
+    /// Per-metric cardinality state: the current drop level (checked on
+    /// every `record_*` call via a cheap atomic load) and the set of
+    /// distinct attribute-tuple hashes observed in the current export
+    /// window (only walked once per window, at `evaluate` time).
+    pub struct CardinalityGuard {
+        name: &'static str,
+        level: AtomicU8,
+        seen: Mutex<HashSet<u64>>,
+    }
+
+    impl CardinalityGuard {
+        pub const fn new(name: &'static str) -> Self {
+            Self {
+                name,
+                level: AtomicU8::new(0),
+                seen: Mutex::new(HashSet::new()),
+            }
+        }
+
+        /// Current drop level. Cheap: a single relaxed atomic load, safe to
+        /// call on every `record_*` invocation.
+        pub fn level(&self) -> DropLevel {
+            DropLevel::from_u8(self.level.load(Ordering::Relaxed))
+        }
+
+        /// Records one more attribute tuple for this export window. Hashes
+        /// the raw (pre-bucketing) label values so cardinality is measured
+        /// against what callers actually sent, not what we reported.
+        pub fn observe(&self, parts: &[&str]) {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            for part in parts {
+                part.hash(&mut hasher);
+            }
+            if let Ok(mut seen) = self.seen.lock() {
+                seen.insert(hasher.finish());
+            }
+        }
+
+        /// Runs once per export interval: compares this window's
+        /// distinct-tuple count against the watermarks, escalates or
+        /// de-escalates the drop level accordingly, and resets the window.
+        fn evaluate(&self, high_watermark: usize, low_watermark: usize) {
+            let count = {
+                let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+                let count = seen.len();
+                seen.clear();
+                count
+            };
+
+            let current = self.level();
+            if count >= high_watermark {
+                if let Some(next) = current.escalate() {
+                    self.level.store(next as u8, Ordering::Relaxed);
+                    warn!(
+                        "telemetry cardinality guard [{}]: {} distinct attribute tuples (>= {}); escalating {:?} -> {:?}",
+                        self.name, count, high_watermark, current, next
+                    );
+                }
+            } else if count < low_watermark {
+                if let Some(next) = current.de_escalate() {
+                    self.level.store(next as u8, Ordering::Relaxed);
+                    info!(
+                        "telemetry cardinality guard [{}]: {} distinct attribute tuples (< {}); de-escalating {:?} -> {:?}",
+                        self.name, count, low_watermark, current, next
+                    );
+                }
+            }
+        }
+    }
+
+    pub static VFS_GUARD: CardinalityGuard = CardinalityGuard::new("vfs_events");
+    pub static FILE_ACCESS_GUARD: CardinalityGuard = CardinalityGuard::new("file_access_events");
+    pub static TCP_CONNECT_GUARD: CardinalityGuard = CardinalityGuard::new("tcp_connect_events");
+    pub static TCP_RETRANS_GUARD: CardinalityGuard = CardinalityGuard::new("tcp_retrans_events");
+    pub static DNS_GUARD: CardinalityGuard = CardinalityGuard::new("dns_query_events");
+    pub static RUNQUEUE_GUARD: CardinalityGuard = CardinalityGuard::new("runqueue_latency_ns");
+    pub static OFFCPU_GUARD: CardinalityGuard = CardinalityGuard::new("offcpu_duration_ns");
+
+    fn all_guards() -> [&'static CardinalityGuard; 7] {
+        [
+            &VFS_GUARD,
+            &FILE_ACCESS_GUARD,
+            &TCP_CONNECT_GUARD,
+            &TCP_RETRANS_GUARD,
+            &DNS_GUARD,
+            &RUNQUEUE_GUARD,
+            &OFFCPU_GUARD,
+        ]
+    }
+
+    fn watermarks() -> (usize, usize) {
+        static WATERMARKS: OnceLock<(usize, usize)> = OnceLock::new();
+        *WATERMARKS.get_or_init(|| {
+            let high = std::env::var("HONEYBEEPF_CARDINALITY_HIGH_WATERMARK")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_HIGH_WATERMARK);
+            let low = std::env::var("HONEYBEEPF_CARDINALITY_LOW_WATERMARK")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_LOW_WATERMARK);
+            (high, low)
+        })
+    }
+
+    /// Labels callers are allowed to strip under escalation. Configurable so
+    /// an operator can pin a label at full resolution (e.g. keep `dest_addr`
+    /// exact for a security workload) even under cardinality pressure.
+    fn droppable_labels() -> &'static HashSet<String> {
+        static LABELS: OnceLock<HashSet<String>> = OnceLock::new();
+        LABELS.get_or_init(|| match std::env::var("HONEYBEEPF_CARDINALITY_DROPPABLE_LABELS") {
+            Ok(v) => v
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            Err(_) => ["filename", "dest_addr", "query_name", "process", "cgroup_id"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        })
+    }
+
+    fn is_droppable(label: &str) -> bool {
+        droppable_labels().contains(label)
+    }
+
+    /// Bucket a filesystem path down to its parent directory.
+    fn bucket_filename(path: &str) -> String {
+        match path.rfind('/') {
+            Some(0) => "/".to_string(),
+            Some(idx) => path[..idx].to_string(),
+            None => "<no-dir>".to_string(),
+        }
+    }
+
+    /// Bucket an IPv4 dotted-quad address down to its /24 subnet. Addresses
+    /// that aren't a plain dotted quad (IPv6, hostnames) pass through
+    /// unchanged since there's no cheap generic subnet to collapse to.
+    fn bucket_addr(addr: &str) -> String {
+        match addr.parse::<std::net::Ipv4Addr>() {
+            Ok(ip) => {
+                let [a, b, c, _] = ip.octets();
+                format!("{a}.{b}.{c}.0/24")
+            }
+            Err(_) => addr.to_string(),
+        }
+    }
+
+    /// Applies the drop level to a single high-cardinality label: passed
+    /// through unchanged below `at_level`, bucketed via `bucket` once the
+    /// guard reaches `at_level`, and omitted entirely at `AggregateOnly`.
+    /// Labels an operator has excluded from `droppable_labels` are never
+    /// bucketed or dropped, regardless of level.
+    pub fn governed_label(
+        level: DropLevel,
+        at_level: DropLevel,
+        label: &'static str,
+        value: &str,
+        bucket: impl FnOnce(&str) -> String,
+    ) -> Option<(&'static str, String)> {
+        if !is_droppable(label) {
+            return Some((label, value.to_string()));
+        }
+        if level >= DropLevel::AggregateOnly {
+            None
+        } else if level >= at_level {
+            Some((label, bucket(value)))
+        } else {
+            Some((label, value.to_string()))
+        }
+    }
+
+    /// Filename-shaped label: buckets to parent directory at `DropFilename`.
+    pub fn governed_filename(level: DropLevel, label: &'static str, value: &str) -> Option<(&'static str, String)> {
+        governed_label(level, DropLevel::DropFilename, label, value, bucket_filename)
+    }
+
+    /// Address-shaped label: buckets to /24 subnet at `DropAddr`.
+    pub fn governed_addr(level: DropLevel, label: &'static str, value: &str) -> Option<(&'static str, String)> {
+        governed_label(level, DropLevel::DropAddr, label, value, bucket_addr)
+    }
+
+    /// Label with no intermediate bucketing of its own (e.g. `process`,
+    /// `cgroup_id`, `query_name`): kept at full resolution until
+    /// `AggregateOnly`, at which point it's dropped entirely.
+    pub fn governed_passthrough(level: DropLevel, label: &'static str, value: &str) -> Option<(&'static str, String)> {
+        governed_label(level, DropLevel::AggregateOnly, label, value, |v| v.to_string())
+    }
+
+    /// Runs the once-per-export-interval evaluation for every guarded
+    /// metric. Called from a background task on the same cadence as the
+    /// OTLP exporter.
+    pub fn evaluate_all() {
+        let (high, low) = watermarks();
+        for guard in all_guards() {
+            guard.evaluate(high, low);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn escalates_and_de_escalates_in_order() {
+            assert_eq!(DropLevel::None.escalate(), Some(DropLevel::DropFilename));
+            assert_eq!(DropLevel::DropFilename.escalate(), Some(DropLevel::DropAddr));
+            assert_eq!(DropLevel::DropAddr.escalate(), Some(DropLevel::AggregateOnly));
+            assert_eq!(DropLevel::AggregateOnly.escalate(), None);
+
+            assert_eq!(DropLevel::AggregateOnly.de_escalate(), Some(DropLevel::DropAddr));
+            assert_eq!(DropLevel::DropAddr.de_escalate(), Some(DropLevel::DropFilename));
+            assert_eq!(DropLevel::DropFilename.de_escalate(), Some(DropLevel::None));
+            assert_eq!(DropLevel::None.de_escalate(), None);
+        }
+
+        #[test]
+        fn guard_escalates_past_high_watermark_and_resets_window() {
+            let guard = CardinalityGuard::new("test_metric");
+            for i in 0..10 {
+                guard.observe(&[&i.to_string()]);
+            }
+            assert_eq!(guard.level(), DropLevel::None);
+
+            guard.evaluate(5, 1);
+            assert_eq!(guard.level(), DropLevel::DropFilename);
+
+            // The window was reset by `evaluate`, so an empty window
+            // immediately de-escalates back down.
+            guard.evaluate(5, 1);
+            assert_eq!(guard.level(), DropLevel::None);
+        }
+
+        #[test]
+        fn bucket_filename_takes_parent_directory() {
+            assert_eq!(bucket_filename("/var/log/app/out.log"), "/var/log/app");
+            assert_eq!(bucket_filename("/etc/hosts"), "/etc");
+            assert_eq!(bucket_filename("relative.txt"), "<no-dir>");
+        }
+
+        #[test]
+        fn bucket_addr_collapses_to_slash_24() {
+            assert_eq!(bucket_addr("10.1.2.3"), "10.1.2.0/24");
+            assert_eq!(bucket_addr("::1"), "::1");
+        }
+
+        #[test]
+        fn governed_label_passes_through_non_droppable_labels() {
+            // "totally_custom_label" isn't in the default droppable set, so
+            // it survives even at AggregateOnly.
+            let result = governed_label(
+                DropLevel::AggregateOnly,
+                DropLevel::None,
+                "totally_custom_label",
+                "value",
+                |v| v.to_string(),
+            );
+            assert_eq!(result, Some(("totally_custom_label", "value".to_string())));
+        }
+    }
+}
+
+/// Reverse-DNS enrichment for network metrics: resolves `dest_addr` IPs to
+/// hostnames off the hot path, mirroring bandwhich's opt-in DNS resolution
+/// mode. Disabled by default -- a raw IP is always recorded immediately;
+/// the `dest_host` attribute only appears once (and if) resolution
+/// completes, cached with a TTL, bounded to the most recently used
+/// addresses so a port-scanned/churning fleet can't grow it unbounded.
+mod dns_cache {
+    use std::collections::HashMap;
+    use std::net::IpAddr;
+    use std::sync::{Mutex, OnceLock};
+    use std::time::{Duration, Instant};
+
+    use log::debug;
+
+    const DEFAULT_CACHE_SIZE: usize = 4_096;
+    const DEFAULT_TTL_SECS: u64 = 300;
+
+    enum Slot {
+        /// A resolution is in flight; further lookups for this IP coalesce
+        /// onto it instead of spawning another `lookup_addr` call.
+        Pending,
+        Resolved { hostname: Option<String>, at: Instant },
+    }
+
+    struct Cache {
+        entries: HashMap<IpAddr, Slot>,
+        /// Most-recently-touched IPs, back = newest; used for LRU eviction
+        /// once `entries` exceeds the configured size.
+        order: Vec<IpAddr>,
+    }
+
+    fn cache() -> &'static Mutex<Cache> {
+        static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+        CACHE.get_or_init(|| {
+            Mutex::new(Cache {
+                entries: HashMap::new(),
+                order: Vec::new(),
+            })
+        })
+    }
+
+    fn enabled() -> bool {
+        static ENABLED: OnceLock<bool> = OnceLock::new();
+        *ENABLED.get_or_init(|| {
+            std::env::var("HONEYBEEPF_DNS_REVERSE_RESOLVE")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false)
+        })
+    }
+
+    fn max_size() -> usize {
+        static MAX_SIZE: OnceLock<usize> = OnceLock::new();
+        *MAX_SIZE.get_or_init(|| {
+            std::env::var("HONEYBEEPF_DNS_REVERSE_CACHE_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_CACHE_SIZE)
+        })
+    }
+
+    fn ttl() -> Duration {
+        static TTL: OnceLock<Duration> = OnceLock::new();
+        *TTL.get_or_init(|| {
+            let secs = std::env::var("HONEYBEEPF_DNS_REVERSE_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_TTL_SECS);
+            Duration::from_secs(secs)
+        })
+    }
+
+    fn touch(cache: &mut Cache, addr: IpAddr) {
+        cache.order.retain(|ip| *ip != addr);
+        cache.order.push(addr);
+        while cache.order.len() > max_size() {
+            let evicted = cache.order.remove(0);
+            cache.entries.remove(&evicted);
+        }
+    }
+
+    /// Returns the cached hostname for `daddr` if already resolved and not
+    /// expired, attaching nothing on a miss/pending/disabled and instead
+    /// kicking off a background resolution (deduplicated via [`Slot::Pending`]
+    /// so a burst of connections to one host triggers a single lookup).
+    pub fn hostname_for(daddr: &str) -> Option<String> {
+        if !enabled() {
+            return None;
+        }
+        let addr: IpAddr = daddr.parse().ok()?;
+
+        let mut guard = cache().lock().ok()?;
+        match guard.entries.get(&addr) {
+            Some(Slot::Resolved { hostname, at }) if at.elapsed() < ttl() => {
+                let hostname = hostname.clone();
+                touch(&mut guard, addr);
+                hostname
+            }
+            Some(Slot::Pending) => None,
+            _ => {
+                guard.entries.insert(addr, Slot::Pending);
+                touch(&mut guard, addr);
+                drop(guard);
+                spawn_resolution(addr);
+                None
+            }
+        }
+    }
+
+    fn spawn_resolution(addr: IpAddr) {
+        std::thread::spawn(move || {
+            // `dns_lookup::lookup_addr` wraps a blocking `getnameinfo(3)`
+            // call, so it runs on its own thread rather than tokio's
+            // executor -- mirrors how `spawn_ringbuf_handler` keeps
+            // blocking syscalls off the async runtime.
+            let hostname = dns_lookup::lookup_addr(&addr).ok();
+            debug!("dns_cache: resolved {} -> {:?}", addr, hostname);
+
+            if let Ok(mut guard) = cache().lock() {
+                guard.entries.insert(
+                    addr,
+                    Slot::Resolved {
+                        hostname,
+                        at: Instant::now(),
+                    },
+                );
+            }
+        });
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn order_front_eviction_drops_least_recently_used_past_max_size() {
+            let mut cache = Cache {
+                entries: HashMap::new(),
+                order: Vec::new(),
+            };
+            for i in 0..3 {
+                let addr: IpAddr = format!("10.0.0.{i}").parse().unwrap();
+                cache.entries.insert(
+                    addr,
+                    Slot::Resolved {
+                        hostname: None,
+                        at: Instant::now(),
+                    },
+                );
+                cache.order.push(addr);
+            }
+            assert_eq!(cache.order.len(), 3);
+
+            let newest: IpAddr = "10.0.0.3".parse().unwrap();
+            cache.entries.insert(
+                newest,
+                Slot::Resolved {
+                    hostname: None,
+                    at: Instant::now(),
+                },
+            );
+            // Simulate max_size()==3 by evicting manually at the same
+            // threshold `touch` would use if the env var were set.
+            cache.order.push(newest);
+            while cache.order.len() > 3 {
+                let evicted = cache.order.remove(0);
+                cache.entries.remove(&evicted);
+            }
+
+            assert_eq!(cache.order.len(), 3);
+            assert!(!cache.entries.contains_key(&"10.0.0.0".parse::<IpAddr>().unwrap()));
+            assert!(cache.entries.contains_key(&newest));
+        }
+    }
+}
+
+/// Tunable latency-histogram bucket boundaries. The SDK's default explicit
+/// bucket boundaries are tuned for millisecond/second-scale values and give
+/// essentially one bucket's worth of resolution for our nanosecond-scale
+/// `*_latency_ns`/`*_duration_ns` histograms. This registers one
+/// `opentelemetry_sdk` View per histogram with boundaries appropriate for
+/// its own span: block I/O and network/TCP/DNS/VFS/runqueue latencies are
+/// narrow (~100ns-10ms, where almost all of them land), while off-CPU
+/// duration gets a wider range (~100ns-10s) since a blocked task can sleep
+/// indefinitely. Each can be overridden per-metric via environment
+/// variable without recompiling.
+mod histogram_views {
+    use log::{info, warn};
+    use opentelemetry_sdk::metrics::{Aggregation, Instrument, Stream, View, new_view};
+
+    /// Narrow range for the majority of our latencies: disk, network, and
+    /// scheduler-queue waits rarely exceed a few milliseconds.
+    fn narrow_ns_boundaries() -> Vec<f64> {
+        vec![
+            100.0, 250.0, 500.0, 1_000.0, 2_500.0, 5_000.0, 10_000.0, 25_000.0, 50_000.0, 100_000.0,
+            250_000.0, 500_000.0, 1_000_000.0, 2_500_000.0, 5_000_000.0, 10_000_000.0,
+        ]
+    }
+
+    /// Wide range for off-CPU duration, which spans ns (lock contention) to
+    /// whole seconds (blocked on I/O or sleeping).
+    fn wide_ns_boundaries() -> Vec<f64> {
+        vec![
+            100.0,
+            1_000.0,
+            10_000.0,
+            100_000.0,
+            1_000_000.0,
+            10_000_000.0,
+            100_000_000.0,
+            1_000_000_000.0,
+            10_000_000_000.0,
+        ]
+    }
+
+    /// Histogram instrument names that get a tuned View, paired with their
+    /// default boundaries. Order matches declaration order in
+    /// `HoneyBeeMetrics::new`.
+    fn default_boundaries() -> Vec<(&'static str, Vec<f64>)> {
+        vec![
+            ("block_io_latency_ns", narrow_ns_boundaries()),
+            ("network_latency_ns", narrow_ns_boundaries()),
+            ("tcp_connect_latency_ns", narrow_ns_boundaries()),
+            ("dns_query_latency_ns", narrow_ns_boundaries()),
+            ("vfs_latency_ns", narrow_ns_boundaries()),
+            ("runqueue_latency_ns", narrow_ns_boundaries()),
+            ("offcpu_duration_ns", wide_ns_boundaries()),
+        ]
+    }
+
+    /// Reads a `HONEYBEEPF_HISTOGRAM_BUCKETS_<METRIC_NAME_UPPERCASE>`
+    /// environment variable, e.g. `HONEYBEEPF_HISTOGRAM_BUCKETS_OFFCPU_DURATION_NS`,
+    /// as a comma-separated list of nanosecond boundaries. Falls back to
+    /// `default` if unset or unparseable.
+    fn boundaries_for(name: &str, default: Vec<f64>) -> Vec<f64> {
+        let env_var = format!("HONEYBEEPF_HISTOGRAM_BUCKETS_{}", name.to_uppercase());
+        match std::env::var(&env_var) {
+            Ok(raw) => {
+                let parsed: Option<Vec<f64>> =
+                    raw.split(',').map(|v| v.trim().parse::<f64>().ok()).collect();
+                match parsed {
+                    Some(boundaries) if !boundaries.is_empty() => {
+                        info!("Using custom histogram buckets for {} from {}", name, env_var);
+                        boundaries
+                    }
+                    _ => {
+                        warn!("Ignoring malformed {}; falling back to default buckets", env_var);
+                        default
+                    }
+                }
+            }
+            Err(_) => default,
+        }
+    }
+
+    /// Builds the View list for `init_metrics` to register on the
+    /// `SdkMeterProvider`, one per tuned histogram.
+    pub fn build() -> Vec<Box<dyn View>> {
+        default_boundaries()
+            .into_iter()
+            .filter_map(|(name, default)| {
+                let boundaries = boundaries_for(name, default);
+                match new_view(
+                    Instrument::new().name(name),
+                    Stream::new().aggregation(Aggregation::ExplicitBucketHistogram {
+                        boundaries,
+                        record_min_max: true,
+                    }),
+                ) {
+                    Ok(view) => Some(view),
+                    Err(e) => {
+                        warn!("Failed to build histogram view for {}: {}", name, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Agent self-observability: periodically samples the collector's own
+/// health (RSS, CPU time) and the kernel side of the pipeline (ring buffer
+/// drops, eBPF map fill ratios, probe attach results), exported as
+/// ObservableGauges. A single registration point builds a list of
+/// `(name, unit, sampler closure)` triples; the OTLP callback re-samples
+/// every entry on each export, so adding a new self-metric is one call to
+/// [`register_self_metric`] rather than a new gauge/callback pair.
+mod self_metrics {
+    use std::sync::{Mutex, OnceLock};
+
+    struct SelfMetricSampler {
+        name: &'static str,
+        unit: &'static str,
+        sample: Box<dyn Fn() -> u64 + Send + Sync>,
+    }
+
+    fn registry() -> &'static Mutex<Vec<SelfMetricSampler>> {
+        static REGISTRY: OnceLock<Mutex<Vec<SelfMetricSampler>>> = OnceLock::new();
+        REGISTRY.get_or_init(|| Mutex::new(Vec::new()))
+    }
+
+    /// Registers one more self-observability metric. Typically called once
+    /// at startup (built-ins in [`register_builtins`], plus one call per
+    /// probe that wants to expose a map fill ratio); the sampler is
+    /// re-invoked on every export.
+    pub fn register_self_metric(
+        name: &'static str,
+        unit: &'static str,
+        sample: impl Fn() -> u64 + Send + Sync + 'static,
+    ) {
+        if let Ok(mut samplers) = registry().lock() {
+            samplers.push(SelfMetricSampler {
+                name,
+                unit,
+                sample: Box::new(sample),
+            });
+        }
+    }
+
+    /// Invoked by the `honeybeepf_self` ObservableGauge callback on every
+    /// export: re-samples every registered metric.
+    pub fn sample_all() -> Vec<(&'static str, &'static str, u64)> {
+        match registry().lock() {
+            Ok(samplers) => samplers.iter().map(|s| (s.name, s.unit, (s.sample)())).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn agent_rss_bytes() -> u64 {
+        procfs::process::Process::myself()
+            .and_then(|p| p.status())
+            .ok()
+            .and_then(|status| status.vmrss)
+            .map(|kb| kb * 1024)
+            .unwrap_or(0)
+    }
+
+    fn agent_cpu_time_ms() -> u64 {
+        let ticks_per_sec = procfs::ticks_per_second().max(1);
+        procfs::process::Process::myself()
+            .and_then(|p| p.stat())
+            .ok()
+            .map(|stat| (stat.utime + stat.stime) * 1000 / ticks_per_sec)
+            .unwrap_or(0)
+    }
+
+    /// Registers the metrics every deployment gets for free: the agent's own
+    /// RSS and CPU time, read from `/proc/self/status` and `/proc/self/stat`,
+    /// the attached/failed probe totals, and the total ring buffer drop
+    /// count. Map fill ratios register themselves via
+    /// `super::register_map_fill_ratio` as probes come online.
+    pub fn register_builtins() {
+        register_self_metric("agent_rss_bytes", "bytes", agent_rss_bytes);
+        register_self_metric("agent_cpu_time_ms", "ms", agent_cpu_time_ms);
+        register_self_metric("probes_attached_total", "probes", || {
+            super::probe_attach_counts().0
+        });
+        register_self_metric("probes_failed_total", "probes", || {
+            super::probe_attach_counts().1
+        });
+        register_self_metric("ringbuf_dropped_events_total", "events", super::total_ringbuf_drops);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+
+        #[test]
+        fn register_self_metric_is_sampled_on_every_call() {
+            let counter = Arc::new(AtomicU64::new(0));
+            let sampler_counter = counter.clone();
+            register_self_metric("test_counter", "count", move || {
+                sampler_counter.fetch_add(1, Ordering::Relaxed)
+            });
+
+            let before = sample_all().len();
+            let first = sample_all();
+            let second = sample_all();
+
+            // Registration is additive and every sampler is re-invoked on
+            // each call, so the count strictly increases between calls.
+            assert_eq!(before, first.len());
+            assert_eq!(first.len(), second.len());
+            let (_, _, first_val) = first.iter().find(|(n, _, _)| *n == "test_counter").unwrap();
+            let (_, _, second_val) = second.iter().find(|(n, _, _)| *n == "test_counter").unwrap();
+            assert!(second_val > first_val);
+        }
+    }
+}
+
 /// Global metrics handle
 static METRICS: OnceLock<HoneyBeeMetrics> = OnceLock::new();
 
 /// Global MeterProvider for graceful shutdown
 static METER_PROVIDER: OnceLock<SdkMeterProvider> = OnceLock::new();
 
+/// Shared pod resolver used to label connection events by namespace/workload
+/// instead of raw cgroup_id in the Prometheus `/metrics` endpoint (see
+/// `crate::metrics`). Registered once at startup via [`set_pod_resolver`];
+/// left unset (e.g. when `NODE_NAME` isn't available) falls back to
+/// cgroup_id-derived labels.
+static POD_RESOLVER: OnceLock<Arc<PodResolver>> = OnceLock::new();
+
+/// Registers the shared pod resolver. Safe to call at most once; later
+/// calls are ignored.
+pub fn set_pod_resolver(resolver: Arc<PodResolver>) {
+    let _ = POD_RESOLVER.set(resolver);
+}
+
 /// Global active probes count (for ObservableGauge callback)
 static ACTIVE_PROBES: OnceLock<RwLock<HashMap<String, u64>>> = OnceLock::new();
 
@@ -34,6 +766,95 @@ fn active_probes_map() -> &'static RwLock<HashMap<String, u64>> {
     ACTIVE_PROBES.get_or_init(|| RwLock::new(HashMap::new()))
 }
 
+/// Per-probe attach outcome (for the `probes_attached_total` /
+/// `probes_failed_total` self-metrics).
+static PROBE_ATTACH_RESULTS: OnceLock<RwLock<HashMap<String, bool>>> = OnceLock::new();
+
+fn probe_attach_results_map() -> &'static RwLock<HashMap<String, bool>> {
+    PROBE_ATTACH_RESULTS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Records whether a probe's attach attempt succeeded, for self-observability.
+/// Call once per probe at attach time, alongside [`record_active_probe`].
+pub fn record_probe_attach_result(probe_name: &str, success: bool) {
+    if let Ok(mut results) = probe_attach_results_map().write() {
+        results.insert(probe_name.to_string(), success);
+    }
+}
+
+fn probe_attach_counts() -> (u64, u64) {
+    match probe_attach_results_map().read() {
+        Ok(results) => {
+            let attached = results.values().filter(|&&ok| ok).count() as u64;
+            let failed = results.values().filter(|&&ok| !ok).count() as u64;
+            (attached, failed)
+        }
+        Err(_) => (0, 0),
+    }
+}
+
+/// Returns `(attached, requested)`, where `requested` is every probe that
+/// had an attach attempt recorded (whether it succeeded or failed).
+pub fn probe_attach_summary() -> (u64, u64) {
+    let (attached, failed) = probe_attach_counts();
+    (attached, attached + failed)
+}
+
+/// Whether the eBPF subsystem is in a usable state: either no probes were
+/// requested, or at least one requested probe actually attached. Lets
+/// `HoneyBeeEngine::run` fail fast instead of running as an observability
+/// agent that silently observes nothing - which, under a systemd unit with
+/// `Restart=on-failure`, turns a misconfigured/unsupported kernel into an
+/// alertable restart loop instead of a quiet no-op.
+pub fn probes_ready() -> bool {
+    let (attached, requested) = probe_attach_summary();
+    requested == 0 || attached > 0
+}
+
+/// Per-ringbuf dropped-event counts (for the `ringbuf_dropped_events_total`
+/// self-metric). Counts consumer-observed malformed/truncated reads; aya's
+/// `RingBuf` doesn't expose the producer-side `reserve()` failure count
+/// directly, so this is a proxy rather than the kernel's exact drop count.
+static RINGBUF_DROPS: OnceLock<RwLock<HashMap<String, u64>>> = OnceLock::new();
+
+fn ringbuf_drops_map() -> &'static RwLock<HashMap<String, u64>> {
+    RINGBUF_DROPS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Records one dropped/malformed event for `map_name`'s ring buffer.
+pub fn record_ringbuf_drop(map_name: &str) {
+    if let Ok(mut drops) = ringbuf_drops_map().write() {
+        *drops.entry(map_name.to_string()).or_insert(0) += 1;
+    }
+}
+
+fn total_ringbuf_drops() -> u64 {
+    match ringbuf_drops_map().read() {
+        Ok(drops) => drops.values().sum(),
+        Err(_) => 0,
+    }
+}
+
+/// Registers a live eBPF map fill ratio (current key count out of
+/// `max_entries`, in permille) as a self-metric named `name` with unit
+/// `"permille"`. `current_count` is called on every export, so it should be cheap (e.g. an
+/// atomic load kept up to date by a background sampler, not a fresh map
+/// iteration).
+pub fn register_map_fill_ratio(
+    name: &'static str,
+    max_entries: u64,
+    current_count: impl Fn() -> u64 + Send + Sync + 'static,
+) {
+    self_metrics::register_self_metric(name, "permille", move || {
+        let count = current_count();
+        if max_entries == 0 {
+            0
+        } else {
+            (count * 1000 / max_entries).min(1000)
+        }
+    });
+}
+
 /// honeybeepf metrics collection
 ///
 /// Note: Do NOT add _total suffix to Counter names (Prometheus adds it automatically)
@@ -61,9 +882,18 @@ pub struct HoneyBeeMetrics {
     pub runqueue_latency_ns: Histogram<u64>,
     pub offcpu_duration_ns: Histogram<u64>,
     pub context_switch_events: Counter<u64>,
-    
+
     // GPU metrics (kept for compatibility)
     pub gpu_open_events: Counter<u64>,
+    pub gpu_mem_allocated_bytes: Histogram<u64>,
+    pub gpu_utilization_pct: Histogram<u64>,
+
+    /// Cumulative `le`-bucketed counter backing `mode: histogram` probes
+    /// (see `spawn_histogram_handler`): one series per `(probe, operation,
+    /// le)`, incremented by each snapshot's delta rather than `.record()`-ed
+    /// per call, since the eBPF side never emits individual latencies in
+    /// this mode.
+    pub histogram_bucket_count: Counter<u64>,
 }
 
 impl HoneyBeeMetrics {
@@ -163,6 +993,24 @@ impl HoneyBeeMetrics {
                 .with_description("Number of GPU device open events")
                 .with_unit("events")
                 .build(),
+            gpu_mem_allocated_bytes: meter
+                .u64_histogram("gpu_mem_allocated_bytes")
+                .with_description("Per-process GPU memory allocation total, sampled on each ioctl")
+                .with_unit("bytes")
+                .build(),
+            gpu_utilization_pct: meter
+                .u64_histogram("gpu_utilization_pct")
+                .with_description("Per-process GPU busy-time utilization, sampled per window")
+                .with_unit("percent")
+                .build(),
+
+            histogram_bucket_count: meter
+                .u64_counter("probe_latency_histogram_bucket")
+                .with_description(
+                    "Cumulative count of calls with latency <= the bucket's `le` bound, for probes running in histogram mode",
+                )
+                .with_unit("events")
+                .build(),
         }
     }
 }
@@ -215,10 +1063,13 @@ pub fn init_metrics() -> Result<()> {
         KeyValue::new("telemetry.sdk.language", "rust"),
     ]));
 
-    let provider = SdkMeterProvider::builder()
+    let mut provider_builder = SdkMeterProvider::builder()
         .with_reader(reader)
-        .with_resource(resource)
-        .build();
+        .with_resource(resource);
+    for view in histogram_views::build() {
+        provider_builder = provider_builder.with_view(view);
+    }
+    let provider = provider_builder.build();
 
     global::set_meter_provider(provider.clone());
     let _ = METER_PROVIDER.set(provider);
@@ -242,6 +1093,35 @@ pub fn init_metrics() -> Result<()> {
 
     let _ = METRICS.set(HoneyBeeMetrics::new(&meter));
 
+    self_metrics::register_builtins();
+
+    // A single gauge fans out to every registered self-metric rather than
+    // one instrument per metric, since the unit varies per sample (bytes,
+    // ms, events, permille) and OTel gauges don't carry a per-observation
+    // unit override -- the name/unit is reported as an attribute instead.
+    let _self_metrics_gauge = meter
+        .u64_observable_gauge("honeybeepf_self")
+        .with_description("Agent self-observability: health, ring buffer drops, map fill ratios, probe attach results")
+        .with_callback(|observer| {
+            for (name, unit, value) in self_metrics::sample_all() {
+                observer.observe(
+                    value,
+                    &[KeyValue::new("metric", name), KeyValue::new("unit", unit)],
+                );
+            }
+        })
+        .build();
+
+    // Evaluate the cardinality guards on the same cadence as the exporter,
+    // so a metric's drop level reacts to the window the collector just saw.
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(METRIC_EXPORT_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            cardinality::evaluate_all();
+        }
+    });
+
     info!("OpenTelemetry metrics initialized successfully");
     Ok(())
 }
@@ -280,6 +1160,25 @@ pub fn record_gpu_open_event(device_path: &str) {
     }
 }
 
+pub fn record_gpu_mem_event(gpu_index: i32, delta_bytes: i64, total_bytes: u64, comm: &str) {
+    if let Some(m) = metrics() {
+        let attrs = [
+            KeyValue::new("gpu_index", gpu_index as i64),
+            KeyValue::new("process", comm.to_string()),
+        ];
+        let _ = delta_bytes;
+        m.gpu_mem_allocated_bytes.record(total_bytes, &attrs);
+    }
+}
+
+pub fn record_gpu_util(pid: u32, busy_ns: u64, utilization_pct: f64) {
+    if let Some(m) = metrics() {
+        let attrs = [KeyValue::new("pid", pid as i64)];
+        let _ = busy_ns;
+        m.gpu_utilization_pct.record(utilization_pct as u64, &attrs);
+    }
+}
+
 /// Record active probe count
 /// Updates the global active probes map for ObservableGauge callback
 pub fn record_active_probe(probe_name: &str, count: u64) {
@@ -293,6 +1192,7 @@ pub fn record_active_probe(probe_name: &str, count: u64) {
 // === Network metric helpers ===
 
 pub fn record_tcp_connect_event(
+    pid: u32,
     daddr: &str,
     dport: u16,
     latency_ns: u64,
@@ -300,36 +1200,89 @@ pub fn record_tcp_connect_event(
     cgroup_id: u64,
 ) {
     if let Some(m) = metrics() {
-        let attrs = [
-            KeyValue::new("dest_addr", daddr.to_string()),
+        let guard = &cardinality::TCP_CONNECT_GUARD;
+        guard.observe(&[daddr, &cgroup_id.to_string()]);
+        let level = guard.level();
+
+        let mut attrs = vec![
             KeyValue::new("dest_port", dport as i64),
             KeyValue::new("success", success),
-            KeyValue::new("cgroup_id", cgroup_id as i64),
         ];
+        // Only enrich with a hostname when the raw dest_addr itself is still
+        // being reported -- once cardinality governance buckets it down to
+        // a /24, a per-host name would reintroduce the cardinality that
+        // bucketing was meant to shed.
+        let attached_addr = cardinality::governed_addr(level, "dest_addr", daddr);
+        if let Some((k, v)) = &attached_addr {
+            attrs.push(KeyValue::new(k.to_string(), v.clone()));
+            if v == daddr {
+                if let Some(hostname) = dns_cache::hostname_for(daddr) {
+                    attrs.push(KeyValue::new("dest_host", hostname));
+                }
+            }
+        }
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "cgroup_id", &cgroup_id.to_string()) {
+            attrs.push(KeyValue::new(k, v));
+        }
+
         m.tcp_connect_events.add(1, &attrs);
         m.tcp_connect_latency_ns.record(latency_ns, &attrs);
     }
+
+    // Prometheus `/metrics` export (see `crate::metrics`) labels by resolved
+    // pod/workload rather than cgroup_id directly, so operators scraping it
+    // don't have to cross-reference cgroup_id against the cluster by hand.
+    // Falls back to a cgroup_id-derived label when no pod has been resolved
+    // (or the resolver was never registered), mirroring the OTLP path's
+    // cardinality-governance fallback above.
+    let (namespace, workload) = POD_RESOLVER
+        .get()
+        .and_then(|resolver| resolver.resolve(pid, cgroup_id))
+        .map(|pod| (pod.namespace.clone(), pod.service_name().to_string()))
+        .unwrap_or_else(|| ("unknown".to_string(), format!("cgroup-{cgroup_id}")));
+
+    prom_metrics::record_connection(&namespace, &workload, daddr, dport, latency_ns);
 }
 
 pub fn record_tcp_retrans_event(daddr: &str, dport: u16, state: &str, cgroup_id: u64) {
     if let Some(m) = metrics() {
-        let attrs = [
-            KeyValue::new("dest_addr", daddr.to_string()),
+        let guard = &cardinality::TCP_RETRANS_GUARD;
+        guard.observe(&[daddr, &cgroup_id.to_string()]);
+        let level = guard.level();
+
+        let mut attrs = vec![
             KeyValue::new("dest_port", dport as i64),
             KeyValue::new("tcp_state", state.to_string()),
-            KeyValue::new("cgroup_id", cgroup_id as i64),
         ];
+        let attached_addr = cardinality::governed_addr(level, "dest_addr", daddr);
+        if let Some((k, v)) = &attached_addr {
+            attrs.push(KeyValue::new(k.to_string(), v.clone()));
+            if let Some(hostname) = dns_cache::hostname_for(daddr) {
+                attrs.push(KeyValue::new("dest_host", hostname));
+            }
+        }
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "cgroup_id", &cgroup_id.to_string()) {
+            attrs.push(KeyValue::new(k, v));
+        }
+
         m.tcp_retrans_events.add(1, &attrs);
     }
 }
 
 pub fn record_dns_query_event(query_name: &str, query_type: &str, latency_ns: u64, cgroup_id: u64) {
     if let Some(m) = metrics() {
-        let attrs = [
-            KeyValue::new("query_name", query_name.to_string()),
-            KeyValue::new("query_type", query_type.to_string()),
-            KeyValue::new("cgroup_id", cgroup_id as i64),
-        ];
+        let guard = &cardinality::DNS_GUARD;
+        guard.observe(&[query_name, &cgroup_id.to_string()]);
+        let level = guard.level();
+
+        let mut attrs = vec![KeyValue::new("query_type", query_type.to_string())];
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "query_name", query_name) {
+            attrs.push(KeyValue::new(k, v));
+        }
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "cgroup_id", &cgroup_id.to_string()) {
+            attrs.push(KeyValue::new(k, v));
+        }
+
         m.dns_query_events.add(1, &attrs);
         m.dns_query_latency_ns.record(latency_ns, &attrs);
     }
@@ -345,18 +1298,24 @@ pub fn record_vfs_event(
     cgroup_id: u64,
 ) {
     if let Some(m) = metrics() {
-        let attrs = [
-            KeyValue::new("operation", op_type.to_string()),
-            KeyValue::new("filename", filename.to_string()),
-            KeyValue::new("cgroup_id", cgroup_id as i64),
-        ];
-        
+        let guard = &cardinality::VFS_GUARD;
+        guard.observe(&[filename, &cgroup_id.to_string()]);
+        let level = guard.level();
+
+        let mut attrs = vec![KeyValue::new("operation", op_type.to_string())];
+        if let Some((k, v)) = cardinality::governed_filename(level, "filename", filename) {
+            attrs.push(KeyValue::new(k, v));
+        }
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "cgroup_id", &cgroup_id.to_string()) {
+            attrs.push(KeyValue::new(k, v));
+        }
+
         match op_type {
             "read" => m.vfs_read_events.add(1, &attrs),
             "write" => m.vfs_write_events.add(1, &attrs),
             _ => {}
         }
-        
+
         m.vfs_latency_ns.record(latency_ns, &attrs);
     }
 }
@@ -368,12 +1327,21 @@ pub fn record_file_access_event(
     cgroup_id: u64,
 ) {
     if let Some(m) = metrics() {
-        let attrs = [
-            KeyValue::new("filename", filename.to_string()),
-            KeyValue::new("flags", flags.to_string()),
-            KeyValue::new("process", comm.to_string()),
-            KeyValue::new("cgroup_id", cgroup_id as i64),
-        ];
+        let guard = &cardinality::FILE_ACCESS_GUARD;
+        guard.observe(&[filename, comm, &cgroup_id.to_string()]);
+        let level = guard.level();
+
+        let mut attrs = vec![KeyValue::new("flags", flags.to_string())];
+        if let Some((k, v)) = cardinality::governed_filename(level, "filename", filename) {
+            attrs.push(KeyValue::new(k, v));
+        }
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "process", comm) {
+            attrs.push(KeyValue::new(k, v));
+        }
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "cgroup_id", &cgroup_id.to_string()) {
+            attrs.push(KeyValue::new(k, v));
+        }
+
         m.file_access_events.add(1, &attrs);
     }
 }
@@ -382,11 +1350,18 @@ pub fn record_file_access_event(
 
 pub fn record_runqueue_latency(latency_ns: u64, cpu: u32, comm: &str, cgroup_id: u64) {
     if let Some(m) = metrics() {
-        let attrs = [
-            KeyValue::new("cpu", cpu as i64),
-            KeyValue::new("process", comm.to_string()),
-            KeyValue::new("cgroup_id", cgroup_id as i64),
-        ];
+        let guard = &cardinality::RUNQUEUE_GUARD;
+        guard.observe(&[comm, &cgroup_id.to_string()]);
+        let level = guard.level();
+
+        let mut attrs = vec![KeyValue::new("cpu", cpu as i64)];
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "process", comm) {
+            attrs.push(KeyValue::new(k, v));
+        }
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "cgroup_id", &cgroup_id.to_string()) {
+            attrs.push(KeyValue::new(k, v));
+        }
+
         m.runqueue_latency_ns.record(latency_ns, &attrs);
     }
 }
@@ -398,16 +1373,46 @@ pub fn record_offcpu_event(
     cgroup_id: u64,
 ) {
     if let Some(m) = metrics() {
-        let attrs = [
-            KeyValue::new("reason", reason.to_string()),
-            KeyValue::new("process", comm.to_string()),
-            KeyValue::new("cgroup_id", cgroup_id as i64),
-        ];
+        let guard = &cardinality::OFFCPU_GUARD;
+        guard.observe(&[comm, &cgroup_id.to_string()]);
+        let level = guard.level();
+
+        let mut attrs = vec![KeyValue::new("reason", reason.to_string())];
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "process", comm) {
+            attrs.push(KeyValue::new(k, v));
+        }
+        if let Some((k, v)) = cardinality::governed_passthrough(level, "cgroup_id", &cgroup_id.to_string()) {
+            attrs.push(KeyValue::new(k, v));
+        }
+
         m.offcpu_duration_ns.record(duration_ns, &attrs);
         m.context_switch_events.add(1, &attrs);
     }
 }
 
+/// Records one flush interval's worth of new calls landing in `bucket` for
+/// `probe`/`op_type`, as reported by `spawn_histogram_handler`. Since the
+/// eBPF-side array stores exclusive per-bucket counts but `le`-bucketed
+/// histograms are cumulative, `delta` is added to `bucket`'s series and
+/// every coarser bucket's series above it, reconstructing cumulative
+/// semantics at export time rather than paying for it in the hot eBPF path.
+pub fn record_histogram_bucket(probe: &str, op_type: &str, bucket: usize, delta: u64) {
+    if delta == 0 {
+        return;
+    }
+    if let Some(m) = metrics() {
+        for le_bucket in bucket..HIST_NUM_BUCKETS {
+            let le_ns = bucket_upper_bound_ns(le_bucket);
+            let attrs = [
+                KeyValue::new("probe", probe.to_string()),
+                KeyValue::new("operation", op_type.to_string()),
+                KeyValue::new("le", le_ns.to_string()),
+            ];
+            m.histogram_bucket_count.add(delta, &attrs);
+        }
+    }
+}
+
 /// Shutdown OpenTelemetry (graceful shutdown)
 /// Flushes pending metrics and shuts down the MeterProvider
 pub fn shutdown_metrics() {
@@ -462,4 +1467,23 @@ mod tests {
         assert_eq!(endpoint, Some("http://collector:4317".to_string()));
         std::env::remove_var("OTEL_EXPORTER_OTLP_ENDPOINT");
     }
+
+    #[test]
+    #[serial]
+    fn test_probes_ready_true_once_one_attaches() {
+        record_probe_attach_result("test_probes_ready_ok", true);
+        assert!(probes_ready());
+    }
+
+    #[test]
+    #[serial]
+    fn test_probe_attach_summary_counts_every_requested_probe() {
+        let (before_attached, before_requested) = probe_attach_summary();
+        record_probe_attach_result("test_probe_attach_summary_a", true);
+        record_probe_attach_result("test_probe_attach_summary_b", false);
+
+        let (attached, requested) = probe_attach_summary();
+        assert_eq!(attached, before_attached + 1);
+        assert_eq!(requested, before_requested + 2);
+    }
 }
@@ -1,5 +1,7 @@
+use std::net::TcpListener;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 use std::{env, fs};
 
 use anyhow::{Context, Result, bail};
@@ -70,6 +72,34 @@ enum Commands {
         #[arg(long, default_value = "dist")]
         output: String,
     },
+
+    /// Boot the probes inside QEMU VMs across a kernel matrix and assert
+    /// ringbuf events flow through on each one
+    IntegrationTest {
+        /// Kernel image to boot (bzImage/vmlinuz); repeat to test a matrix,
+        /// e.g. `--kernel images/6.1/vmlinuz --kernel images/6.4/vmlinuz`
+        #[arg(long = "kernel", required = true)]
+        kernels: Vec<PathBuf>,
+
+        /// Root filesystem image (qcow2) used as the backing disk for every
+        /// kernel; a fresh overlay is created per boot so kernels never share
+        /// disk state
+        #[arg(long)]
+        rootfs: PathBuf,
+
+        /// Guest architecture, selects the qemu-system-$ARCH binary
+        /// (default: host arch)
+        #[arg(long)]
+        arch: Option<String>,
+
+        /// SSH user configured in the rootfs image (e.g. via cloud-init)
+        #[arg(long, default_value = "root")]
+        ssh_user: String,
+
+        /// Seconds to wait for the guest's SSH server before giving up
+        #[arg(long, default_value_t = 60)]
+        boot_timeout: u64,
+    },
 }
 
 fn main() -> Result<()> {
@@ -94,6 +124,15 @@ fn main() -> Result<()> {
         Commands::Package { target, output } => {
             package(target.as_deref(), &output)?;
         }
+        Commands::IntegrationTest {
+            kernels,
+            rootfs,
+            arch,
+            ssh_user,
+            boot_timeout,
+        } => {
+            integration_test(&kernels, &rootfs, arch.as_deref(), &ssh_user, boot_timeout)?;
+        }
     }
 
     Ok(())
@@ -377,24 +416,209 @@ echo "   Start with: sudo systemctl start honeybeepf"
     );
     
     fs::write(package_dir.join("install-service.sh"), service_script)?;
-    
+
     // Create tarball
     let tarball = output_path.join(format!("{}.tar.gz", package_name));
-    
+
     let status = Command::new("tar")
         .current_dir(&output_path)
         .args(&["-czf", &tarball.to_string_lossy(), &package_name])
         .status()
         .context("Failed to create tarball")?;
-    
+
     if !status.success() {
         bail!("Failed to create tarball");
     }
-    
+
     // Cleanup directory
     fs::remove_dir_all(&package_dir)?;
-    
+
     println!("✅ Package created: {}", tarball.display());
-    
+
+    Ok(())
+}
+
+/// Boots each kernel in `kernels` in its own QEMU VM, copies the release
+/// binary in over scp, runs it over ssh, and reports pass/fail per kernel.
+/// Tracepoint layouts, BTF availability, and helper support all vary by
+/// kernel, so this is the only thing that actually confirms the probes
+/// attach on a given target rather than just that the workspace builds.
+fn integration_test(
+    kernels: &[PathBuf],
+    rootfs: &Path,
+    arch: Option<&str>,
+    ssh_user: &str,
+    boot_timeout: u64,
+) -> Result<()> {
+    // Build once; every kernel boots the same binary.
+    build(true, None)?;
+
+    let root = project_root();
+    let binary_path = root.join("target").join("release").join("honeybeepf");
+    if !binary_path.exists() {
+        bail!("Binary not found at: {}", binary_path.display());
+    }
+
+    let arch = arch.unwrap_or(env::consts::ARCH);
+    let qemu_bin = format!("qemu-system-{}", arch);
+    let boot_timeout = Duration::from_secs(boot_timeout);
+
+    println!("🧪 Running integration tests across {} kernel(s)...", kernels.len());
+
+    let mut results = Vec::new();
+    for kernel in kernels {
+        let label = kernel.display().to_string();
+        println!("🐧 Booting kernel: {}", label);
+
+        match run_kernel_test(&qemu_bin, kernel, rootfs, ssh_user, boot_timeout, &binary_path) {
+            Ok(()) => {
+                println!("✅ {} passed", label);
+                results.push((label, true));
+            }
+            Err(e) => {
+                println!("❌ {} failed: {}", label, e);
+                results.push((label, false));
+            }
+        }
+    }
+
+    println!("\n📋 Integration test summary:");
+    for (label, ok) in &results {
+        println!("   {} {}", if *ok { "✅" } else { "❌" }, label);
+    }
+
+    let failed = results.iter().filter(|(_, ok)| !ok).count();
+    if failed > 0 {
+        bail!("{} of {} kernel(s) failed", failed, results.len());
+    }
+
     Ok(())
 }
+
+/// Runs one kernel through the full boot/scp/ssh/teardown cycle. The VM is
+/// always torn down on the way out, whether the test passed or failed.
+fn run_kernel_test(
+    qemu_bin: &str,
+    kernel: &Path,
+    rootfs: &Path,
+    ssh_user: &str,
+    boot_timeout: Duration,
+    binary_path: &Path,
+) -> Result<()> {
+    // A fresh overlay per kernel keeps `rootfs` itself pristine, so each
+    // kernel in the matrix boots from the same unmodified image.
+    let overlay = env::temp_dir().join(format!(
+        "honeybeepf-xtask-{}-{}.qcow2",
+        std::process::id(),
+        kernel.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+    ));
+
+    let status = Command::new("qemu-img")
+        .args(["create", "-f", "qcow2", "-F", "qcow2", "-b"])
+        .arg(rootfs)
+        .arg(&overlay)
+        .status()
+        .context("Failed to run qemu-img create")?;
+    if !status.success() {
+        bail!("qemu-img create failed for overlay backed by {}", rootfs.display());
+    }
+
+    let ssh_port = free_tcp_port()?;
+
+    let mut qemu = Command::new(qemu_bin)
+        .args(["-m", "2048", "-smp", "2", "-nographic", "-no-reboot", "-enable-kvm"])
+        .arg("-kernel")
+        .arg(kernel)
+        .arg("-drive")
+        .arg(format!("file={},if=virtio,format=qcow2", overlay.display()))
+        .arg("-append")
+        .arg("console=ttyS0 root=/dev/vda rw")
+        .arg("-netdev")
+        .arg(format!("user,id=net0,hostfwd=tcp::{}-:22", ssh_port))
+        .arg("-device")
+        .arg("virtio-net-pci,netdev=net0")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to spawn qemu")?;
+
+    let result = run_probe_test_over_ssh(ssh_port, ssh_user, boot_timeout, binary_path);
+
+    let _ = qemu.kill();
+    let _ = qemu.wait();
+    let _ = fs::remove_file(&overlay);
+
+    result
+}
+
+/// Waits for the guest's SSH server, copies the binary in, and runs it with
+/// a self-test flag that attaches every builtin probe and asserts at least
+/// one event flows through each ringbuf before exiting.
+fn run_probe_test_over_ssh(
+    ssh_port: u16,
+    ssh_user: &str,
+    boot_timeout: Duration,
+    binary_path: &Path,
+) -> Result<()> {
+    wait_for_ssh(ssh_port, ssh_user, boot_timeout)?;
+
+    let remote_binary = "/tmp/honeybeepf-under-test";
+    let status = Command::new("scp")
+        .args(ssh_opts())
+        .arg("-P")
+        .arg(ssh_port.to_string())
+        .arg(binary_path)
+        .arg(format!("{}@127.0.0.1:{}", ssh_user, remote_binary))
+        .status()
+        .context("Failed to scp test binary into guest")?;
+    if !status.success() {
+        bail!("scp into guest failed");
+    }
+
+    let status = Command::new("ssh")
+        .args(ssh_opts())
+        .arg("-p")
+        .arg(ssh_port.to_string())
+        .arg(format!("{}@127.0.0.1", ssh_user))
+        .arg(format!("chmod +x {remote_binary} && sudo {remote_binary} --self-test"))
+        .status()
+        .context("Failed to run test binary over ssh")?;
+    if !status.success() {
+        bail!("probe self-test exited non-zero inside guest");
+    }
+
+    Ok(())
+}
+
+fn wait_for_ssh(ssh_port: u16, ssh_user: &str, timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let status = Command::new("ssh")
+            .args(ssh_opts())
+            .args(["-o", "ConnectTimeout=2", "-o", "BatchMode=yes"])
+            .arg("-p")
+            .arg(ssh_port.to_string())
+            .arg(format!("{}@127.0.0.1", ssh_user))
+            .arg("true")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+
+        if matches!(status, Ok(s) if s.success()) {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            bail!("Timed out waiting for SSH on port {}", ssh_port);
+        }
+        std::thread::sleep(Duration::from_secs(2));
+    }
+}
+
+fn ssh_opts() -> [&'static str; 2] {
+    ["-o", "StrictHostKeyChecking=no"]
+}
+
+fn free_tcp_port() -> Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0").context("Failed to bind ephemeral port")?;
+    Ok(listener.local_addr()?.port())
+}